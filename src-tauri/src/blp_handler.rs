@@ -1,7 +1,24 @@
-use blp::core::image::ImageBlp;
+use blp::core::image::{ImageBlp, MAX_MIPS};
+use blp::core::types::{TextureType, Version};
+use byteorder::{LittleEndian, WriteBytesExt};
+use image::imageops::FilterType;
 use image::{ImageFormat, RgbaImage};
 use std::io::Cursor;
 
+// 编码使用的压缩类型，与 get_blp_info 返回的 format 对应
+pub const COMPRESSION_JPEG: u32 = 1;
+pub const COMPRESSION_PALETTED: u32 = 2;
+pub const COMPRESSION_DXT: u32 = 3;
+
+// JPEG 编码质量
+const JPEG_QUALITY: u8 = 90;
+// BLP1 文件头大小: magic + 6 个 u32 字段 + 16 个偏移 + 16 个长度
+const BLP1_HEADER_SIZE: usize = 4 + 6 * 4 + MAX_MIPS * 8;
+// 调色板大小: 256 个 BGRA 颜色
+const PALETTE_SIZE: usize = 256 * 4;
+// BLP2 文件头大小: magic + type + 4 个 u8 字段 + 宽高 + 16 个偏移 + 16 个长度
+const BLP2_HEADER_SIZE: usize = 4 + 4 + 4 + 8 + MAX_MIPS * 8;
+
 #[derive(serde::Serialize, Debug, Clone)]
 pub struct BlpImageData {
     pub width: u32,
@@ -15,6 +32,18 @@ pub struct BlpInfo {
     pub height: u32,
     pub mipmap_count: usize,
     pub format: String,
+    pub has_alpha: bool,
+    pub alpha_bits: u8,
+    pub mipmap_dimensions: Vec<(u32, u32)>, // 文件中实际存储的各级尺寸，从原图开始逐级减半
+}
+
+/// 某一级 mipmap 在文件中存储的原始（未解码）数据
+#[derive(serde::Serialize, Debug)]
+pub struct BlpMipmapRaw {
+    pub format: String, // "jpeg"、"palette"、"dxt1"/"dxt3"/"dxt5" 或 "bgra"
+    pub width: u32,
+    pub height: u32,
+    pub data: Vec<u8>,
 }
 
 /// 解码 BLP 文件为 ImageData（RGBA 格式）
@@ -23,14 +52,21 @@ pub fn decode_blp(blp_data: &[u8]) -> Result<BlpImageData, String> {
     let mut blp = ImageBlp::from_buf(blp_data)
         .map_err(|e| format!("BLP 解析失败: {:?}", e))?;
     
-    // 解码第一层 mipmap（最高分辨率）
-    blp.decode(blp_data, &[true])
-        .map_err(|e| format!("BLP 解码失败: {:?}", e))?;
-    
+    // 解码第一个实际存有数据的层级（通常就是最高分辨率的第 0 级）
+    let level = blp
+        .mipmaps
+        .iter()
+        .position(|mip| mip.length > 0)
+        .ok_or_else(|| format!("BLP 没有可解码的图像数据（{}）", describe_blp(&blp)))?;
+    let mut decode_flags = vec![false; level + 1];
+    decode_flags[level] = true;
+    blp.decode(blp_data, &decode_flags)
+        .map_err(|e| format!("BLP 解码失败: {:?}（{}）", e, describe_blp(&blp)))?;
+
     // 获取 RGBA 图像
-    let img = blp.mipmaps[0].image
+    let img = blp.mipmaps[level].image
         .take()
-        .ok_or_else(|| "没有可用的图像数据".to_string())?;
+        .ok_or_else(|| format!("BLP 没有可解码的图像数据（{}）", describe_blp(&blp)))?;
     
     let (width, height) = img.dimensions();
     let raw_data = img.into_raw();
@@ -42,27 +78,69 @@ pub fn decode_blp(blp_data: &[u8]) -> Result<BlpImageData, String> {
     })
 }
 
+/// 解码 BLP 并按 WebGL 纹理的需要处理：`flip_y` 使原点位于左下角，
+/// `premultiply_alpha` 把颜色预乘 alpha
+pub fn decode_blp_ex(
+    blp_data: &[u8],
+    flip_y: bool,
+    premultiply_alpha: bool,
+) -> Result<BlpImageData, String> {
+    let mut image = decode_blp(blp_data)?;
+    if flip_y {
+        let row = image.width as usize * 4;
+        let rows: Vec<&[u8]> = image.data.chunks_exact(row).rev().collect();
+        image.data = rows.concat();
+    }
+    if premultiply_alpha {
+        for pixel in image.data.chunks_exact_mut(4) {
+            let alpha = pixel[3] as u32;
+            for channel in &mut pixel[..3] {
+                *channel = ((*channel as u32 * alpha + 127) / 255) as u8;
+            }
+        }
+    }
+    Ok(image)
+}
+
 /// 获取 BLP 文件的 mipmap 信息
 pub fn get_blp_info(blp_data: &[u8]) -> Result<BlpInfo, String> {
     let blp = ImageBlp::from_buf(blp_data)
         .map_err(|e| format!("BLP 解析失败: {:?}", e))?;
     
-    // blp crate 使用 compression 字段而不是 encoding
-    let format = match blp.compression {
-        1 => "JPEG",
-        2 => "Paletted",
-        3 => "DXT",
-        _ => "Unknown",
+    // BLP1 只区分 JPEG 与调色板；BLP2 的 compression 字段: 1 调色板, 2 DXT, 3 未压缩 BGRA，
+    // DXT 变体由 alpha_type 区分: 0 DXT1, 1 DXT3, 7 DXT5
+    let format = match blp.texture_type {
+        TextureType::JPEG => "JPEG",
+        TextureType::DIRECT if blp.version < Version::BLP2 => "Paletted",
+        TextureType::DIRECT => match (blp.compression, blp.alpha_type) {
+            (1, _) => "Paletted",
+            (2, 7) => "DXT5",
+            (2, 1) => "DXT3",
+            (2, _) => "DXT1",
+            (3, _) => "BGRA",
+            _ => "Unknown",
+        },
     };
     
     // 计算 mipmap 数量（从 mipmaps 数组长度获取）
     let mipmap_count = blp.mipmaps.len();
-    
+
+    let stored_levels = blp.mipmaps.iter().filter(|mip| mip.length > 0).count().max(1);
+    let mipmap_dimensions = std::iter::successors(Some((blp.width, blp.height)), |&(w, h)| {
+        Some(((w / 2).max(1), (h / 2).max(1)))
+    })
+    .take(stored_levels)
+    .collect();
+
     Ok(BlpInfo {
         width: blp.width,
         height: blp.height,
         mipmap_count,
         format: format.to_string(),
+        // BLP1 头部的 alpha 位数为 u32，BLP2 为 u8，blp crate 统一读成 u32
+        has_alpha: blp.alpha_bits > 0,
+        alpha_bits: blp.alpha_bits.min(u8::MAX as u32) as u8,
+        mipmap_dimensions,
     })
 }
 
@@ -75,17 +153,81 @@ pub fn decode_blp_to_png_base64(blp_data: &[u8]) -> Result<String, String> {
         .ok_or_else(|| "无法创建图像".to_string())?;
     
     // 转换为 PNG
-    let mut png_buffer = Vec::new();
-    let mut cursor = Cursor::new(&mut png_buffer);
-    
-    img.write_to(&mut cursor, ImageFormat::Png)
-        .map_err(|e| format!("PNG 编码失败: {}", e))?;
+    let png_buffer = encode_png(&img)?;
     
     // 编码为 base64
     let base64_str = base64::Engine::encode(&base64::engine::general_purpose::STANDARD, &png_buffer);
     Ok(format!("data:image/png;base64,{}", base64_str))
 }
 
+/// 将 RGBA 图像编码为 PNG 字节
+pub fn encode_png(img: &RgbaImage) -> Result<Vec<u8>, String> {
+    let mut png_buffer = Vec::new();
+    img.write_to(&mut Cursor::new(&mut png_buffer), ImageFormat::Png)
+        .map_err(|e| format!("PNG 编码失败: {}", e))?;
+    Ok(png_buffer)
+}
+
+/// 解码 BLP 最高一级 mipmap 并编码为指定格式（png、jpeg、tga、webp、bmp），返回文件字节
+pub fn convert_blp(blp_data: &[u8], format: &str) -> Result<Vec<u8>, String> {
+    let image_format = match format.to_ascii_lowercase().as_str() {
+        "png" => ImageFormat::Png,
+        "jpeg" | "jpg" => ImageFormat::Jpeg,
+        "tga" => ImageFormat::Tga,
+        "webp" => ImageFormat::WebP,
+        "bmp" => ImageFormat::Bmp,
+        _ => return Err(format!("不支持的图像格式: {}", format)),
+    };
+
+    let image_data = decode_blp(blp_data)?;
+    let img = RgbaImage::from_raw(image_data.width, image_data.height, image_data.data)
+        .ok_or_else(|| "无法创建图像".to_string())?;
+    // JPEG 不支持 alpha 通道，先丢弃
+    let img = if image_format == ImageFormat::Jpeg {
+        image::DynamicImage::ImageRgba8(img).into_rgb8().into()
+    } else {
+        image::DynamicImage::ImageRgba8(img)
+    };
+
+    let mut buffer = Vec::new();
+    img.write_to(&mut Cursor::new(&mut buffer), image_format)
+        .map_err(|e| format!("{} 编码失败: {}", format, e))?;
+    Ok(buffer)
+}
+
+/// 将纹理按 `rows`×`cols` 网格切分，返回第 `index` 格（行优先）的 PNG 数据
+///
+/// 用于预览纵向/网格堆叠的序列帧纹理
+pub fn extract_texture_frame(blp_data: &[u8], rows: u32, cols: u32, index: u32) -> Result<Vec<u8>, String> {
+    if rows == 0 || cols == 0 {
+        return Err("行数和列数必须大于 0".to_string());
+    }
+    let frame_count = rows
+        .checked_mul(cols)
+        .ok_or_else(|| format!("网格 {}x{} 过大", rows, cols))?;
+    if index >= frame_count {
+        return Err(format!("帧索引 {} 超出范围 (共 {} 帧)", index, frame_count));
+    }
+
+    let image_data = decode_blp(blp_data)?;
+    let img = RgbaImage::from_raw(image_data.width, image_data.height, image_data.data)
+        .ok_or_else(|| "无法创建图像".to_string())?;
+
+    let cell_width = img.width() / cols;
+    let cell_height = img.height() / rows;
+    if cell_width == 0 || cell_height == 0 {
+        return Err(format!(
+            "纹理尺寸 {}x{} 不足以切分为 {}x{} 网格",
+            img.width(), img.height(), rows, cols
+        ));
+    }
+
+    let x = (index % cols) * cell_width;
+    let y = (index / cols) * cell_height;
+    let frame = image::imageops::crop_imm(&img, x, y, cell_width, cell_height).to_image();
+    encode_png(&frame)
+}
+
 /// 解码 BLP 指定 mipmap 层级
 pub fn decode_blp_mipmap(blp_data: &[u8], mipmap_level: usize) -> Result<BlpImageData, String> {
     let mut blp = ImageBlp::from_buf(blp_data)
@@ -118,11 +260,817 @@ pub fn decode_blp_mipmap(blp_data: &[u8], mipmap_level: usize) -> Result<BlpImag
     })
 }
 
+/// 一次解析并解码所有 mipmap 层级，按从大到小排列；没有图像数据的层级直接跳过
+pub fn decode_blp_all_mipmaps(blp_data: &[u8]) -> Result<Vec<BlpImageData>, String> {
+    let mut blp = ImageBlp::from_buf(blp_data)
+        .map_err(|e| format!("BLP 解析失败: {:?}", e))?;
+
+    let decode_flags = vec![true; blp.mipmaps.len()];
+    blp.decode(blp_data, &decode_flags)
+        .map_err(|e| format!("BLP 解码失败: {:?}", e))?;
+
+    Ok(blp
+        .mipmaps
+        .iter_mut()
+        .filter_map(|mipmap| mipmap.image.take())
+        .map(|img| {
+            let (width, height) = img.dimensions();
+            BlpImageData {
+                width,
+                height,
+                data: img.into_raw(),
+            }
+        })
+        .collect())
+}
+
+/// 生成长边不超过 `max_edge` 的缩略图
+///
+/// 只解码长边仍不小于 `max_edge` 的最小一级 mipmap（都不满足时用原图），再缩放到目标尺寸。
+pub fn decode_blp_thumbnail(blp_data: &[u8], max_edge: u32) -> Result<BlpImageData, String> {
+    if max_edge == 0 {
+        return Err("缩略图尺寸必须大于 0".to_string());
+    }
+    let blp = ImageBlp::from_buf(blp_data)
+        .map_err(|e| format!("BLP 解析失败: {:?}", e))?;
+
+    let level = blp
+        .mipmaps
+        .iter()
+        .rposition(|mip| mip.length > 0 && mip.width.max(mip.height) >= max_edge)
+        .unwrap_or(0);
+    let image_data = decode_blp_mipmap(blp_data, level)?;
+
+    let (width, height) = (image_data.width, image_data.height);
+    if width.max(height) <= max_edge {
+        return Ok(image_data);
+    }
+    let img = RgbaImage::from_raw(width, height, image_data.data)
+        .ok_or_else(|| "无法创建图像".to_string())?;
+    let scale = max_edge as f64 / width.max(height) as f64;
+    let target_width = ((width as f64 * scale).round() as u32).max(1);
+    let target_height = ((height as f64 * scale).round() as u32).max(1);
+    let thumbnail = image::imageops::resize(&img, target_width, target_height, FilterType::Triangle);
+
+    Ok(BlpImageData {
+        width: target_width,
+        height: target_height,
+        data: thumbnail.into_raw(),
+    })
+}
+
+/// 估算纹理上传到 GPU 后占用的显存（RGBA8，含完整 mipmap 链），只读取文件头
+pub fn estimate_gpu_memory(blp_data: &[u8]) -> Result<u64, String> {
+    let blp = ImageBlp::from_buf(blp_data)
+        .map_err(|e| format!("BLP 解析失败: {:?}", e))?;
+
+    let (mut width, mut height) = (blp.width.max(1) as u64, blp.height.max(1) as u64);
+    let mut total = width * height * 4;
+    if blp.has_mipmaps != 0 {
+        while width > 1 || height > 1 {
+            width = (width / 2).max(1);
+            height = (height / 2).max(1);
+            total += width * height * 4;
+        }
+    }
+
+    Ok(total)
+}
+
+// 判断遮罩纹理时允许的通道抖动（JPEG 压缩会引入少量噪点）
+const MASK_CHANNEL_TOLERANCE: u8 = 8;
+
+/// 判断纹理是否为遮罩：RGB 通道恒定（如全黑/全白），变化全部体现在 alpha 通道
+pub fn is_mask_texture(blp_data: &[u8]) -> Result<bool, String> {
+    let image_data = decode_blp(blp_data)?;
+    if image_data.data.is_empty() {
+        return Ok(false);
+    }
+
+    let mut min = [u8::MAX; 4];
+    let mut max = [u8::MIN; 4];
+    for pixel in image_data.data.chunks_exact(4) {
+        for channel in 0..4 {
+            min[channel] = min[channel].min(pixel[channel]);
+            max[channel] = max[channel].max(pixel[channel]);
+        }
+    }
+
+    let rgb_constant = (0..3).all(|c| max[c] - min[c] <= MASK_CHANNEL_TOLERANCE);
+    let alpha_varies = max[3] - min[3] > MASK_CHANNEL_TOLERANCE;
+    Ok(rgb_constant && alpha_varies)
+}
+
+// Transparent 模式的 alpha 测试阈值（与游戏一致，约 0.75）
+const ALPHA_TEST_THRESHOLD: u8 = 191;
+
+/// 按材质层混合模式把纹理合成到纯色背景上，返回 PNG 数据
+///
+/// 混合模式编号与 MDX 材质层一致: 0 None, 1 Transparent, 2 Blend, 3 Additive,
+/// 4 AddAlpha, 5 Modulate, 6 Modulate2x。
+pub fn preview_layer_blend(blp_data: &[u8], filter_mode: u32, backdrop: [u8; 3]) -> Result<Vec<u8>, String> {
+    if filter_mode > 6 {
+        return Err(format!("未知的混合模式: {}", filter_mode));
+    }
+
+    let image_data = decode_blp(blp_data)?;
+    let mut img = RgbaImage::from_raw(image_data.width, image_data.height, image_data.data)
+        .ok_or_else(|| "无法创建图像".to_string())?;
+
+    for pixel in img.pixels_mut() {
+        let [r, g, b, a] = pixel.0;
+        let alpha = a as f32 / 255.0;
+        let blend = |src: u8, dst: u8| -> u8 {
+            let (s, d) = (src as f32, dst as f32);
+            let value = match filter_mode {
+                0 => s,
+                1 if a >= ALPHA_TEST_THRESHOLD => s,
+                1 => d,
+                2 => s * alpha + d * (1.0 - alpha),
+                3 => s + d,
+                4 => s * alpha + d,
+                5 => s * d / 255.0,
+                _ => 2.0 * s * d / 255.0,
+            };
+            value.round().clamp(0.0, 255.0) as u8
+        };
+        pixel.0 = [blend(r, backdrop[0]), blend(g, backdrop[1]), blend(b, backdrop[2]), 255];
+    }
+
+    encode_png(&img)
+}
+
+/// 读取第 `level` 级 mipmap 的原始存储数据，不做 RGBA 解码
+///
+/// JPEG 格式返回的只是该级的数据块，不含各级共用的 JPEG 头部；
+/// 调色板格式返回索引与 alpha 字节，不含调色板。
+pub fn get_blp_mipmap_raw(blp_data: &[u8], level: usize) -> Result<BlpMipmapRaw, String> {
+    if !blp_data.starts_with(b"BLP") {
+        return Err("不是 BLP 文件".to_string());
+    }
+    let blp = ImageBlp::from_buf(blp_data)
+        .map_err(|e| format!("BLP 解析失败: {:?}", e))?;
+
+    let mip = blp
+        .mipmaps
+        .get(level)
+        .filter(|mip| mip.length > 0)
+        .ok_or_else(|| format!("mipmap 级别 {} 不存在", level))?;
+    let data = blp_data
+        .get(mip.offset..mip.offset + mip.length)
+        .ok_or_else(|| format!("mipmap 级别 {} 超出文件范围", level))?;
+
+    let format = match blp.texture_type {
+        TextureType::JPEG => "jpeg",
+        // BLP2 的 alpha_type 区分 DXT 变体: 0 DXT1, 1 DXT3, 7 DXT5
+        TextureType::DIRECT if blp.version >= Version::BLP2 && blp.compression == 2 => match blp.alpha_type {
+            7 => "dxt5",
+            1 => "dxt3",
+            _ => "dxt1",
+        },
+        TextureType::DIRECT if blp.version >= Version::BLP2 && blp.compression == 3 => "bgra",
+        TextureType::DIRECT => "palette",
+    };
+
+    Ok(BlpMipmapRaw {
+        format: format.to_string(),
+        width: mip.width,
+        height: mip.height,
+        data: data.to_vec(),
+    })
+}
+
+/// 把 JPEG BLP 第 `level` 级 mipmap 还原为完整的 JPEG 文件
+///
+/// BLP 把各级共用的 JPEG 头部单独存放，拼上该级数据块即得到可直接保存的 .jpg。
+/// 注意 BLP 中的 JPEG 按 BGRA 通道存储，普通查看器显示时红蓝会互换。
+pub fn extract_blp_jpeg(blp_data: &[u8], level: usize) -> Result<Vec<u8>, String> {
+    if !blp_data.starts_with(b"BLP") {
+        return Err("不是 BLP 文件".to_string());
+    }
+    let blp = ImageBlp::from_buf(blp_data)
+        .map_err(|e| format!("BLP 解析失败: {:?}", e))?;
+    if blp.texture_type != TextureType::JPEG {
+        return Err("不是 JPEG 压缩的 BLP".to_string());
+    }
+
+    let header = blp_data
+        .get(blp.header_offset..blp.header_offset + blp.header_length)
+        .ok_or_else(|| "JPEG 头部超出文件范围".to_string())?;
+    let mip = blp
+        .mipmaps
+        .get(level)
+        .filter(|mip| mip.length > 0)
+        .ok_or_else(|| format!("mipmap 级别 {} 不存在", level))?;
+    let data = blp_data
+        .get(mip.offset..mip.offset + mip.length)
+        .ok_or_else(|| format!("mipmap 级别 {} 超出文件范围", level))?;
+
+    let mut jpeg = Vec::with_capacity(header.len() + data.len());
+    jpeg.extend_from_slice(header);
+    jpeg.extend_from_slice(data);
+    Ok(jpeg)
+}
+
+/// 读取调色板 BLP 的 256 色调色板，按 RGBA 返回
+///
+/// 文件中按 BGRA 存储且第 4 字节不使用（透明度单独按像素存储），返回时 alpha 固定为 255。
+pub fn get_blp_palette(blp_data: &[u8]) -> Result<Vec<[u8; 4]>, String> {
+    if !blp_data.starts_with(b"BLP") {
+        return Err("不是 BLP 文件".to_string());
+    }
+    let blp = ImageBlp::from_buf(blp_data)
+        .map_err(|e| format!("BLP 解析失败: {:?}", e))?;
+
+    // BLP2 的 compression 字段: 1 调色板, 2 DXT, 3 未压缩 BGRA
+    let is_paletted = blp.texture_type == TextureType::DIRECT
+        && (blp.version < Version::BLP2 || blp.compression == 1);
+    if !is_paletted {
+        return Err(format!("不是调色板压缩的 BLP（{}）", describe_blp(&blp)));
+    }
+
+    // 调色板紧跟在文件头之后，BLP2 的文件头比 BLP1 少 extra 与 has_mipmaps 两个字段
+    let offset = if blp.version >= Version::BLP2 { BLP2_HEADER_SIZE } else { BLP1_HEADER_SIZE };
+    let palette = blp_data
+        .get(offset..offset + PALETTE_SIZE)
+        .ok_or_else(|| "调色板超出文件范围".to_string())?;
+    Ok(palette.chunks_exact(4).map(|bgra| [bgra[2], bgra[1], bgra[0], 255]).collect())
+}
+
+// 图集最大边长（像素）
+const MAX_ATLAS_SIZE: u32 = 8192;
+
+/// 把多个图标按方形网格拼成一张图集，每格 `cell`×`cell`
+///
+/// 返回图集 PNG 与每个图标的 UV 矩形 [u0, v0, u1, v1]（顺序与输入一致）。
+pub fn build_icon_atlas(blps: &[Vec<u8>], cell: u32) -> Result<(Vec<u8>, Vec<[f32; 4]>), String> {
+    if blps.is_empty() {
+        return Err("没有要合并的图标".to_string());
+    }
+    if cell == 0 {
+        return Err("格子尺寸必须大于 0".to_string());
+    }
+
+    let cols = (blps.len() as f64).sqrt().ceil() as u32;
+    let size = cols
+        .checked_mul(cell)
+        .filter(|&size| size <= MAX_ATLAS_SIZE)
+        .ok_or_else(|| format!("图集尺寸超过 {} 像素", MAX_ATLAS_SIZE))?;
+
+    let mut atlas = RgbaImage::new(size, size);
+    let mut rects = Vec::with_capacity(blps.len());
+    for (index, blp_data) in blps.iter().enumerate() {
+        let image_data = decode_blp(blp_data).map_err(|e| format!("图标 {}: {}", index, e))?;
+        let icon = RgbaImage::from_raw(image_data.width, image_data.height, image_data.data)
+            .ok_or_else(|| "无法创建图像".to_string())?;
+        let icon = if icon.dimensions() == (cell, cell) {
+            icon
+        } else {
+            image::imageops::resize(&icon, cell, cell, FilterType::Triangle)
+        };
+
+        let (x, y) = ((index as u32 % cols) * cell, (index as u32 / cols) * cell);
+        image::imageops::replace(&mut atlas, &icon, x as i64, y as i64);
+        rects.push([
+            x as f32 / size as f32,
+            y as f32 / size as f32,
+            (x + cell) as f32 / size as f32,
+            (y + cell) as f32 / size as f32,
+        ]);
+    }
+
+    Ok((encode_png(&atlas)?, rects))
+}
+
+/// 按本模块的压缩类型编号识别 BLP 的编码方式
+fn source_compression(blp: &ImageBlp) -> u32 {
+    match blp.texture_type {
+        TextureType::JPEG => COMPRESSION_JPEG,
+        // BLP2 的 compression 字段: 1 调色板, 2 DXT, 3 未压缩 BGRA
+        TextureType::DIRECT if blp.version >= Version::BLP2 && blp.compression == 2 => COMPRESSION_DXT,
+        TextureType::DIRECT => COMPRESSION_PALETTED,
+    }
+}
+
+/// 错误信息中用到的尺寸与压缩方式描述
+fn describe_blp(blp: &ImageBlp) -> String {
+    let compression = match source_compression(blp) {
+        COMPRESSION_JPEG => "JPEG",
+        COMPRESSION_DXT => "DXT",
+        _ => "调色板",
+    };
+    format!("{}x{}，{} 压缩", blp.width, blp.height, compression)
+}
+
+/// 生成 mipmap 链（逐级减半直至 1x1），不生成时只包含原图
+fn build_mip_chain(img: &RgbaImage, generate_mipmaps: bool) -> Vec<RgbaImage> {
+    let mut levels = vec![img.clone()];
+    if generate_mipmaps {
+        let (mut width, mut height) = img.dimensions();
+        while (width > 1 || height > 1) && levels.len() < MAX_MIPS {
+            width = (width / 2).max(1);
+            height = (height / 2).max(1);
+            let previous = levels.last().unwrap();
+            levels.push(image::imageops::resize(previous, width, height, FilterType::Triangle));
+        }
+    }
+    levels
+}
+
+/// 编码为调色板 BLP1（256 色，有透明像素时附带 8 位 alpha）
+fn encode_paletted(levels: &[RgbaImage]) -> Vec<u8> {
+    let base = &levels[0];
+    let has_alpha = base.pixels().any(|p| p.0[3] < 255);
+    let alpha_bits: u32 = if has_alpha { 8 } else { 0 };
+
+    // 调色板只量化 RGB，alpha 单独存储
+    let opaque: Vec<u8> = base.pixels().flat_map(|p| [p.0[0], p.0[1], p.0[2], 255]).collect();
+    let quantizer = color_quant::NeuQuant::new(10, 256, &opaque);
+
+    let mut mip_data = Vec::with_capacity(levels.len());
+    for level in levels {
+        let mut data: Vec<u8> = level
+            .pixels()
+            .map(|p| quantizer.index_of(&[p.0[0], p.0[1], p.0[2], 255]) as u8)
+            .collect();
+        if has_alpha {
+            data.extend(level.pixels().map(|p| p.0[3]));
+        }
+        mip_data.push(data);
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"BLP1");
+    out.write_u32::<LittleEndian>(1).unwrap(); // 0 JPEG, 1 调色板
+    out.write_u32::<LittleEndian>(alpha_bits).unwrap();
+    out.write_u32::<LittleEndian>(base.width()).unwrap();
+    out.write_u32::<LittleEndian>(base.height()).unwrap();
+    out.write_u32::<LittleEndian>(if has_alpha { 4 } else { 5 }).unwrap();
+    out.write_u32::<LittleEndian>((levels.len() > 1) as u32).unwrap();
+
+    let mut offset = BLP1_HEADER_SIZE + PALETTE_SIZE;
+    for i in 0..MAX_MIPS {
+        let length = mip_data.get(i).map_or(0, |d| d.len());
+        out.write_u32::<LittleEndian>(if length > 0 { offset as u32 } else { 0 }).unwrap();
+        offset += length;
+    }
+    for i in 0..MAX_MIPS {
+        out.write_u32::<LittleEndian>(mip_data.get(i).map_or(0, |d| d.len() as u32)).unwrap();
+    }
+
+    for color in quantizer.color_map_rgba().chunks_exact(4) {
+        out.extend_from_slice(&[color[2], color[1], color[0], 0]);
+    }
+    for data in &mip_data {
+        out.extend_from_slice(data);
+    }
+    out
+}
+
+/// 把 RGB 压缩为 RGB565
+fn to_rgb565(c: [u8; 3]) -> u16 {
+    ((c[0] as u16 >> 3) << 11) | ((c[1] as u16 >> 2) << 5) | (c[2] as u16 >> 3)
+}
+
+/// RGB565 还原为 8 位 RGB
+fn from_rgb565(c: u16) -> [i32; 3] {
+    let (r, g, b) = ((c >> 11) & 0x1F, (c >> 5) & 0x3F, c & 0x1F);
+    [(r << 3 | r >> 2) as i32, (g << 2 | g >> 4) as i32, (b << 3 | b >> 2) as i32]
+}
+
+/// 取出 (x, y) 处的 4x4 像素块，超出边界的部分重复边缘像素
+fn block_pixels(img: &RgbaImage, x: u32, y: u32) -> [[u8; 4]; 16] {
+    std::array::from_fn(|i| {
+        let px = (x + i as u32 % 4).min(img.width() - 1);
+        let py = (y + i as u32 / 4).min(img.height() - 1);
+        img.get_pixel(px, py).0
+    })
+}
+
+/// DXT1 颜色块：取包围盒两端作为端点，四色模式（color0 > color1）
+fn encode_color_block(pixels: &[[u8; 4]; 16], out: &mut Vec<u8>) {
+    let mut min = [255u8; 3];
+    let mut max = [0u8; 3];
+    for p in pixels {
+        for k in 0..3 {
+            min[k] = min[k].min(p[k]);
+            max[k] = max[k].max(p[k]);
+        }
+    }
+    let (mut c0, mut c1) = (to_rgb565(max), to_rgb565(min));
+    if c0 < c1 {
+        std::mem::swap(&mut c0, &mut c1);
+    }
+
+    let mut indices = 0u32;
+    if c0 != c1 {
+        let (e0, e1) = (from_rgb565(c0), from_rgb565(c1));
+        let palette: [[i32; 3]; 4] = [
+            e0,
+            e1,
+            std::array::from_fn(|k| (2 * e0[k] + e1[k]) / 3),
+            std::array::from_fn(|k| (e0[k] + 2 * e1[k]) / 3),
+        ];
+        for (i, p) in pixels.iter().enumerate() {
+            let distance = |c: &[i32; 3]| (0..3).map(|k| (c[k] - p[k] as i32).pow(2)).sum::<i32>();
+            let best = (0..4).min_by_key(|&j| distance(&palette[j])).unwrap() as u32;
+            indices |= best << (2 * i);
+        }
+    }
+    out.extend_from_slice(&c0.to_le_bytes());
+    out.extend_from_slice(&c1.to_le_bytes());
+    out.extend_from_slice(&indices.to_le_bytes());
+}
+
+/// DXT5 alpha 块：8 级插值（alpha0 > alpha1），每像素 3 位索引
+fn encode_alpha_block(pixels: &[[u8; 4]; 16], out: &mut Vec<u8>) {
+    let a0 = pixels.iter().map(|p| p[3]).max().unwrap();
+    let a1 = pixels.iter().map(|p| p[3]).min().unwrap();
+    let palette: [i32; 8] = std::array::from_fn(|j| {
+        let (a0, a1) = (a0 as i32, a1 as i32);
+        match j {
+            0 => a0,
+            1 => a1,
+            _ => ((8 - j as i32) * a0 + (j as i32 - 1) * a1) / 7,
+        }
+    });
+
+    let mut indices = 0u64;
+    if a0 != a1 {
+        for (i, p) in pixels.iter().enumerate() {
+            let best = (0..8).min_by_key(|&j| (palette[j] - p[3] as i32).abs()).unwrap() as u64;
+            indices |= best << (3 * i);
+        }
+    }
+    out.extend_from_slice(&[a0, a1]);
+    out.extend_from_slice(&indices.to_le_bytes()[..6]);
+}
+
+/// 编码为 DXT BLP2（BLP1 不支持 DXT）；不透明图像用 DXT1，有透明像素时用 DXT5
+fn encode_dxt(levels: &[RgbaImage]) -> Vec<u8> {
+    let base = &levels[0];
+    let has_alpha = base.pixels().any(|p| p.0[3] < 255);
+
+    let mip_data: Vec<Vec<u8>> = levels
+        .iter()
+        .map(|level| {
+            let mut data = Vec::new();
+            for y in (0..level.height()).step_by(4) {
+                for x in (0..level.width()).step_by(4) {
+                    let pixels = block_pixels(level, x, y);
+                    if has_alpha {
+                        encode_alpha_block(&pixels, &mut data);
+                    }
+                    encode_color_block(&pixels, &mut data);
+                }
+            }
+            data
+        })
+        .collect();
+
+    let mut out = Vec::new();
+    out.extend_from_slice(b"BLP2");
+    out.write_u32::<LittleEndian>(1).unwrap(); // 0 JPEG, 1 直接存储
+    out.push(2); // compression: 2 DXT
+    out.push(if has_alpha { 8 } else { 0 }); // alpha 位数
+    out.push(if has_alpha { 7 } else { 0 }); // alpha_type: 0 DXT1, 7 DXT5
+    out.push((levels.len() > 1) as u8);
+    out.write_u32::<LittleEndian>(base.width()).unwrap();
+    out.write_u32::<LittleEndian>(base.height()).unwrap();
+
+    let mut offset = BLP2_HEADER_SIZE + PALETTE_SIZE;
+    for i in 0..MAX_MIPS {
+        let length = mip_data.get(i).map_or(0, |d| d.len());
+        out.write_u32::<LittleEndian>(if length > 0 { offset as u32 } else { 0 }).unwrap();
+        offset += length;
+    }
+    for i in 0..MAX_MIPS {
+        out.write_u32::<LittleEndian>(mip_data.get(i).map_or(0, |d| d.len() as u32)).unwrap();
+    }
+
+    // BLP2 文件头后总是跟着调色板区域，DXT 不使用
+    out.extend(std::iter::repeat_n(0u8, PALETTE_SIZE));
+    for data in &mip_data {
+        out.extend_from_slice(data);
+    }
+    out
+}
+
+/// 编码为 JPEG BLP（由 blp crate 完成）
+fn encode_jpeg(levels: Vec<RgbaImage>) -> Result<Vec<u8>, String> {
+    let base = &levels[0];
+    let mut blp = ImageBlp::from_rgba(base.as_raw(), base.width(), base.height())
+        .map_err(|e| format!("BLP 编码失败: {:?}", e))?;
+    if (blp.width, blp.height) != base.dimensions() {
+        return Err(format!(
+            "JPEG 编码要求尺寸为 2 的幂，当前为 {}x{}",
+            base.width(),
+            base.height()
+        ));
+    }
+
+    let visible: Vec<bool> = (0..MAX_MIPS).map(|i| i < levels.len()).collect();
+    for (mipmap, level) in blp.mipmaps.iter_mut().zip(levels) {
+        mipmap.image = Some(level);
+    }
+
+    let encoded = blp
+        .encode_blp(JPEG_QUALITY, &visible)
+        .map_err(|e| format!("BLP 编码失败: {:?}", e))?;
+    Ok(encoded.bytes)
+}
+
+/// 将 RGBA 图像编码为 BLP
+///
+/// `compression` 取值见 `COMPRESSION_*` 常量；JPEG 与调色板输出 BLP1，DXT 输出 BLP2。
+pub fn encode_blp(img: &RgbaImage, compression: u32, generate_mipmaps: bool) -> Result<Vec<u8>, String> {
+    if img.width() == 0 || img.height() == 0 {
+        return Err("图像尺寸不能为 0".to_string());
+    }
+
+    let levels = build_mip_chain(img, generate_mipmaps);
+    match compression {
+        COMPRESSION_JPEG => encode_jpeg(levels),
+        COMPRESSION_PALETTED => Ok(encode_paletted(&levels)),
+        COMPRESSION_DXT => Ok(encode_dxt(&levels)),
+        _ => Err(format!("未知的压缩类型: {}", compression)),
+    }
+}
+
+/// 解码 -> 按原压缩方式重新编码 -> 再解码，返回平均每通道差值（0-255）
+///
+/// 用于衡量编码器的保真度，0 表示无损。
+pub fn verify_blp_roundtrip(blp_data: &[u8]) -> Result<f32, String> {
+    let blp = ImageBlp::from_buf(blp_data)
+        .map_err(|e| format!("BLP 解析失败: {:?}", e))?;
+    let compression = source_compression(&blp);
+
+    let original = decode_blp(blp_data)?;
+    let img = RgbaImage::from_raw(original.width, original.height, original.data)
+        .ok_or_else(|| "无法创建图像".to_string())?;
+
+    let encoded = encode_blp(&img, compression, blp.has_mipmaps != 0)?;
+    let decoded = decode_blp(&encoded)?;
+    if (decoded.width, decoded.height) != img.dimensions() {
+        return Err(format!(
+            "重新编码后尺寸不一致: {}x{} -> {}x{}",
+            img.width(),
+            img.height(),
+            decoded.width,
+            decoded.height
+        ));
+    }
+
+    let total: u64 = img
+        .as_raw()
+        .iter()
+        .zip(&decoded.data)
+        .map(|(&a, &b)| a.abs_diff(b) as u64)
+        .sum();
+    Ok(total as f32 / img.as_raw().len() as f32)
+}
+
 #[cfg(test)]
 mod tests {
+    use super::*;
+
     #[test]
-    fn test_decode_blp() {
-        // 这里可以添加测试代码
-        // 需要一个有效的 BLP 文件数据
+    fn test_paletted_roundtrip() {
+        let img = RgbaImage::from_fn(16, 16, |x, y| {
+            let colors = [[255, 0, 0, 255], [0, 255, 0, 128], [0, 0, 255, 0], [255, 255, 255, 255]];
+            image::Rgba(colors[((x / 8) + (y / 8) * 2) as usize])
+        });
+
+        let encoded = encode_blp(&img, COMPRESSION_PALETTED, true).unwrap();
+        let decoded = decode_blp(&encoded).unwrap();
+        assert_eq!((decoded.width, decoded.height), (16, 16));
+        let info = get_blp_info(&encoded).unwrap();
+        assert_eq!(info.mipmap_count, MAX_MIPS);
+        assert_eq!(info.format, "Paletted");
+        assert!(info.has_alpha);
+        assert_eq!(info.alpha_bits, 8);
+        assert_eq!(info.mipmap_dimensions, vec![(16, 16), (8, 8), (4, 4), (2, 2), (1, 1)]);
+
+        let difference = verify_blp_roundtrip(&encoded).unwrap();
+        assert!(difference < 1.0, "difference {}", difference);
+
+        // 调色板索引 + 8 位 alpha，每像素 2 字节
+        let raw = get_blp_mipmap_raw(&encoded, 1).unwrap();
+        assert_eq!(raw.format, "palette");
+        assert_eq!(raw.data.len(), 8 * 8 * 2);
+
+        let sizes: Vec<u32> = decode_blp_all_mipmaps(&encoded)
+            .unwrap()
+            .iter()
+            .map(|level| level.width)
+            .collect();
+        assert_eq!(sizes, vec![16, 8, 4, 2, 1]);
+        let single = encode_blp(&img, COMPRESSION_PALETTED, false).unwrap();
+        assert_eq!(decode_blp_all_mipmaps(&single).unwrap().len(), 1);
+
+        // 12 像素的缩略图取 16x16 一级再缩小；大于原图时直接返回原图
+        let thumbnail = decode_blp_thumbnail(&encoded, 12).unwrap();
+        assert_eq!((thumbnail.width, thumbnail.height), (12, 12));
+        assert_eq!(thumbnail.data.len(), 12 * 12 * 4);
+        let thumbnail = decode_blp_thumbnail(&encoded, 4).unwrap();
+        assert_eq!((thumbnail.width, thumbnail.height), (4, 4));
+        assert_eq!(decode_blp_thumbnail(&encoded, 64).unwrap().width, 16);
+    }
+
+    #[test]
+    fn test_decode_ex_flip_and_premultiply() {
+        let img = RgbaImage::from_fn(2, 2, |_, y| {
+            image::Rgba(if y == 0 { [200, 100, 0, 128] } else { [0, 255, 0, 255] })
+        });
+        let encoded = encode_blp(&img, COMPRESSION_PALETTED, false).unwrap();
+
+        let plain = decode_blp_ex(&encoded, false, false).unwrap();
+        assert_eq!(plain.data, decode_blp(&encoded).unwrap().data);
+
+        // 翻转后第一行是原来的底行；颜色按 alpha 预乘
+        let decoded = decode_blp_ex(&encoded, true, true).unwrap();
+        assert_eq!(decoded.data[..8], plain.data[8..]);
+        let [r, g, b, a] = [0, 1, 2, 3].map(|i| plain.data[i] as u32);
+        let expected = [r, g, b].map(|c| ((c * a + 127) / 255) as u8);
+        assert_eq!(decoded.data[12..15], expected);
+        assert_eq!(decoded.data[15], a as u8);
+    }
+
+    #[test]
+    fn test_extract_texture_frame() {
+        // 2x2 网格，每格 4x4 像素，颜色各不相同
+        let colors = [[255, 0, 0, 255], [0, 255, 0, 255], [0, 0, 255, 255], [255, 255, 255, 255]];
+        let img = RgbaImage::from_fn(8, 8, |x, y| image::Rgba(colors[((x / 4) + (y / 4) * 2) as usize]));
+        let encoded = encode_blp(&img, COMPRESSION_PALETTED, false).unwrap();
+
+        let png = extract_texture_frame(&encoded, 2, 2, 3).unwrap();
+        let frame = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(frame.dimensions(), (4, 4));
+        assert_eq!(frame.get_pixel(0, 0).0, colors[3]);
+
+        assert!(extract_texture_frame(&encoded, 2, 2, 4).is_err());
+        assert!(extract_texture_frame(&encoded, u32::MAX, 2, 0).is_err());
+    }
+
+    #[test]
+    fn test_is_mask_texture() {
+        // RGB 恒为白色，只有 alpha 变化
+        let mask = RgbaImage::from_fn(4, 4, |x, _| image::Rgba([255, 255, 255, (x * 80) as u8]));
+        let encoded = encode_blp(&mask, COMPRESSION_PALETTED, false).unwrap();
+        assert!(is_mask_texture(&encoded).unwrap());
+
+        // 颜色变化的普通纹理
+        let colored = RgbaImage::from_fn(4, 4, |x, _| image::Rgba([(x * 80) as u8, 0, 0, (x * 80) as u8]));
+        let encoded = encode_blp(&colored, COMPRESSION_PALETTED, false).unwrap();
+        assert!(!is_mask_texture(&encoded).unwrap());
+
+        // alpha 不变时不是遮罩
+        let opaque = RgbaImage::from_pixel(4, 4, image::Rgba([255, 255, 255, 255]));
+        let encoded = encode_blp(&opaque, COMPRESSION_PALETTED, false).unwrap();
+        assert!(!is_mask_texture(&encoded).unwrap());
+    }
+
+    #[test]
+    fn test_estimate_gpu_memory() {
+        let img = RgbaImage::from_pixel(16, 8, image::Rgba([0, 0, 0, 255]));
+        // 16x8 + 8x4 + 4x2 + 2x1 + 1x1，每像素 4 字节
+        let mipmapped = encode_blp(&img, COMPRESSION_PALETTED, true).unwrap();
+        assert_eq!(estimate_gpu_memory(&mipmapped).unwrap(), (128 + 32 + 8 + 2 + 1) * 4);
+        let single = encode_blp(&img, COMPRESSION_PALETTED, false).unwrap();
+        assert_eq!(estimate_gpu_memory(&single).unwrap(), 128 * 4);
+    }
+
+    #[test]
+    fn test_preview_layer_blend() {
+        let img = RgbaImage::from_pixel(16, 16, image::Rgba([200, 100, 0, 128]));
+        let encoded = encode_blp(&img, COMPRESSION_PALETTED, false).unwrap();
+        let backdrop = [0, 0, 255];
+        let pixel = |filter_mode| {
+            let png = preview_layer_blend(&encoded, filter_mode, backdrop).unwrap();
+            image::load_from_memory(&png).unwrap().to_rgba8().get_pixel(0, 0).0
+        };
+
+        assert_eq!(pixel(0), [200, 100, 0, 255]);
+        // alpha 128 低于 alpha 测试阈值，只剩背景
+        assert_eq!(pixel(1), [0, 0, 255, 255]);
+        assert_eq!(pixel(2), [100, 50, 127, 255]);
+        assert_eq!(pixel(3), [200, 100, 255, 255]);
+        assert_eq!(pixel(5), [0, 0, 0, 255]);
+        assert!(preview_layer_blend(&encoded, 7, backdrop).is_err());
+    }
+
+    #[test]
+    fn test_decode_without_base_level() {
+        let img = RgbaImage::from_pixel(8, 8, image::Rgba([0, 255, 0, 255]));
+        let single = encode_blp(&img, COMPRESSION_PALETTED, false).unwrap();
+        assert_eq!(decode_blp(&single).unwrap().data.len(), 8 * 8 * 4);
+
+        let mut blp = encode_blp(&img, COMPRESSION_PALETTED, true).unwrap();
+        // 长度表位于 magic、6 个 u32 字段与偏移表之后
+        let lengths = 4 + 6 * 4 + MAX_MIPS * 4;
+
+        // 第 0 级缺失时退回第一个有数据的层级
+        blp[lengths..lengths + 4].fill(0);
+        let decoded = decode_blp(&blp).unwrap();
+        assert_eq!((decoded.width, decoded.height), (4, 4));
+
+        // 完全没有数据时给出尺寸与压缩方式
+        blp[lengths..lengths + MAX_MIPS * 4].fill(0);
+        let error = decode_blp(&blp).unwrap_err();
+        assert!(error.contains("8x8") && error.contains("调色板"), "{}", error);
+    }
+
+    #[test]
+    fn test_convert_blp() {
+        let img = RgbaImage::from_pixel(4, 4, image::Rgba([200, 100, 50, 128]));
+        let blp = encode_blp(&img, COMPRESSION_PALETTED, false).unwrap();
+
+        for (format, expected) in [
+            ("png", ImageFormat::Png),
+            ("JPG", ImageFormat::Jpeg),
+            ("tga", ImageFormat::Tga),
+            ("webp", ImageFormat::WebP),
+            ("bmp", ImageFormat::Bmp),
+        ] {
+            let bytes = convert_blp(&blp, format).unwrap();
+            let decoded = image::load_from_memory_with_format(&bytes, expected).unwrap();
+            assert_eq!((decoded.width(), decoded.height()), (4, 4), "{}", format);
+        }
+        assert!(convert_blp(&blp, "gif").is_err());
+    }
+
+    #[test]
+    fn test_get_palette() {
+        let red = RgbaImage::from_pixel(4, 4, image::Rgba([255, 0, 0, 255]));
+        let paletted = encode_blp(&red, COMPRESSION_PALETTED, false).unwrap();
+        let palette = get_blp_palette(&paletted).unwrap();
+        assert_eq!(palette.len(), 256);
+        let index = get_blp_mipmap_raw(&paletted, 0).unwrap().data[0];
+        assert_eq!(palette[index as usize], [255, 0, 0, 255]);
+
+        let dxt = encode_blp(&red, COMPRESSION_DXT, false).unwrap();
+        assert!(get_blp_palette(&dxt).is_err());
+    }
+
+    #[test]
+    fn test_dxt_encoding() {
+        // 不透明纯红 -> DXT1，每个 4x4 块 8 字节，端点为 RGB565 红色
+        let red = RgbaImage::from_pixel(8, 8, image::Rgba([255, 0, 0, 255]));
+        let encoded = encode_blp(&red, COMPRESSION_DXT, true).unwrap();
+        assert!(encoded.starts_with(b"BLP2"));
+        let info = get_blp_info(&encoded).unwrap();
+        assert!(!info.has_alpha);
+        assert_eq!(info.format, "DXT1");
+        assert_eq!(info.mipmap_dimensions.len(), 4);
+        let raw = get_blp_mipmap_raw(&encoded, 0).unwrap();
+        assert_eq!(raw.format, "dxt1");
+        assert_eq!(raw.data.len(), 4 * 8);
+        assert_eq!(&raw.data[..4], &[0x00, 0xF8, 0x00, 0xF8]);
+        // 2x2 与 1x1 级别也占一个完整块
+        assert_eq!(get_blp_mipmap_raw(&encoded, 3).unwrap().data.len(), 8);
+
+        // 左右两半 alpha 不同 -> DXT5，每块 16 字节
+        let half = RgbaImage::from_fn(4, 4, |x, _| {
+            image::Rgba([0, 0, 255, if x < 2 { 0 } else { 255 }])
+        });
+        let encoded = encode_blp(&half, COMPRESSION_DXT, false).unwrap();
+        let raw = get_blp_mipmap_raw(&encoded, 0).unwrap();
+        assert_eq!(raw.format, "dxt5");
+        assert_eq!(get_blp_info(&encoded).unwrap().format, "DXT5");
+        assert_eq!(raw.data.len(), 16);
+        assert_eq!(&raw.data[..2], &[255, 0]);
+        // 第一行像素的 alpha 索引: 1, 1, 0, 0
+        assert_eq!(u16::from_le_bytes([raw.data[2], raw.data[3]]) & 0o7777, 0o0011);
+    }
+
+    #[test]
+    fn test_extract_jpeg() {
+        let mut jpeg = Vec::new();
+        let img = image::RgbImage::from_pixel(4, 4, image::Rgb([10, 200, 30]));
+        image::codecs::jpeg::JpegEncoder::new(&mut jpeg).encode_image(&img).unwrap();
+
+        // 手工拼一个单级 JPEG BLP1：前 20 字节作为共用头部，其余作为 mipmap 数据
+        let (header, tail) = jpeg.split_at(20);
+        let data_offset = BLP1_HEADER_SIZE + 4 + header.len();
+        let mut blp = Vec::new();
+        blp.extend_from_slice(b"BLP1");
+        for value in [0, 0, 4, 4, 5, 0] {
+            blp.write_u32::<LittleEndian>(value).unwrap();
+        }
+        for i in 0..MAX_MIPS {
+            blp.write_u32::<LittleEndian>(if i == 0 { data_offset as u32 } else { 0 }).unwrap();
+        }
+        for i in 0..MAX_MIPS {
+            blp.write_u32::<LittleEndian>(if i == 0 { tail.len() as u32 } else { 0 }).unwrap();
+        }
+        blp.write_u32::<LittleEndian>(header.len() as u32).unwrap();
+        blp.extend_from_slice(header);
+        blp.extend_from_slice(tail);
+
+        assert_eq!(extract_blp_jpeg(&blp, 0).unwrap(), jpeg);
+        assert!(extract_blp_jpeg(&blp, 1).is_err());
+
+        let paletted = encode_blp(&RgbaImage::new(4, 4), COMPRESSION_PALETTED, false).unwrap();
+        assert!(extract_blp_jpeg(&paletted, 0).is_err());
     }
 }
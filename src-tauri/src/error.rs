@@ -0,0 +1,78 @@
+// 命令返回给前端的结构化错误
+
+use serde::Serialize;
+
+/// 命令错误，序列化为 `{ kind, message }`，前端按 `kind` 区分错误类型
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", content = "message")]
+pub enum AppError {
+    /// 本地文件读写失败
+    Io(String),
+    /// 无法打开 MPQ 档案
+    MpqOpen(String),
+    /// 档案已打开，但读取其中的文件失败
+    MpqRead(String),
+    /// BLP 解析或解码失败
+    BlpDecode(String),
+    /// BLP 编码失败
+    BlpEncode(String),
+    /// MDX/MDL 模型解析或处理失败
+    MdxParse(String),
+    /// 地图数据（w3i、w3e 等）解析失败
+    MapParse(String),
+    /// 无法启动外部进程
+    ProcessSpawn(String),
+    /// 用户取消了操作（如 UAC 权限提升）
+    #[cfg_attr(not(windows), allow(dead_code))] // 目前只有 Windows 的权限提升会产生
+    UserCancelled(String),
+    /// 当前平台或输入不支持该操作
+    Unsupported(String),
+    /// 其他错误
+    Other(String),
+}
+
+impl AppError {
+    pub fn message(&self) -> &str {
+        match self {
+            AppError::Io(message)
+            | AppError::MpqOpen(message)
+            | AppError::MpqRead(message)
+            | AppError::BlpDecode(message)
+            | AppError::BlpEncode(message)
+            | AppError::MdxParse(message)
+            | AppError::MapParse(message)
+            | AppError::ProcessSpawn(message)
+            | AppError::UserCancelled(message)
+            | AppError::Unsupported(message)
+            | AppError::Other(message) => message,
+        }
+    }
+}
+
+impl std::fmt::Display for AppError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str(self.message())
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        AppError::Io(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialize_kind_and_message() {
+        let json = serde_json::to_string(&AppError::UserCancelled("用户取消".to_string())).unwrap();
+        assert_eq!(json, r#"{"kind":"UserCancelled","message":"用户取消"}"#);
+
+        let io: AppError = std::io::Error::new(std::io::ErrorKind::NotFound, "missing").into();
+        assert!(matches!(io, AppError::Io(ref message) if message == "missing"));
+    }
+}
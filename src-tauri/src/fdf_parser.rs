@@ -0,0 +1,498 @@
+// FDF (Frame Definition File) 界面定义解析器
+// 格式: `Frame "TYPE" "Name" INHERITS WITHCHILDREN "Base" { ... }`，块内每行一条以逗号结尾的属性，
+// 块可以嵌套（Frame/Texture/String/Layer 等），`//` 开始行注释
+
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+// 以关键字本身作为类型的子块；其余块写作 `Frame "TYPE" "Name"`
+const BLOCK_KEYWORDS: &[&str] = &["Texture", "String", "Layer", "StringList"];
+const INDENT: &str = "    ";
+
+/// 锚点（SetPoint）
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Anchor {
+    pub point: String, // 如 TOPLEFT
+    pub relative_to: String,
+    pub relative_point: String,
+    pub x: f32,
+    pub y: f32,
+}
+
+/// 属性值：带引号的字符串或不带引号的标识符/数字，写回时保持原样
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(tag = "kind", content = "value", rename_all = "lowercase")]
+pub enum FdfValue {
+    Word(String),
+    #[serde(rename = "string")]
+    Str(String),
+}
+
+impl FdfValue {
+    pub fn as_str(&self) -> &str {
+        match self {
+            FdfValue::Word(value) | FdfValue::Str(value) => value,
+        }
+    }
+}
+
+/// 未单独建模的属性（如 Text、File、BackdropBackground）
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct FdfProperty {
+    pub name: String,
+    pub values: Vec<FdfValue>,
+}
+
+/// 界面框体定义
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct FrameDef {
+    pub frame_type: String, // 如 BACKDROP、TEXT；Texture/String 等子块为块关键字本身
+    pub name: String,       // 匿名块为空
+    pub inherits: Option<String>,
+    pub inherit_children: bool, // INHERITS WITHCHILDREN
+    pub width: Option<f32>,
+    pub height: Option<f32>,
+    pub anchors: Vec<Anchor>,
+    pub properties: Vec<FdfProperty>,
+    pub children: Vec<FrameDef>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Str(String),
+    Comma,
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+}
+
+/// 解析 FDF 文件，返回顶层块（顶层的 IncludeFile 等属性被忽略）
+pub fn parse_fdf(data: &[u8]) -> Result<Vec<FrameDef>, String> {
+    let text = String::from_utf8_lossy(data);
+    let tokens = tokenize(&text)?;
+    let mut parser = FdfParser { tokens, pos: 0 };
+
+    let mut frames = Vec::new();
+    while parser.pos < parser.tokens.len() {
+        if let Statement::Block(frame) = parser.parse_statement()? {
+            frames.push(frame);
+        }
+    }
+    Ok(frames)
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            '\n' => {
+                line += 1;
+                chars.next();
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '/' => {
+                chars.next();
+                if chars.peek() != Some(&'/') {
+                    return Err(format!("Unexpected '/' on line {}", line));
+                }
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\n') | None => {
+                            return Err(format!("Unterminated string on line {}", line))
+                        }
+                        Some(c) => value.push(c),
+                    }
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Str(value),
+                    line,
+                });
+            }
+            ',' | '{' | '}' => {
+                chars.next();
+                let kind = match c {
+                    ',' => TokenKind::Comma,
+                    '{' => TokenKind::Open,
+                    _ => TokenKind::Close,
+                };
+                tokens.push(Token { kind, line });
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, ',' | '{' | '}' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Word(word),
+                    line,
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+enum Statement {
+    Property(FdfProperty),
+    Block(FrameDef),
+}
+
+struct FdfParser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl FdfParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    /// 解析一条属性或一个块
+    ///
+    /// 属性的参数以逗号分隔，行尾的逗号结束属性；遇到 `{` 则为块。
+    fn parse_statement(&mut self) -> Result<Statement, String> {
+        let keyword = self.tokens[self.pos].clone();
+        self.pos += 1;
+        let TokenKind::Word(name) = keyword.kind else {
+            return Err(format!("Expected keyword on line {}", keyword.line));
+        };
+
+        let mut args = Vec::new();
+        let mut last_line = keyword.line;
+        while let Some(token) = self.peek().cloned() {
+            match token.kind {
+                TokenKind::Open => {
+                    self.pos += 1;
+                    return Ok(Statement::Block(self.parse_block(&name, args, token.line)?));
+                }
+                TokenKind::Close => break,
+                TokenKind::Comma => {
+                    self.pos += 1;
+                    if self.peek().is_none_or(|next| next.line != token.line) {
+                        break;
+                    }
+                }
+                _ if token.line != last_line => break,
+                TokenKind::Word(value) => {
+                    self.pos += 1;
+                    last_line = token.line;
+                    args.push(FdfValue::Word(value));
+                }
+                TokenKind::Str(value) => {
+                    self.pos += 1;
+                    last_line = token.line;
+                    args.push(FdfValue::Str(value));
+                }
+            }
+        }
+
+        Ok(Statement::Property(FdfProperty { name, values: args }))
+    }
+
+    fn parse_block(
+        &mut self,
+        keyword: &str,
+        args: Vec<FdfValue>,
+        line: usize,
+    ) -> Result<FrameDef, String> {
+        let mut frame = FrameDef::default();
+        let mut args = args.into_iter().peekable();
+
+        // Frame "TYPE" "Name"；其余块（Texture "Name" 等）以关键字作为类型
+        if keyword.eq_ignore_ascii_case("Frame") {
+            let Some(FdfValue::Str(frame_type)) = args.next() else {
+                return Err(format!("Frame without type on line {}", line));
+            };
+            frame.frame_type = frame_type;
+        } else {
+            frame.frame_type = keyword.to_string();
+        }
+        if let Some(FdfValue::Str(_)) = args.peek() {
+            if let Some(FdfValue::Str(name)) = args.next() {
+                frame.name = name;
+            }
+        }
+        if let Some(FdfValue::Word(word)) = args.next() {
+            if !word.eq_ignore_ascii_case("INHERITS") {
+                return Err(format!("Unexpected '{}' on line {}", word, line));
+            }
+            if let Some(FdfValue::Word(word)) = args.peek() {
+                frame.inherit_children = word.eq_ignore_ascii_case("WITHCHILDREN");
+                args.next();
+            }
+            let Some(FdfValue::Str(base)) = args.next() else {
+                return Err(format!("INHERITS without base frame on line {}", line));
+            };
+            frame.inherits = Some(base);
+        }
+
+        loop {
+            let Some(token) = self.peek() else {
+                return Err(format!("Unclosed block opened on line {}", line));
+            };
+            if token.kind == TokenKind::Close {
+                self.pos += 1;
+                return Ok(frame);
+            }
+            let statement_line = token.line;
+
+            match self.parse_statement()? {
+                Statement::Block(child) => frame.children.push(child),
+                Statement::Property(property) => {
+                    apply_property(&mut frame, property, statement_line)?
+                }
+            }
+        }
+    }
+}
+
+fn parse_number(value: Option<&FdfValue>, line: usize) -> Result<f32, String> {
+    let value = value
+        .ok_or_else(|| format!("Missing number on line {}", line))?
+        .as_str();
+    value
+        .trim_end_matches('f')
+        .parse()
+        .map_err(|_| format!("Invalid number '{}' on line {}", value, line))
+}
+
+fn apply_property(frame: &mut FrameDef, property: FdfProperty, line: usize) -> Result<(), String> {
+    let values = &property.values;
+    match property.name.as_str() {
+        "Width" => frame.width = Some(parse_number(values.first(), line)?),
+        "Height" => frame.height = Some(parse_number(values.first(), line)?),
+        "SetPoint" => {
+            if values.len() < 5 {
+                return Err(format!("SetPoint needs 5 arguments on line {}", line));
+            }
+            frame.anchors.push(Anchor {
+                point: values[0].as_str().to_string(),
+                relative_to: values[1].as_str().to_string(),
+                relative_point: values[2].as_str().to_string(),
+                x: parse_number(values.get(3), line)?,
+                y: parse_number(values.get(4), line)?,
+            });
+        }
+        _ => frame.properties.push(property),
+    }
+    Ok(())
+}
+
+/// 解析 TOC 文件，按顺序返回其中列出的 FDF 路径（跳过空行与 `//` 注释）
+pub fn parse_toc(data: &[u8]) -> Result<Vec<String>, String> {
+    let text = String::from_utf8_lossy(data);
+    Ok(text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with("//"))
+        .map(str::to_string)
+        .collect())
+}
+
+/// 把框体定义树写回 FDF 文本（4 空格缩进，顶层块之间空一行）
+pub fn write_fdf(frames: &[FrameDef]) -> Result<String, String> {
+    let mut out = String::new();
+    for (index, frame) in frames.iter().enumerate() {
+        if index > 0 {
+            out.push('\n');
+        }
+        write_frame(&mut out, frame, 0)?;
+    }
+    Ok(out)
+}
+
+fn write_frame(out: &mut String, frame: &FrameDef, depth: usize) -> Result<(), String> {
+    let indent = INDENT.repeat(depth);
+    let inner = INDENT.repeat(depth + 1);
+
+    if BLOCK_KEYWORDS.contains(&frame.frame_type.as_str()) {
+        out.push_str(&indent);
+        out.push_str(&frame.frame_type);
+        if !frame.name.is_empty() {
+            write!(out, " {}", quote(&frame.name)?).unwrap();
+        }
+    } else {
+        write!(
+            out,
+            "{}Frame {} {}",
+            indent,
+            quote(&frame.frame_type)?,
+            quote(&frame.name)?
+        )
+        .unwrap();
+    }
+    if let Some(base) = &frame.inherits {
+        let children = if frame.inherit_children {
+            " WITHCHILDREN"
+        } else {
+            ""
+        };
+        write!(out, " INHERITS{} {}", children, quote(base)?).unwrap();
+    }
+    out.push_str(" {\n");
+
+    if let Some(width) = frame.width {
+        writeln!(out, "{}Width {},", inner, width).unwrap();
+    }
+    if let Some(height) = frame.height {
+        writeln!(out, "{}Height {},", inner, height).unwrap();
+    }
+    for anchor in &frame.anchors {
+        writeln!(
+            out,
+            "{}SetPoint {}, {}, {}, {}, {},",
+            inner,
+            anchor.point,
+            quote(&anchor.relative_to)?,
+            anchor.relative_point,
+            anchor.x,
+            anchor.y
+        )
+        .unwrap();
+    }
+    for property in &frame.properties {
+        out.push_str(&inner);
+        out.push_str(&property.name);
+        for (index, value) in property.values.iter().enumerate() {
+            out.push_str(if index == 0 { " " } else { ", " });
+            match value {
+                FdfValue::Word(word) => out.push_str(word),
+                FdfValue::Str(text) => out.push_str(&quote(text)?),
+            }
+        }
+        out.push_str(",\n");
+    }
+    for child in &frame.children {
+        write_frame(out, child, depth + 1)?;
+    }
+
+    writeln!(out, "{}}}", indent).unwrap();
+    Ok(())
+}
+
+/// FDF 字符串不支持转义，含引号或换行的值无法写出
+fn quote(value: &str) -> Result<String, String> {
+    if value.contains(['"', '\n']) {
+        return Err(format!(
+            "FDF strings cannot contain quotes or newlines: {:?}",
+            value
+        ));
+    }
+    Ok(format!("\"{}\"", value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_nested_frames() {
+        let fdf = br#"
+IncludeFile "UI\FrameDef\UI\EscMenuTemplates.fdf",
+
+// Custom panel
+Frame "BACKDROP" "MyPanel" INHERITS WITHCHILDREN "EscMenuBackdrop"
+{
+    Width 0.3,
+    Height 0.2f,
+    SetPoint TOPLEFT, "ConsoleUI", TOPLEFT, 0.01, -0.02,
+    BackdropBackground "Textures\panel.blp",
+
+    Frame "TEXT" "MyPanelTitle" {
+        Text "Hello",
+        SetAllPoints,
+    }
+    Texture {
+        File "icon.blp",
+    }
+}
+"#;
+        let frames = parse_fdf(fdf).unwrap();
+        assert_eq!(frames.len(), 1);
+        let panel = &frames[0];
+        assert_eq!(
+            (panel.frame_type.as_str(), panel.name.as_str()),
+            ("BACKDROP", "MyPanel")
+        );
+        assert_eq!(panel.inherits.as_deref(), Some("EscMenuBackdrop"));
+        assert!(panel.inherit_children);
+        assert_eq!((panel.width, panel.height), (Some(0.3), Some(0.2)));
+        assert_eq!(
+            panel.anchors,
+            vec![Anchor {
+                point: "TOPLEFT".to_string(),
+                relative_to: "ConsoleUI".to_string(),
+                relative_point: "TOPLEFT".to_string(),
+                x: 0.01,
+                y: -0.02,
+            }]
+        );
+        assert_eq!(
+            panel.properties[0].values,
+            vec![FdfValue::Str("Textures\\panel.blp".to_string())]
+        );
+
+        assert_eq!(panel.children.len(), 2);
+        let title = &panel.children[0];
+        assert_eq!(title.name, "MyPanelTitle");
+        assert_eq!(title.properties.len(), 2);
+        assert_eq!(title.properties[1].name, "SetAllPoints");
+        assert_eq!(panel.children[1].frame_type, "Texture");
+    }
+
+    #[test]
+    fn test_write_fdf_roundtrip() {
+        let fdf = br#"
+Frame "SIMPLEFRAME" "Bar" INHERITS "BaseBar" {
+    Width 0.1,
+    SetPoint BOTTOM, "Parent", TOP, 0, 0.005,
+    Font "MasterFont", 0.012,
+    DecorateFileNames,
+    Texture "BarFill" {
+        File "bar.blp",
+        AlphaMode "BLEND",
+    }
+}
+StringList {
+    OK "OK",
+}
+"#;
+        let frames = parse_fdf(fdf).unwrap();
+        let written = write_fdf(&frames).unwrap();
+        assert_eq!(parse_fdf(written.as_bytes()).unwrap(), frames);
+        assert!(written.contains("    SetPoint BOTTOM, \"Parent\", TOP, 0, 0.005,\n"));
+        assert!(written.contains("    Texture \"BarFill\" {\n        File \"bar.blp\",\n"));
+    }
+
+    #[test]
+    fn test_parse_toc() {
+        let toc = b"// UI\r\nUI\\MyPanel.fdf\r\n\r\nUI\\MyBar.fdf\r\n\r\n";
+        assert_eq!(
+            parse_toc(toc).unwrap(),
+            vec!["UI\\MyPanel.fdf", "UI\\MyBar.fdf"]
+        );
+    }
+}
@@ -0,0 +1,513 @@
+// 把解析后的模型导出为 glTF 2.0 二进制（.glb）
+// 几何、材质、骨骼层级与（调用方提供的）PNG 纹理写入单个文件。
+// War3 为 Z 轴向上，导出时转换为 glTF 的 Y 轴向上
+
+use crate::mdx_parser::{Geoset, MdxModel, Vertex, NO_PARENT};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const GLB_MAGIC: &[u8; 4] = b"glTF";
+const GLB_VERSION: u32 = 2;
+const CHUNK_JSON: u32 = 0x4E4F_534A;
+const CHUNK_BIN: u32 = 0x004E_4942;
+
+// accessor componentType
+const FLOAT: u32 = 5126;
+const UNSIGNED_SHORT: u32 = 5123;
+// bufferView target
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+// sampler wrap
+const REPEAT: u32 = 10497;
+const CLAMP_TO_EDGE: u32 = 33071;
+
+// 材质层 filter mode 0 为不透明，1 为 alpha 测试，其余按混合处理
+const FILTER_NONE: u32 = 0;
+const FILTER_TRANSPARENT: u32 = 1;
+const ALPHA_CUTOFF: f32 = 0.75;
+const SHADING_TWO_SIDED: u32 = 0x10;
+
+/// Z 轴向上转 Y 轴向上
+fn to_y_up(v: &Vertex) -> [f32; 3] {
+    [v.x, v.z, -v.y]
+}
+
+/// 二进制缓冲区及其 bufferView/accessor 描述
+struct BinBuilder {
+    data: Vec<u8>,
+    views: Vec<Value>,
+    accessors: Vec<Value>,
+}
+
+impl BinBuilder {
+    fn push_view(&mut self, bytes: &[u8], target: Option<u32>) -> usize {
+        self.data.resize(self.data.len().next_multiple_of(4), 0);
+        let mut view = json!({
+            "buffer": 0,
+            "byteOffset": self.data.len(),
+            "byteLength": bytes.len(),
+        });
+        if let Some(target) = target {
+            view["target"] = json!(target);
+        }
+        self.data.extend_from_slice(bytes);
+        self.views.push(view);
+        self.views.len() - 1
+    }
+
+    fn push_floats<const N: usize>(&mut self, values: &[[f32; N]], with_bounds: bool) -> usize {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flatten()
+            .flat_map(|v| v.to_le_bytes())
+            .collect();
+        let view = self.push_view(&bytes, Some(ARRAY_BUFFER));
+        let mut accessor = json!({
+            "bufferView": view,
+            "componentType": FLOAT,
+            "count": values.len(),
+            "type": accessor_type(N),
+        });
+        // POSITION 必须提供 min/max
+        if with_bounds {
+            let mut min = [f32::MAX; N];
+            let mut max = [f32::MIN; N];
+            for value in values {
+                for i in 0..N {
+                    min[i] = min[i].min(value[i]);
+                    max[i] = max[i].max(value[i]);
+                }
+            }
+            accessor["min"] = json!(min.to_vec());
+            accessor["max"] = json!(max.to_vec());
+        }
+        self.accessors.push(accessor);
+        self.accessors.len() - 1
+    }
+
+    fn push_u16s(&mut self, values: &[u16], kind: &str, target: Option<u32>) -> usize {
+        let bytes: Vec<u8> = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+        let view = self.push_view(&bytes, target);
+        let components = if kind == "VEC4" { 4 } else { 1 };
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": UNSIGNED_SHORT,
+            "count": values.len() / components,
+            "type": kind,
+        }));
+        self.accessors.len() - 1
+    }
+}
+
+fn accessor_type(components: usize) -> &'static str {
+    match components {
+        1 => "SCALAR",
+        2 => "VEC2",
+        3 => "VEC3",
+        _ => "VEC4",
+    }
+}
+
+/// 每个顶点最多 4 个骨骼（关节下标）及权重
+///
+/// 有 SKIN 数据时使用实际权重；经典模型按 GNDX 矩阵组平均分配。
+/// 没有骨骼的顶点绑定到第一个关节。
+fn vertex_joints(geoset: &Geoset, joint_of: &HashMap<u32, usize>) -> (Vec<u16>, Vec<[f32; 4]>) {
+    let joint = |matrix: usize| -> Option<u16> {
+        let object_id = geoset.matrix_indices.get(matrix)?;
+        joint_of.get(object_id).map(|&j| j as u16)
+    };
+
+    let mut group_starts = Vec::with_capacity(geoset.matrix_groups.len());
+    let mut start = 0usize;
+    for &size in &geoset.matrix_groups {
+        group_starts.push((start, size as usize));
+        start += size as usize;
+    }
+
+    let mut joints = Vec::with_capacity(geoset.vertices.len() * 4);
+    let mut weights = Vec::with_capacity(geoset.vertices.len());
+    for i in 0..geoset.vertices.len() {
+        let mut influences: Vec<(u16, f32)> = match geoset.skin.get(i) {
+            Some(entry) => (0..4)
+                .filter(|&k| entry[4 + k] > 0)
+                .filter_map(|k| Some((joint(entry[k] as usize)?, entry[4 + k] as f32)))
+                .collect(),
+            None => geoset
+                .vertex_groups
+                .get(i)
+                .and_then(|&group| group_starts.get(group as usize))
+                .map(|&(start, size)| {
+                    (start..start + size)
+                        .filter_map(|m| Some((joint(m)?, 1.0)))
+                        .take(4)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        };
+        if influences.is_empty() {
+            influences.push((0, 1.0));
+        }
+
+        let total: f32 = influences.iter().map(|&(_, w)| w).sum();
+        let mut j = [0u16; 4];
+        let mut w = [0f32; 4];
+        for (k, &(index, weight)) in influences.iter().enumerate() {
+            j[k] = index;
+            w[k] = weight / total;
+        }
+        joints.extend_from_slice(&j);
+        weights.push(w);
+    }
+    (joints, weights)
+}
+
+/// 导出为 .glb：每个 geoset 一个图元（共用一个 mesh），节点层级按 pivot 生成关节与蒙皮
+///
+/// `texture_images` 为纹理下标 -> PNG 数据，以 bufferView 图像内嵌；没有图像的纹理不生成贴图
+pub fn export_glb(model: &MdxModel, texture_images: &HashMap<usize, Vec<u8>>) -> Result<Vec<u8>, String> {
+    let mut bin = BinBuilder {
+        data: Vec::new(),
+        views: Vec::new(),
+        accessors: Vec::new(),
+    };
+
+    // 图像与纹理
+    let mut images = Vec::new();
+    let mut samplers = Vec::new();
+    let mut textures = Vec::new();
+    let mut texture_of = HashMap::new();
+    for (index, texture) in model.textures.iter().enumerate() {
+        let Some(png) = texture_images.get(&index) else {
+            continue;
+        };
+        let view = bin.push_view(png, None);
+        images.push(json!({ "bufferView": view, "mimeType": "image/png" }));
+        samplers.push(json!({
+            "wrapS": if texture.flags & 0x1 != 0 { REPEAT } else { CLAMP_TO_EDGE },
+            "wrapT": if texture.flags & 0x2 != 0 { REPEAT } else { CLAMP_TO_EDGE },
+        }));
+        textures.push(json!({ "source": images.len() - 1, "sampler": samplers.len() - 1 }));
+        texture_of.insert(index as u32, textures.len() - 1);
+    }
+
+    // 材质取第一层的纹理、透明度与混合方式
+    let materials: Vec<Value> = model
+        .materials
+        .iter()
+        .enumerate()
+        .map(|(index, material)| {
+            let layer = material.layers.first();
+            let mut pbr = json!({
+                "baseColorFactor": [1.0, 1.0, 1.0, layer.map_or(1.0, |l| l.alpha)],
+                "metallicFactor": 0.0,
+                "roughnessFactor": 1.0,
+            });
+            if let Some(&texture) = layer.and_then(|l| texture_of.get(&l.texture_id)) {
+                pbr["baseColorTexture"] = json!({ "index": texture });
+            }
+            let mut value = json!({
+                "name": format!("material_{}", index),
+                "pbrMetallicRoughness": pbr,
+                "doubleSided": layer.is_some_and(|l| l.shading_flags & SHADING_TWO_SIDED != 0),
+            });
+            match layer.map_or(FILTER_NONE, |l| l.filter_mode) {
+                FILTER_NONE => value["alphaMode"] = json!("OPAQUE"),
+                FILTER_TRANSPARENT => {
+                    value["alphaMode"] = json!("MASK");
+                    value["alphaCutoff"] = json!(ALPHA_CUTOFF);
+                }
+                _ => value["alphaMode"] = json!("BLEND"),
+            }
+            value
+        })
+        .collect();
+
+    // 骨骼层级：节点的局部平移为自身 pivot 减去父节点 pivot
+    let model_nodes = model.all_nodes();
+    let joint_of: HashMap<u32, usize> = model_nodes
+        .iter()
+        .enumerate()
+        .map(|(index, node)| (node.object_id, index))
+        .collect();
+    let pivot = |object_id: u32| -> Vertex {
+        model
+            .pivot_points
+            .get(object_id as usize)
+            .copied()
+            .unwrap_or_default()
+    };
+    let mut nodes: Vec<Value> = Vec::with_capacity(model_nodes.len() + 1);
+    let mut children: Vec<Vec<usize>> = vec![Vec::new(); model_nodes.len()];
+    let mut roots = Vec::new();
+    for (index, node) in model_nodes.iter().enumerate() {
+        let own = to_y_up(&pivot(node.object_id));
+        let parent = joint_of
+            .get(&node.parent_id)
+            .copied()
+            .filter(|&parent| node.parent_id != NO_PARENT && parent != index);
+        let base = match parent {
+            Some(parent) => {
+                children[parent].push(index);
+                to_y_up(&pivot(model_nodes[parent].object_id))
+            }
+            None => {
+                roots.push(index);
+                [0.0; 3]
+            }
+        };
+        nodes.push(json!({
+            "name": node.name,
+            "translation": [own[0] - base[0], own[1] - base[1], own[2] - base[2]],
+        }));
+    }
+    for (node, node_children) in nodes.iter_mut().zip(&children) {
+        if !node_children.is_empty() {
+            node["children"] = json!(node_children);
+        }
+    }
+    let skinned = !model_nodes.is_empty();
+
+    // 每个 geoset 一个图元；越界的面被跳过
+    let mut primitives = Vec::new();
+    for geoset in &model.geosets {
+        let vertex_count = geoset.vertices.len();
+        let indices: Vec<u16> = geoset
+            .faces
+            .iter()
+            .filter(|face| face.indices.iter().all(|&i| (i as usize) < vertex_count))
+            .flat_map(|face| face.indices)
+            .collect();
+        if vertex_count == 0 || indices.is_empty() {
+            continue;
+        }
+
+        let positions: Vec<[f32; 3]> = geoset.vertices.iter().map(to_y_up).collect();
+        let mut attributes = json!({ "POSITION": bin.push_floats(&positions, true) });
+        if geoset.normals.len() == vertex_count {
+            let normals: Vec<[f32; 3]> = geoset
+                .normals
+                .iter()
+                .map(|n| {
+                    to_y_up(&Vertex {
+                        x: n.x,
+                        y: n.y,
+                        z: n.z,
+                    })
+                })
+                .collect();
+            attributes["NORMAL"] = json!(bin.push_floats(&normals, false));
+        }
+        for (layer, uvs) in geoset.uvs.iter().enumerate() {
+            if uvs.len() == vertex_count {
+                let uvs: Vec<[f32; 2]> = uvs.iter().map(|uv| [uv.u, uv.v]).collect();
+                attributes[format!("TEXCOORD_{}", layer)] = json!(bin.push_floats(&uvs, false));
+            }
+        }
+        if skinned {
+            let (joints, weights) = vertex_joints(geoset, &joint_of);
+            attributes["JOINTS_0"] = json!(bin.push_u16s(&joints, "VEC4", Some(ARRAY_BUFFER)));
+            attributes["WEIGHTS_0"] = json!(bin.push_floats(&weights, false));
+        }
+
+        let mut primitive = json!({
+            "attributes": attributes,
+            "indices": bin.push_u16s(&indices, "SCALAR", Some(ELEMENT_ARRAY_BUFFER)),
+        });
+        if (geoset.material_id as usize) < materials.len() {
+            primitive["material"] = json!(geoset.material_id);
+        }
+        primitives.push(primitive);
+    }
+
+    let mut gltf = json!({
+        "asset": { "version": "2.0", "generator": "wc3-ui-designer" },
+        "scene": 0,
+    });
+    let mut skins = Vec::new();
+    let mut meshes = Vec::new();
+    if !primitives.is_empty() {
+        let mut mesh_node = json!({ "name": model.name, "mesh": 0 });
+        if skinned {
+            // 绑定姿态下关节的世界变换就是平移到 pivot，逆绑定矩阵为平移回原点
+            let inverse_bind: Vec<[f32; 16]> = model_nodes
+                .iter()
+                .map(|node| {
+                    let [x, y, z] = to_y_up(&pivot(node.object_id));
+                    let mut m = [0.0; 16];
+                    m[0] = 1.0;
+                    m[5] = 1.0;
+                    m[10] = 1.0;
+                    m[15] = 1.0;
+                    m[12] = -x;
+                    m[13] = -y;
+                    m[14] = -z;
+                    m
+                })
+                .collect();
+            skins.push(json!({
+                "joints": (0..model_nodes.len()).collect::<Vec<_>>(),
+                "inverseBindMatrices": bin.push_floats(&inverse_bind, false),
+            }));
+            mesh_node["skin"] = json!(0);
+        }
+        meshes.push(json!({ "name": model.name, "primitives": primitives }));
+        nodes.push(mesh_node);
+        roots.push(nodes.len() - 1);
+    }
+    bin.data.resize(bin.data.len().next_multiple_of(4), 0);
+
+    gltf["scenes"] = json!([{ "nodes": roots }]);
+    gltf["nodes"] = json!(nodes);
+    for (key, list) in [
+        ("meshes", meshes),
+        ("skins", skins),
+        ("materials", materials),
+        ("textures", textures),
+        ("images", images),
+        ("samplers", samplers),
+        ("accessors", std::mem::take(&mut bin.accessors)),
+        ("bufferViews", std::mem::take(&mut bin.views)),
+    ] {
+        if !list.is_empty() {
+            gltf[key] = json!(list);
+        }
+    }
+    if !bin.data.is_empty() {
+        gltf["buffers"] = json!([{ "byteLength": bin.data.len() }]);
+    }
+
+    let mut json_bytes =
+        serde_json::to_vec(&gltf).map_err(|e| format!("Failed to serialize glTF: {}", e))?;
+    json_bytes.resize(json_bytes.len().next_multiple_of(4), b' ');
+
+    let mut total = 12 + 8 + json_bytes.len();
+    if !bin.data.is_empty() {
+        total += 8 + bin.data.len();
+    }
+    let mut out = Vec::with_capacity(total);
+    out.extend_from_slice(GLB_MAGIC);
+    out.extend_from_slice(&GLB_VERSION.to_le_bytes());
+    out.extend_from_slice(&(total as u32).to_le_bytes());
+    out.extend_from_slice(&(json_bytes.len() as u32).to_le_bytes());
+    out.extend_from_slice(&CHUNK_JSON.to_le_bytes());
+    out.extend_from_slice(&json_bytes);
+    if !bin.data.is_empty() {
+        out.extend_from_slice(&(bin.data.len() as u32).to_le_bytes());
+        out.extend_from_slice(&CHUNK_BIN.to_le_bytes());
+        out.extend_from_slice(&bin.data);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdx_parser::{Bone, Face, Layer, Material, Node, NodeFlags, Texture, UV};
+
+    #[test]
+    fn test_export_glb_structure() {
+        let mut model = MdxModel::default();
+        model.geosets.push(Geoset {
+            vertices: vec![
+                Vertex {
+                    x: 0.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                Vertex {
+                    x: 1.0,
+                    y: 0.0,
+                    z: 0.0,
+                },
+                Vertex {
+                    x: 0.0,
+                    y: 1.0,
+                    z: 1.0,
+                },
+            ],
+            uvs: vec![vec![UV { u: 0.0, v: 0.0 }; 3]],
+            faces: vec![Face { indices: [0, 1, 2] }],
+            vertex_groups: vec![0, 0, 0],
+            matrix_groups: vec![1],
+            matrix_indices: vec![0],
+            ..Geoset::default()
+        });
+        model.bones.push(Bone {
+            node: Node {
+                name: "Root".to_string(),
+                object_id: 0,
+                parent_id: NO_PARENT,
+                flags: 0,
+                decoded_flags: NodeFlags::default(),
+                translation: None,
+                rotation: None,
+                scaling: None,
+            },
+            geoset_id: 0,
+            geoset_animation_id: u32::MAX,
+        });
+        model.pivot_points.push(Vertex {
+            x: 0.0,
+            y: 0.0,
+            z: 5.0,
+        });
+
+        // 纹理 0 有图像，纹理 1 没有
+        model.textures = vec![
+            Texture::new(0, "Textures\\Box.blp".to_string(), 0),
+            Texture::new(1, String::new(), 0),
+        ];
+        model.materials = [0, 1]
+            .into_iter()
+            .map(|texture_id| Material {
+                priority_plane: 0,
+                flags: 0,
+                shader: String::new(),
+                layers: vec![Layer {
+                    filter_mode: 0,
+                    shading_flags: 0,
+                    texture_id,
+                    texture_animation_id: u32::MAX,
+                    coord_id: 0,
+                    alpha: 1.0,
+                    alpha_track: None,
+                    texture_track: None,
+                    emissive_gain: None,
+                    fresnel_color: None,
+                    fresnel_opacity: None,
+                    fresnel_team_color: None,
+                }],
+            })
+            .collect();
+        let png = vec![0x89, b'P', b'N', b'G'];
+        let images = HashMap::from([(0, png.clone())]);
+
+        let glb = export_glb(&model, &images).unwrap();
+        assert_eq!(&glb[0..4], GLB_MAGIC);
+        assert_eq!(
+            u32::from_le_bytes(glb[8..12].try_into().unwrap()) as usize,
+            glb.len()
+        );
+
+        let json_len = u32::from_le_bytes(glb[12..16].try_into().unwrap()) as usize;
+        let gltf: Value = serde_json::from_slice(&glb[20..20 + json_len]).unwrap();
+        let attributes = &gltf["meshes"][0]["primitives"][0]["attributes"];
+        assert!(attributes["TEXCOORD_0"].is_number());
+        assert!(attributes["JOINTS_0"].is_number());
+        assert_eq!(gltf["skins"][0]["joints"], json!([0]));
+        // pivot (0, 0, 5) 转为 Y 轴向上
+        assert_eq!(gltf["nodes"][0]["translation"], json!([0.0, 5.0, 0.0]));
+
+        // 图像内嵌在 BIN chunk 中，没有外部 uri
+        assert_eq!(gltf["images"].as_array().unwrap().len(), 1);
+        let image = &gltf["images"][0];
+        assert!(image.get("uri").is_none());
+        assert_eq!(image["mimeType"], "image/png");
+        let view = &gltf["bufferViews"][image["bufferView"].as_u64().unwrap() as usize];
+        let offset = 20 + json_len + 8 + view["byteOffset"].as_u64().unwrap() as usize;
+        assert_eq!(glb[offset..offset + png.len()], png);
+        assert_eq!(gltf["materials"][0]["pbrMetallicRoughness"]["baseColorTexture"]["index"], 0);
+        assert!(gltf["materials"][1]["pbrMetallicRoughness"].get("baseColorTexture").is_none());
+    }
+}
@@ -1,9 +1,23 @@
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
+use rayon::prelude::*;
 
 mod mdx_parser;
+mod mdl_parser;
+mod mdl_writer;
+mod mdx_animation;
+mod mdx_writer;
+mod mdx_tools;
+mod gltf_export;
 mod blp_handler;
+mod w3i_parser;
+mod w3e_parser;
+mod minimap;
+mod map_script;
+mod fdf_parser;
+mod error;
 
+use error::AppError;
 use mdx_parser::MdxParser;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
@@ -16,21 +30,90 @@ fn greet(name: &str) -> String {
 struct MpqFileInfo {
     name: String,
     size: u64,
+    locale: u16,   // Windows LANGID，0 为默认（中性）语言
+    platform: u16,
 }
 
-// MPQ 档案缓存
+/// 在哈希表中查找同一路径的所有有效条目（不同语言版本各占一个条目）
+fn hash_entries_for<'a>(table: &'a wow_mpq::HashTable, name: &str) -> Vec<&'a wow_mpq::HashEntry> {
+    use wow_mpq::{hash_string, hash_type};
+
+    let entries = table.entries();
+    if entries.is_empty() {
+        return Vec::new();
+    }
+    let name_a = hash_string(name, hash_type::NAME_A);
+    let name_b = hash_string(name, hash_type::NAME_B);
+    let start = hash_string(name, hash_type::TABLE_OFFSET) as usize % entries.len();
+
+    // 线性探测直到遇到从未使用过的空位
+    let mut found = Vec::new();
+    for i in 0..entries.len() {
+        let entry = &entries[(start + i) % entries.len()];
+        if entry.is_empty() {
+            break;
+        }
+        if entry.is_valid() && entry.name_1 == name_a && entry.name_2 == name_b {
+            found.push(entry);
+        }
+    }
+    found
+}
+
+// 档案文件在磁盘上的修改时间与大小，用于判断缓存是否过期
+type ArchiveStamp = (Option<std::time::SystemTime>, u64);
+
+fn archive_stamp(path: &str) -> Option<ArchiveStamp> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok(), meta.len()))
+}
+
+// MPQ 档案缓存（均记录缓存时档案的修改时间与大小，档案被外部修改后自动失效）
 struct MpqCache {
-    archives: HashMap<String, Vec<MpqFileInfo>>,
+    archives: HashMap<String, (Option<ArchiveStamp>, Vec<MpqFileInfo>)>,
+    handles: HashMap<String, (Option<ArchiveStamp>, Arc<Mutex<wow_mpq::Archive>>)>, // 已打开的档案句柄，避免每次读取都重新打开
+    recent: Vec<String>, // 按最近使用排序的档案路径，末尾为最近使用
+    limit: usize,        // 最多缓存的档案数，超出时淘汰最久未使用的
 }
 
+// 默认最多缓存的档案数
+const DEFAULT_MPQ_CACHE_LIMIT: usize = 8;
+
 impl MpqCache {
     fn new() -> Self {
         MpqCache {
             archives: HashMap::new(),
+            handles: HashMap::new(),
+            recent: Vec::new(),
+            limit: DEFAULT_MPQ_CACHE_LIMIT,
+        }
+    }
+
+    /// 标记档案为最近使用，并淘汰超出上限的档案（文件列表与句柄一并释放）
+    fn touch(&mut self, path: &str) {
+        self.recent.retain(|p| p != path);
+        self.recent.push(path.to_string());
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while self.recent.len() > self.limit {
+            let oldest = self.recent.remove(0);
+            self.archives.remove(&oldest);
+            self.handles.remove(&oldest);
         }
     }
 }
 
+/// 缓存统计：缓存的档案数、打开的句柄数与文件列表的大致内存占用
+#[derive(serde::Serialize, Clone)]
+struct CacheStats {
+    entries: usize,
+    open_handles: usize,
+    approx_bytes: usize,
+    limit: usize,
+}
+
 static MPQ_CACHE: Mutex<Option<MpqCache>> = Mutex::new(None);
 
 fn init_cache() {
@@ -40,167 +123,1575 @@ fn init_cache() {
     }
 }
 
+/// 使用缓存的档案句柄执行操作（首次使用时打开）
+/// 缓存锁只在取出句柄时持有；`op` 执行期间只锁该档案自己的句柄（`read_file` 需要 `&mut self`），
+/// 因此同一档案的读取串行化，不同档案可以并发读取
+/// 注意不要在 `op` 中对同一档案再调用 `with_archive`，否则会死锁
+/// 打开失败报告为 `MpqOpen`，`op` 返回的错误报告为 `MpqRead`
+fn with_archive<T>(
+    path: &str,
+    op: impl FnOnce(&mut wow_mpq::Archive) -> Result<T, String>,
+) -> Result<T, AppError> {
+    let handle = {
+        let mut cache = MPQ_CACHE.lock().unwrap();
+        let cache = cache.get_or_insert_with(MpqCache::new);
+        let stamp = archive_stamp(path);
+        let handle = match cache.handles.get(path) {
+            Some((cached, handle)) if *cached == stamp => Arc::clone(handle),
+            _ => {
+                let handle = Arc::new(Mutex::new(open_archive(path)?));
+                cache.handles.insert(path.to_string(), (stamp, Arc::clone(&handle)));
+                handle
+            }
+        };
+        cache.touch(path);
+        handle
+    };
+    let mut archive = handle.lock().unwrap();
+    op(&mut archive).map_err(AppError::MpqRead)
+}
+
+/// 打开 MPQ 档案（不经过缓存）
+fn open_archive(path: &str) -> Result<wow_mpq::Archive, AppError> {
+    wow_mpq::Archive::open(path)
+        .map_err(|e| AppError::MpqOpen(format!("无法打开 MPQ 档案: {:?}", e)))
+}
+
+/// 把文件列表写入缓存，附带档案当前的修改时间与大小
+fn cache_file_list(path: String, files: &[MpqFileInfo]) {
+    let mut cache = MPQ_CACHE.lock().unwrap();
+    if let Some(ref mut cache) = *cache {
+        cache.archives.insert(path.clone(), (archive_stamp(&path), files.to_vec()));
+        cache.touch(&path);
+    }
+}
+
+// 批量处理使用的线程池，未设置时使用 rayon 全局线程池（线程数 = CPU 核数）
+static WORKER_POOL: Mutex<Option<Arc<rayon::ThreadPool>>> = Mutex::new(None);
+
+/// 在批量处理线程池中执行
+fn run_in_worker_pool<T: Send>(op: impl FnOnce() -> T + Send) -> T {
+    let pool = WORKER_POOL.lock().unwrap().clone();
+    match pool {
+        Some(pool) => pool.install(op),
+        None => op(),
+    }
+}
+
+/// 设置批量处理的线程数，0 表示恢复默认（CPU 核数）
+#[tauri::command]
+fn set_worker_threads(n: usize) -> Result<(), AppError> {
+    let pool = if n == 0 {
+        None
+    } else {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(n)
+            .build()
+            .map_err(|e| AppError::Other(format!("创建线程池失败: {}", e)))?;
+        Some(Arc::new(pool))
+    };
+    *WORKER_POOL.lock().unwrap() = pool;
+    Ok(())
+}
+
+/// 在阻塞线程中执行耗时的解析/读取，避免占用命令线程
+async fn run_blocking<T: Send + 'static>(
+    op: impl FnOnce() -> Result<T, AppError> + Send + 'static,
+) -> Result<T, AppError> {
+    tauri::async_runtime::spawn_blocking(op)
+        .await
+        .map_err(|e| AppError::Other(format!("后台任务失败: {}", e)))?
+}
+
 #[tauri::command]
-fn load_mpq_archive(path: String) -> Result<Vec<MpqFileInfo>, String> {
+async fn load_mpq_archive(path: String, force: bool) -> Result<Vec<MpqFileInfo>, AppError> {
+    run_blocking(move || load_archive_file_list(path, force)).await
+}
+
+/// 读取档案文件列表（带缓存）
+fn load_archive_file_list(path: String, force: bool) -> Result<Vec<MpqFileInfo>, AppError> {
     init_cache();
     
-    // 检查缓存
+    // 检查缓存（档案在磁盘上被修改过则重新加载；force 时跳过缓存并重新打开档案）
     {
-        let cache = MPQ_CACHE.lock().unwrap();
-        if let Some(ref cache) = *cache {
-            if let Some(files) = cache.archives.get(&path) {
-                return Ok(files.clone());
+        let mut cache = MPQ_CACHE.lock().unwrap();
+        if let Some(ref mut cache) = *cache {
+            if force {
+                cache.handles.remove(&path);
+            } else if let Some((stamp, files)) = cache.archives.get(&path) {
+                if *stamp == archive_stamp(&path) {
+                    let files = files.clone();
+                    cache.touch(&path);
+                    return Ok(files);
+                }
             }
         }
     }
     
-    // 打开 MPQ 档案（wow-mpq 使用路径而不是 File）
-    let mut archive = wow_mpq::Archive::open(&path)
-        .map_err(|e| format!("无法打开 MPQ 档案: {:?}", e))?;
-    
     // 获取文件列表
-    let mut files = Vec::new();
+    let files = with_archive(&path, |archive| {
+        let mut files = Vec::new();
     
-    // 尝试读取 listfile
-    match archive.read_file("(listfile)") {
-        Ok(listfile_data) => {
-            let listfile_str = String::from_utf8_lossy(&listfile_data);
-            for line in listfile_str.lines() {
-                let filename = line.trim();
-                if !filename.is_empty() {
-                    files.push(MpqFileInfo {
-                        name: filename.to_string(),
-                        size: 0,
-                    });
+        // 尝试读取 listfile
+        match archive.read_file("(listfile)") {
+            Ok(listfile_data) => {
+                let listfile_str = String::from_utf8_lossy(&listfile_data);
+                for line in listfile_str.lines() {
+                    let filename = line.trim();
+                    if filename.is_empty() {
+                        continue;
+                    }
+                    let entries = named_entries(archive, filename);
+                    if entries.is_empty() {
+                        // 无哈希表（HET/BET 档案）或 listfile 中多余的条目，按默认语言列出
+                        files.push(MpqFileInfo {
+                            name: filename.to_string(),
+                            size: 0,
+                            locale: 0,
+                            platform: 0,
+                        });
+                    }
+                    files.extend(entries);
                 }
             }
+            Err(_) => {
+                // listfile 被删除（常见于加密/保护过的地图），直接从哈希表枚举
+                files = unnamed_entries(archive);
+            }
         }
-        Err(_) => {
-            // listfile 不存在，忽略
-        }
-    }
+        Ok(files)
+    })?;
     
     // 缓存结果
+    cache_file_list(path, &files);
+    
+    Ok(files)
+}
+
+/// 文件名在哈希表中对应的各语言版本条目，无哈希表或名称不存在时为空
+fn named_entries(archive: &wow_mpq::Archive, name: &str) -> Vec<MpqFileInfo> {
+    archive
+        .hash_table()
+        .map(|table| hash_entries_for(table, name))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|entry| MpqFileInfo {
+            name: name.to_string(),
+            size: 0,
+            locale: entry.locale,
+            platform: entry.platform,
+        })
+        .collect()
+}
+
+/// 加载档案文件列表，并合并外部 listfile 中的文件名（如社区维护的完整 listfile）
+/// 外部文件名需在哈希表中确实存在才会加入；被认出的 `unknown_<index>` 条目改用真实名称
+#[tauri::command]
+fn load_mpq_archive_with_listfile(path: String, external_listfile: String) -> Result<Vec<MpqFileInfo>, AppError> {
+    use wow_mpq::{hash_string, hash_type};
+
+    let mut files = load_archive_file_list(path.clone(), false)?;
+    let listfile = std::fs::read(&external_listfile)
+        .map_err(|e| AppError::Io(format!("无法读取 listfile {}: {}", external_listfile, e)))?;
+    let listfile = String::from_utf8_lossy(&listfile);
+
+    let files = with_archive(&path, |archive| {
+        let mut known: std::collections::HashSet<String> =
+            files.iter().map(|file| file.name.to_ascii_lowercase()).collect();
+        let mut resolved = std::collections::HashSet::new();
+        for line in listfile.lines() {
+            let name = line.trim();
+            if name.is_empty() || !known.insert(name.to_ascii_lowercase()) {
+                continue;
+            }
+            let entries = named_entries(archive, name);
+            if !entries.is_empty() {
+                resolved.insert((
+                    hash_string(name, hash_type::NAME_A),
+                    hash_string(name, hash_type::NAME_B),
+                ));
+                files.extend(entries);
+            }
+        }
+
+        // 去掉已经认出名称的未命名条目
+        if let Some(table) = archive.hash_table() {
+            files.retain(|file| {
+                let index = file
+                    .name
+                    .strip_prefix(UNKNOWN_FILE_PREFIX)
+                    .and_then(|i| i.parse::<usize>().ok());
+                match index.and_then(|i| table.get(i)) {
+                    Some(entry) => !resolved.contains(&(entry.name_1, entry.name_2)),
+                    None => true,
+                }
+            });
+        }
+        Ok(files)
+    })?;
+
+    // 合并后的列表替换缓存，后续过滤、解压都能使用真实名称
+    cache_file_list(path, &files);
+    Ok(files)
+}
+
+// 无 listfile 时文件名未知的条目以哈希表下标命名：unknown_<index>
+const UNKNOWN_FILE_PREFIX: &str = "unknown_";
+
+/// 枚举哈希表中所有指向有效数据块的条目，名称为 `unknown_<哈希表下标>`
+fn unnamed_entries(archive: &wow_mpq::Archive) -> Vec<MpqFileInfo> {
+    let (Some(hash_table), Some(block_table)) = (archive.hash_table(), archive.block_table()) else {
+        return Vec::new();
+    };
+    hash_table
+        .entries()
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.is_valid())
+        .filter_map(|(index, entry)| {
+            let block = block_table.get(entry.block_index as usize)?;
+            block.exists().then(|| MpqFileInfo {
+                name: format!("{}{}", UNKNOWN_FILE_PREFIX, index),
+                size: block.file_size as u64,
+                locale: entry.locale,
+                platform: entry.platform,
+            })
+        })
+        .collect()
+}
+
+/// 按文件名读取；`unknown_<index>` 形式的名称按哈希表下标直接读取
+fn read_archive_file(archive: &mut wow_mpq::Archive, name: &str) -> Result<Vec<u8>, String> {
+    let error = match archive.read_file(name) {
+        Ok(data) => return Ok(data),
+        Err(e) => e,
+    };
+    let hash_index = name
+        .strip_prefix(UNKNOWN_FILE_PREFIX)
+        .and_then(|index| index.parse::<usize>().ok());
+    let block_index = hash_index.and_then(|i| {
+        let entry = archive.hash_table()?.get(i)?;
+        entry.is_valid().then_some(entry.block_index as usize)
+    });
+    match (hash_index, block_index) {
+        (Some(hash_index), Some(block_index)) => archive
+            .read_file_by_indices(hash_index, Some(block_index))
+            .map_err(|e| format!("无法读取文件 {}: {:?}", name, e)),
+        _ => Err(format!("无法读取文件 {}: {:?}", name, error)),
+    }
+}
+
+/// 档案中文件（指定语言版本）的解压后大小，找不到时为 0
+fn mpq_file_size(archive: &wow_mpq::Archive, name: &str, locale: u16) -> u64 {
+    let block_index = archive.hash_table().and_then(|table| {
+        hash_entries_for(table, name)
+            .into_iter()
+            .find(|entry| entry.locale == locale)
+            .map(|entry| entry.block_index as usize)
+    });
+    if let Some(block) = block_index.and_then(|i| archive.block_table()?.get(i)) {
+        return block.file_size as u64;
+    }
+    // HET/BET 档案没有经典哈希表
+    match archive.find_file(name) {
+        Ok(Some(info)) => info.file_size,
+        _ => 0,
+    }
+}
+
+// UI 界面定义相关的文件扩展名
+const UI_FILE_EXTENSIONS: &[&str] = &[".fdf", ".toc", ".txt"];
+
+/// 列出档案中的 UI 界面文件（.fdf/.toc/.txt），附带文件大小
+#[tauri::command]
+fn list_ui_files(archive_path: String) -> Result<Vec<MpqFileInfo>, AppError> {
+    let files = load_archive_file_list(archive_path.clone(), false)?;
+
+    with_archive(&archive_path, |archive| {
+        Ok(files
+            .into_iter()
+            .filter(|file| {
+                let lower = file.name.to_ascii_lowercase();
+                UI_FILE_EXTENSIONS.iter().any(|ext| lower.ends_with(ext))
+            })
+            .map(|mut file| {
+                file.size = mpq_file_size(archive, &file.name, file.locale);
+                file
+            })
+            .collect())
+    })
+}
+
+/// 简单通配符匹配：`*` 匹配任意串，`?` 匹配单个字符；不区分大小写，`/` 与 `\` 视为相同
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let normalize = |s: &str| -> Vec<char> {
+        s.chars()
+            .map(|c| if c == '/' { '\\' } else { c.to_ascii_lowercase() })
+            .collect()
+    };
+    let (pattern, name) = (normalize(pattern), normalize(name));
+
+    // 回溯到最近一个 `*` 继续尝试
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if p < pattern.len() && (pattern[p] == '?' || pattern[p] == name[n]) {
+            p += 1;
+            n += 1;
+        } else if p < pattern.len() && pattern[p] == '*' {
+            star = Some((p, n));
+            p += 1;
+        } else if let Some((star_p, star_n)) = star {
+            p = star_p + 1;
+            n = star_n + 1;
+            star = Some((star_p, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// 按通配符过滤档案文件列表（如 `Units\Human\*`、`*.blp`），结果按文件名排序
+#[tauri::command]
+fn list_mpq_files_filtered(path: String, pattern: String) -> Result<Vec<MpqFileInfo>, AppError> {
+    let mut files: Vec<MpqFileInfo> = load_archive_file_list(path, false)?
+        .into_iter()
+        .filter(|file| glob_match(&pattern, &file.name))
+        .collect();
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+#[tauri::command]
+fn read_mpq_file(archive_path: String, file_name: String) -> Result<Vec<u8>, AppError> {
+    // 读取指定文件（复用缓存中已打开的档案）
+    with_archive(&archive_path, |archive| read_archive_file(archive, &file_name))
+}
+
+/// 从同一档案批量读取文件（只打开一次档案），每个文件单独返回成功或错误
+#[tauri::command]
+async fn read_mpq_files(
+    archive_path: String,
+    names: Vec<String>,
+) -> Result<Vec<Result<Vec<u8>, AppError>>, AppError> {
+    run_blocking(move || {
+        with_archive(&archive_path, |archive| {
+            Ok(names
+                .iter()
+                .map(|name| read_archive_file(archive, name).map_err(AppError::MpqRead))
+                .collect())
+        })
+    })
+    .await
+}
+
+/// 按游戏的加载顺序在多个档案中读取文件
+/// `archive_paths` 按加载顺序排列（如 War3.mpq、War3x.mpq、War3xLocal.mpq、War3Patch.mpq），
+/// 靠后的档案优先（补丁覆盖原始资源），因此从最后一个档案开始查找；无法打开的档案被跳过，
+/// 文件存在但读取失败时直接报错，不回退到更早的档案
+#[tauri::command]
+fn read_from_mpq_chain(archive_paths: Vec<String>, file_name: String) -> Result<Vec<u8>, AppError> {
+    let mut skipped = Vec::new();
+    for path in archive_paths.iter().rev() {
+        let result = with_archive(path, |archive| {
+            if matches!(archive.find_file(&file_name), Ok(None)) {
+                return Ok(None);
+            }
+            read_archive_file(archive, &file_name).map(Some)
+        });
+        match result {
+            Ok(Some(data)) => return Ok(data),
+            Ok(None) => {}
+            Err(AppError::MpqOpen(e)) => skipped.push(format!("{}: {}", path, e)),
+            Err(e) => return Err(e),
+        }
+    }
+    let mut message = format!("所有档案中都不存在文件: {}", file_name);
+    if !skipped.is_empty() {
+        message.push_str(&format!("（无法打开: {}）", skipped.join("; ")));
+    }
+    Err(AppError::MpqRead(message))
+}
+
+/// 档案链中的文件及提供它的档案
+#[derive(serde::Serialize, Clone)]
+struct MpqChainFileInfo {
+    name: String,
+    size: u64,
+    archive: String,
+}
+
+/// 合并多个档案的文件列表，顺序与优先级同 `read_from_mpq_chain`：
+/// 同名文件（不区分大小写）只保留靠后档案中的版本，结果按文件名排序；
+/// 与读取一致，无法打开的档案被跳过，全部无法打开时报错
+#[tauri::command]
+fn list_mpq_chain(archive_paths: Vec<String>) -> Result<Vec<MpqChainFileInfo>, AppError> {
+    let mut seen = std::collections::HashSet::new();
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    for path in archive_paths.iter().rev() {
+        let list = match load_archive_file_list(path.clone(), false) {
+            Ok(list) => list,
+            Err(AppError::MpqOpen(e)) => {
+                skipped.push(format!("{}: {}", path, e));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+        for file in list {
+            if seen.insert(file.name.to_ascii_lowercase()) {
+                files.push(MpqChainFileInfo {
+                    name: file.name,
+                    size: file.size,
+                    archive: path.clone(),
+                });
+            }
+        }
+    }
+    if !archive_paths.is_empty() && skipped.len() == archive_paths.len() {
+        return Err(AppError::MpqOpen(format!("无法打开任何档案: {}", skipped.join("; "))));
+    }
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+/// 按文件名的两个哈希值（NAME_A / NAME_B）读取文件，用于 listfile 名称有误但已自行算出哈希的情况
+/// 不知道文件名就无法算出起始槽位，因此遍历整个哈希表；多个语言版本时优先默认语言
+#[tauri::command]
+fn read_mpq_file_by_hash(archive_path: String, name_a: u32, name_b: u32) -> Result<Vec<u8>, AppError> {
+    with_archive(&archive_path, |archive| {
+        let slot = {
+            let table = archive
+                .hash_table()
+                .ok_or_else(|| "档案没有哈希表，无法按哈希读取".to_string())?;
+            let mut matches = table
+                .entries()
+                .iter()
+                .enumerate()
+                .filter(|(_, entry)| entry.is_valid() && entry.name_1 == name_a && entry.name_2 == name_b);
+            let first = matches.next();
+            matches
+                .find(|(_, entry)| entry.locale == 0)
+                .or(first)
+                .map(|(index, entry)| (index, entry.block_index as usize))
+        };
+        let (hash_index, block_index) =
+            slot.ok_or_else(|| format!("哈希表中没有匹配的条目: {:08X} {:08X}", name_a, name_b))?;
+        archive
+            .read_file_by_indices(hash_index, Some(block_index))
+            .map_err(|e| format!("无法读取文件 {:08X} {:08X}: {:?}", name_a, name_b, e))
+    })
+}
+
+/// 检查档案中是否存在指定文件（无 listfile 时探测 war3map.w3i 等常见文件）
+#[tauri::command]
+fn file_exists_in_mpq(path: String, name: String) -> Result<bool, AppError> {
+    with_archive(&path, |archive| {
+        archive
+            .find_file(&name)
+            .map(|info| info.is_some())
+            .map_err(|e| format!("无法查找文件 {}: {:?}", name, e))
+    })
+}
+
+/// 档案内文件的存储信息（排查解码失败用）
+#[derive(serde::Serialize, Clone)]
+struct MpqFileMeta {
+    compressed_size: u64,
+    uncompressed_size: u64,
+    flags: u32,
+    is_encrypted: bool,
+    is_single_unit: bool,
+}
+
+/// 获取档案内文件的压缩/加密等存储标志（读取块表条目，不解压文件）
+#[tauri::command]
+fn get_mpq_file_meta(archive_path: String, file_name: String) -> Result<MpqFileMeta, AppError> {
+    with_archive(&archive_path, |archive| {
+        let info = archive
+            .find_file(&file_name)
+            .map_err(|e| format!("无法查找文件 {}: {:?}", file_name, e))?;
+        if let Some(info) = info {
+            return Ok(MpqFileMeta {
+                compressed_size: info.compressed_size,
+                uncompressed_size: info.file_size,
+                flags: info.flags,
+                is_encrypted: info.is_encrypted(),
+                is_single_unit: info.is_single_unit(),
+            });
+        }
+
+        // 无 listfile 时的 unknown_<index> 条目直接查块表
+        let block = file_name
+            .strip_prefix(UNKNOWN_FILE_PREFIX)
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| archive.hash_table()?.get(index))
+            .filter(|entry| entry.is_valid())
+            .and_then(|entry| archive.block_table()?.get(entry.block_index as usize))
+            .ok_or_else(|| format!("档案中不存在文件: {}", file_name))?;
+        Ok(MpqFileMeta {
+            compressed_size: block.compressed_size as u64,
+            uncompressed_size: block.file_size as u64,
+            flags: block.flags,
+            is_encrypted: block.is_encrypted(),
+            is_single_unit: block.is_single_unit(),
+        })
+    })
+}
+
+/// 关闭指定档案的缓存句柄（档案在磁盘上被修改后需要重新打开）
+#[tauri::command]
+fn close_mpq_archive(path: String) -> Result<(), AppError> {
     let mut cache = MPQ_CACHE.lock().unwrap();
     if let Some(ref mut cache) = *cache {
-        cache.archives.insert(path, files.clone());
+        cache.handles.remove(&path);
     }
-    
-    Ok(files)
+    Ok(())
 }
 
+/// 把文件写入已有档案并更新 (listfile)；`replace` 为 false 时档案中已有同名文件则报错
+/// 写入前后都会清除该档案的缓存，之后的读取会重新打开档案
 #[tauri::command]
-fn read_mpq_file(archive_path: String, file_name: String) -> Result<Vec<u8>, String> {
-    // 打开 MPQ 档案
-    let mut archive = wow_mpq::Archive::open(&archive_path)
-        .map_err(|e| format!("无法打开 MPQ 档案: {:?}", e))?;
-    
-    // 读取指定文件
-    let file_data = archive
-        .read_file(&file_name)
-        .map_err(|e| format!("无法读取文件 {}: {:?}", file_name, e))?;
-    
-    Ok(file_data)
+fn write_mpq_file(archive_path: String, file_name: String, data: Vec<u8>, replace: bool) -> Result<(), AppError> {
+    use wow_mpq::{AddFileOptions, Error, MutableArchive};
+
+    let meta = std::fs::metadata(&archive_path)
+        .map_err(|e| AppError::Io(format!("无法读取档案 {}: {}", archive_path, e)))?;
+    if meta.permissions().readonly() {
+        return Err(AppError::Unsupported(format!("档案是只读文件，无法写入: {}", archive_path)));
+    }
+
+    invalidate_archive_cache(&archive_path);
+    let result = (|| {
+        let mut archive = MutableArchive::open(&archive_path).map_err(|e| match e {
+            Error::Io(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+                AppError::Unsupported(format!("没有档案的写入权限（可能被其他程序占用）: {}", archive_path))
+            }
+            e => AppError::MpqOpen(format!("无法以写入方式打开 MPQ 档案: {:?}", e)),
+        })?;
+        archive
+            .add_file_data(&data, &file_name, AddFileOptions::new().replace_existing(replace))
+            .map_err(|e| match e {
+                Error::FileExists(name) => AppError::Other(format!("档案中已存在文件: {}", name)),
+                Error::ReadOnly => AppError::Unsupported(format!("档案是只读的: {}", archive_path)),
+                e => AppError::Other(format!("写入文件 {} 失败: {:?}", file_name, e)),
+            })?;
+        archive
+            .flush()
+            .map_err(|e| AppError::Other(format!("保存档案失败: {:?}", e)))
+    })();
+    invalidate_archive_cache(&archive_path);
+    result
+}
+
+/// 用磁盘上的文件创建新档案并生成 (listfile)，`files` 为 (档案内路径, 源文件路径)
+/// `version` 为 MPQ 格式版本 1 或 2（默认 1，War3 使用 v1）
+/// 档案先写入临时文件再替换到 `output_path`，任一源文件缺失时不会生成档案
+#[tauri::command]
+fn create_mpq(output_path: String, files: Vec<(String, String)>, version: Option<u32>) -> Result<(), AppError> {
+    use wow_mpq::{ArchiveBuilder, FormatVersion};
+
+    let format = match version.unwrap_or(1) {
+        1 => FormatVersion::V1,
+        2 => FormatVersion::V2,
+        v => return Err(AppError::Unsupported(format!("不支持的 MPQ 格式版本: {}", v))),
+    };
+    let missing: Vec<&str> = files
+        .iter()
+        .filter(|(_, source)| !std::path::Path::new(source).is_file())
+        .map(|(_, source)| source.as_str())
+        .collect();
+    if !missing.is_empty() {
+        return Err(AppError::Io(format!("源文件不存在: {}", missing.join(", "))));
+    }
+
+    let builder = files
+        .iter()
+        .fold(ArchiveBuilder::new().version(format), |builder, (name, source)| {
+            builder.add_file(source, &name.replace('/', "\\"))
+        });
+    builder
+        .build(&output_path)
+        .map_err(|e| AppError::Other(format!("创建 MPQ 档案失败: {:?}", e)))?;
+    invalidate_archive_cache(&output_path);
+    Ok(())
+}
+
+/// 清除指定档案的文件列表缓存与已打开的句柄
+fn invalidate_archive_cache(path: &str) {
+    let mut cache = MPQ_CACHE.lock().unwrap();
+    if let Some(ref mut cache) = *cache {
+        cache.archives.remove(path);
+        cache.handles.remove(path);
+        cache.recent.retain(|p| p != path);
+    }
 }
 
 #[tauri::command]
-fn clear_mpq_cache() -> Result<(), String> {
+fn clear_mpq_cache() -> Result<(), AppError> {
     let mut cache = MPQ_CACHE.lock().unwrap();
     if let Some(ref mut cache) = *cache {
         cache.archives.clear();
+        cache.handles.clear();
+        cache.recent.clear();
     }
     Ok(())
 }
 
+/// 设置最多缓存的档案数（至少 1），超出部分立即按最久未使用淘汰
+#[tauri::command]
+fn set_mpq_cache_limit(max: usize) -> Result<(), AppError> {
+    let mut cache = MPQ_CACHE.lock().unwrap();
+    let cache = cache.get_or_insert_with(MpqCache::new);
+    cache.limit = max.max(1);
+    cache.evict();
+    Ok(())
+}
+
+/// 获取 MPQ 缓存统计（供前端显示内存占用）
+#[tauri::command]
+fn get_mpq_cache_stats() -> Result<CacheStats, AppError> {
+    let mut cache = MPQ_CACHE.lock().unwrap();
+    let cache = cache.get_or_insert_with(MpqCache::new);
+    let approx_bytes = cache
+        .archives
+        .values()
+        .flat_map(|(_, files)| files)
+        .map(|file| std::mem::size_of::<MpqFileInfo>() + file.name.capacity())
+        .sum();
+    Ok(CacheStats {
+        entries: cache.archives.len(),
+        open_handles: cache.handles.len(),
+        approx_bytes,
+        limit: cache.limit,
+    })
+}
+
+/// 从 MPQ 档案中解析 FDF 界面定义文件，返回顶层框体定义树
+#[tauri::command]
+fn parse_fdf_from_mpq(archive_path: String, file_name: String) -> Result<Vec<fdf_parser::FrameDef>, AppError> {
+    let data = read_mpq_file(archive_path, file_name)?;
+    fdf_parser::parse_fdf(&data).map_err(AppError::Other)
+}
+
+/// 从 MPQ 档案中读取 TOC 文件，返回其中按顺序列出的 FDF 路径
+#[tauri::command]
+fn parse_toc_from_mpq(archive_path: String, toc_name: String) -> Result<Vec<String>, AppError> {
+    let data = read_mpq_file(archive_path, toc_name)?;
+    fdf_parser::parse_toc(&data).map_err(AppError::Other)
+}
+
+/// 把编辑后的框体定义树写回 FDF 文本
+#[tauri::command]
+fn save_fdf(frames: Vec<fdf_parser::FrameDef>) -> Result<String, AppError> {
+    fdf_parser::write_fdf(&frames).map_err(AppError::Other)
+}
+
 /// 解码 BLP 图像为 PNG base64
 #[tauri::command]
-fn decode_blp_to_png(blp_data: Vec<u8>) -> Result<String, String> {
-    blp_handler::decode_blp_to_png_base64(&blp_data)
+fn decode_blp_to_png(blp_data: Vec<u8>) -> Result<String, AppError> {
+    blp_handler::decode_blp_to_png_base64(&blp_data).map_err(AppError::BlpDecode)
+}
+
+/// 将 BLP 转换为 png/jpeg/tga/webp/bmp 文件数据（批量导出图标用）
+#[tauri::command]
+fn convert_blp(blp_data: Vec<u8>, format: String) -> Result<Vec<u8>, AppError> {
+    blp_handler::convert_blp(&blp_data, &format).map_err(AppError::BlpDecode)
 }
 
 /// 解码 BLP 图像为 RGBA 数据（用于前端）
 #[tauri::command]
-fn decode_blp_to_rgba(blp_data: Vec<u8>) -> Result<blp_handler::BlpImageData, String> {
-    blp_handler::decode_blp(&blp_data)
+fn decode_blp_to_rgba(blp_data: Vec<u8>) -> Result<blp_handler::BlpImageData, AppError> {
+    blp_handler::decode_blp(&blp_data).map_err(AppError::BlpDecode)
+}
+
+/// 解码 BLP 为 WebGL 纹理数据，可选翻转为左下角原点、预乘 alpha
+#[tauri::command]
+fn decode_blp_ex(
+    blp_data: Vec<u8>,
+    flip_y: bool,
+    premultiply_alpha: bool,
+) -> Result<blp_handler::BlpImageData, AppError> {
+    blp_handler::decode_blp_ex(&blp_data, flip_y, premultiply_alpha).map_err(AppError::BlpDecode)
 }
 
 /// 获取 BLP 文件信息
 #[tauri::command]
-fn get_blp_file_info(blp_data: Vec<u8>) -> Result<blp_handler::BlpInfo, String> {
-    blp_handler::get_blp_info(&blp_data)
+fn get_blp_file_info(blp_data: Vec<u8>) -> Result<blp_handler::BlpInfo, AppError> {
+    blp_handler::get_blp_info(&blp_data).map_err(AppError::BlpDecode)
 }
 
 /// 解码 BLP 指定 mipmap 层级
 #[tauri::command]
-fn decode_blp_mipmap_level(blp_data: Vec<u8>, level: usize) -> Result<blp_handler::BlpImageData, String> {
-    blp_handler::decode_blp_mipmap(&blp_data, level)
+fn decode_blp_mipmap_level(blp_data: Vec<u8>, level: usize) -> Result<blp_handler::BlpImageData, AppError> {
+    blp_handler::decode_blp_mipmap(&blp_data, level).map_err(AppError::BlpDecode)
+}
+
+/// 一次性解码 BLP 的所有 mipmap 层级（从大到小）
+#[tauri::command]
+fn decode_blp_all_mipmaps(blp_data: Vec<u8>) -> Result<Vec<blp_handler::BlpImageData>, AppError> {
+    blp_handler::decode_blp_all_mipmaps(&blp_data).map_err(AppError::BlpDecode)
+}
+
+/// 把 RGBA 像素编码为 BLP：compression 1 JPEG、2 调色板（BLP1），3 DXT（BLP2）
+#[tauri::command]
+fn encode_rgba_to_blp(
+    width: u32,
+    height: u32,
+    rgba: Vec<u8>,
+    compression: u32,
+    generate_mipmaps: bool,
+) -> Result<Vec<u8>, AppError> {
+    let img = image::RgbaImage::from_raw(width, height, rgba)
+        .ok_or_else(|| AppError::BlpEncode(format!("RGBA 数据长度与尺寸 {}x{} 不符", width, height)))?;
+    blp_handler::encode_blp(&img, compression, generate_mipmaps).map_err(AppError::BlpEncode)
+}
+
+/// 判断 BLP 是否为仅 alpha 通道的遮罩纹理
+#[tauri::command]
+fn is_mask_texture(blp_data: Vec<u8>) -> Result<bool, AppError> {
+    blp_handler::is_mask_texture(&blp_data).map_err(AppError::BlpDecode)
+}
+
+/// 从网格排列的序列帧纹理中提取指定帧，返回 PNG 数据
+#[tauri::command]
+fn extract_texture_frame(blp_data: Vec<u8>, rows: u32, cols: u32, index: u32) -> Result<Vec<u8>, AppError> {
+    blp_handler::extract_texture_frame(&blp_data, rows, cols, index).map_err(AppError::BlpDecode)
+}
+
+/// 按材质层混合模式把纹理合成到纯色背景上，返回 PNG 数据（材质编辑器预览用）
+#[tauri::command]
+fn preview_layer_blend(blp_data: Vec<u8>, filter_mode: u32, backdrop: [u8; 3]) -> Result<Vec<u8>, AppError> {
+    blp_handler::preview_layer_blend(&blp_data, filter_mode, backdrop).map_err(AppError::BlpDecode)
+}
+
+/// 读取某一级 mipmap 的原始存储数据（JPEG 块、调色板索引或 DXT 块），附带格式标记
+#[tauri::command]
+fn get_blp_mipmap_raw(blp_data: Vec<u8>, level: usize) -> Result<blp_handler::BlpMipmapRaw, AppError> {
+    blp_handler::get_blp_mipmap_raw(&blp_data, level).map_err(AppError::BlpDecode)
+}
+
+/// 生成长边不超过 max_edge 的 BLP 缩略图（资源网格批量预览用）
+#[tauri::command]
+fn decode_blp_thumbnail(blp_data: Vec<u8>, max_edge: u32) -> Result<blp_handler::BlpImageData, AppError> {
+    blp_handler::decode_blp_thumbnail(&blp_data, max_edge).map_err(AppError::BlpDecode)
+}
+
+/// 并行批量解码 BLP（指定 as_thumbnail 时生成该尺寸的缩略图），每项单独返回成功或错误
+#[tauri::command]
+async fn decode_blp_batch(
+    items: Vec<Vec<u8>>,
+    as_thumbnail: Option<u32>,
+) -> Result<Vec<Result<blp_handler::BlpImageData, AppError>>, AppError> {
+    if as_thumbnail == Some(0) {
+        return Err(AppError::Unsupported("缩略图尺寸必须大于 0".to_string()));
+    }
+    run_blocking(move || {
+        Ok(run_in_worker_pool(|| {
+            items
+                .par_iter()
+                .map(|blp_data| match as_thumbnail {
+                    Some(max_edge) => blp_handler::decode_blp_thumbnail(blp_data, max_edge),
+                    None => blp_handler::decode_blp(blp_data),
+                }
+                .map_err(AppError::BlpDecode))
+                .collect()
+        }))
+    })
+    .await
+}
+
+/// 读取调色板 BLP 的 256 色调色板（RGBA）
+#[tauri::command]
+fn get_blp_palette(blp_data: Vec<u8>) -> Result<Vec<[u8; 4]>, AppError> {
+    blp_handler::get_blp_palette(&blp_data).map_err(AppError::BlpDecode)
+}
+
+/// 把 JPEG BLP 的某一级 mipmap 还原为完整的 JPEG 文件数据
+#[tauri::command]
+fn extract_blp_jpeg(blp_data: Vec<u8>, mipmap_level: usize) -> Result<Vec<u8>, AppError> {
+    blp_handler::extract_blp_jpeg(&blp_data, mipmap_level).map_err(AppError::BlpDecode)
+}
+
+/// 把多个图标合并为一张网格图集，返回图集 PNG 与各图标的 UV 矩形
+#[tauri::command]
+fn build_icon_atlas(blps: Vec<Vec<u8>>, cell: u32) -> Result<(Vec<u8>, Vec<[f32; 4]>), AppError> {
+    blp_handler::build_icon_atlas(&blps, cell).map_err(AppError::BlpEncode)
+}
+
+/// 将 BLP 解码后按原压缩方式重新编码，返回平均每通道差值（衡量编码损失）
+#[tauri::command]
+fn verify_blp_roundtrip(blp_data: Vec<u8>) -> Result<f32, AppError> {
+    blp_handler::verify_blp_roundtrip(&blp_data).map_err(AppError::BlpEncode)
 }
 
 /// 解析 MDX/MDL 模型文件，返回几何数据的 JSON
+///
+/// `compute_missing_normals` 为 true 时为缺少 NRMS 的 geoset 生成平滑法线。
+/// 在后台线程解析，每读完一个 chunk 向窗口发送 `mdx-parse-progress` 事件。
 #[tauri::command]
-fn parse_mdx_file(mdx_data: Vec<u8>) -> Result<String, String> {
-    let mut parser = MdxParser::new(mdx_data)?;
-    let model = parser.parse()?;
+async fn parse_mdx_file(
+    window: tauri::Window,
+    mdx_data: Vec<u8>,
+    compute_missing_normals: bool,
+) -> Result<String, AppError> {
+    use tauri::Emitter;
+
+    run_blocking(move || {
+        mdx_to_json(mdx_data, compute_missing_normals, |progress| {
+            let _ = window.emit("mdx-parse-progress", progress);
+        })
+    })
+    .await
+}
+
+/// 解析 MDX 并序列化为 JSON，`on_chunk` 在每个 chunk 读完后调用
+fn mdx_to_json(
+    mdx_data: Vec<u8>,
+    compute_missing_normals: bool,
+    on_chunk: impl FnMut(&mdx_parser::ParseProgress),
+) -> Result<String, AppError> {
+    let mut model = MdxParser::new(mdx_data)
+        .and_then(|mut parser| parser.parse_with_progress(on_chunk))
+        .map_err(AppError::MdxParse)?;
+    if compute_missing_normals {
+        model.compute_missing_normals();
+    }
     
     // 转换为 JSON
-    serde_json::to_string(&model)
-        .map_err(|e| format!("JSON 序列化失败: {}", e))
+    model_to_json(&model)
+}
+
+/// 模型序列化为 JSON
+fn model_to_json(model: &mdx_parser::MdxModel) -> Result<String, AppError> {
+    serde_json::to_string(model).map_err(|e| AppError::Other(format!("JSON 序列化失败: {}", e)))
+}
+
+/// 解析 MDX 数据，失败时报告为 `MdxParse`
+fn parse_mdx(mdx_data: Vec<u8>) -> Result<mdx_parser::MdxModel, AppError> {
+    MdxParser::new(mdx_data)
+        .and_then(|mut parser| parser.parse())
+        .map_err(AppError::MdxParse)
+}
+
+/// 按文件头识别并解析模型数据：以 `MDLX` 开头的按 MDX 二进制解析，其余按 MDL 文本解析，
+/// `is_text` 为 true 时强制按 MDL 解析
+fn parse_model_data(data: Vec<u8>, is_text: bool) -> Result<mdx_parser::MdxModel, String> {
+    if !is_text && data.starts_with(b"MDLX") {
+        MdxParser::new(data).and_then(|mut parser| parser.parse())
+    } else {
+        mdl_parser::MdlParser::new(data).and_then(|mut parser| parser.parse())
+    }
+}
+
+/// 解析模型文件（MDX 二进制或 MDL 文本），格式识别见 `parse_model_data`
+#[tauri::command]
+fn parse_model_file(data: Vec<u8>, is_text: bool) -> Result<String, AppError> {
+    let model = parse_model_data(data, is_text).map_err(AppError::MdxParse)?;
+    model_to_json(&model)
 }
 
 /// 从 MPQ 中读取并解析 MDX 文件
 #[tauri::command]
-fn parse_mdx_from_mpq(archive_path: String, file_name: String) -> Result<String, String> {
-    // 从 MPQ 读取文件
-    let mdx_data = read_mpq_file(archive_path, file_name)?;
-    
-    // 解析 MDX
-    parse_mdx_file(mdx_data)
+async fn parse_mdx_from_mpq(archive_path: String, file_name: String) -> Result<String, AppError> {
+    run_blocking(move || {
+        // 从 MPQ 读取文件
+        let mdx_data = read_mpq_file(archive_path, file_name)?;
+
+        // 解析 MDX
+        mdx_to_json(mdx_data, false, |_| {})
+    })
+    .await
 }
 
 /// 从本地文件系统读取并解析 MDX 文件
 #[tauri::command]
-fn parse_mdx_from_file(file_path: String) -> Result<String, String> {
-    use std::fs;
-    
-    // 读取本地文件
-    let mdx_data = fs::read(&file_path)
-        .map_err(|e| format!("无法读取文件 {}: {}", file_path, e))?;
-    
-    // 解析 MDX
-    parse_mdx_file(mdx_data)
+async fn parse_mdx_from_file(file_path: String) -> Result<String, AppError> {
+    run_blocking(move || {
+        // 读取本地文件
+        let mdx_data = std::fs::read(&file_path)
+            .map_err(|e| AppError::Io(format!("无法读取文件 {}: {}", file_path, e)))?;
+
+        // 解析 MDX
+        mdx_to_json(mdx_data, false, |_| {})
+    })
+    .await
+}
+
+/// 把（编辑后的）模型 JSON 写回二进制 MDX，JSON 结构与 `parse_mdx_file` 的返回值相同
+#[tauri::command]
+fn serialize_mdx(model_json: String) -> Result<Vec<u8>, AppError> {
+    let mut model: mdx_parser::MdxModel = serde_json::from_str(&model_json)
+        .map_err(|e| AppError::MdxParse(format!("无效的模型 JSON: {}", e)))?;
+    // 顶点可能已被编辑，重新计算包围盒
+    for geoset in &mut model.geosets {
+        geoset.bounds = mdx_parser::calculate_bounds(&geoset.vertices);
+    }
+    model.update_bounds();
+    mdx_writer::write_model(&model).map_err(AppError::MdxParse)
+}
+
+/// 按查找/替换表重写模型中的纹理路径，返回新的 MDX 数据
+///
+/// `prefix_mode` 为 true 时按路径前缀替换（如 `Textures\` -> `war3mapImported\`）
+#[tauri::command]
+fn remap_textures(
+    mdx_data: Vec<u8>,
+    replacements: HashMap<String, String>,
+    prefix_mode: Option<bool>,
+) -> Result<Vec<u8>, AppError> {
+    mdx_tools::remap_textures(&mdx_data, &replacements, prefix_mode.unwrap_or(false)).map_err(AppError::MdxParse)
+}
+
+/// 为缺少 extent 的序列计算真实包围盒并写回，返回修复后的 MDX 数据
+#[tauri::command]
+fn repair_sequence_extents(mdx_data: Vec<u8>) -> Result<Vec<u8>, AppError> {
+    mdx_tools::repair_sequence_extents(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 检查模型挂点名称是否符合命名约定，返回警告列表
+#[tauri::command]
+fn check_attachment_names(mdx_data: Vec<u8>) -> Result<Vec<String>, AppError> {
+    mdx_tools::check_attachment_names(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 返回模型概要（名称与 geoset、顶点、三角面、骨骼、纹理、序列数量），不传输几何数据
+#[tauri::command]
+fn get_mdx_summary(mdx_data: Vec<u8>) -> Result<mdx_tools::MdxSummary, AppError> {
+    mdx_tools::get_mdx_summary(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 动画时间轴：各序列的帧区间与时长（毫秒），以及全局序列时长
+#[tauri::command]
+fn get_mdx_timeline(mdx_data: Vec<u8>) -> Result<mdx_tools::Timeline, AppError> {
+    mdx_tools::get_mdx_timeline(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 生成适合版本控制 diff 的模型文本摘要
+#[tauri::command]
+fn mdx_to_text_summary(mdx_data: Vec<u8>) -> Result<String, AppError> {
+    mdx_tools::mdx_to_text_summary(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 导出模型几何为 Wavefront OBJ 文本
+#[tauri::command]
+fn export_mdx_to_obj(mdx_data: Vec<u8>) -> Result<String, AppError> {
+    mdx_tools::export_obj(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 导出模型为 MDL 文本（暂不包含灯光、粒子发射器与镜头）
+#[tauri::command]
+fn export_mdx_to_mdl(mdx_data: Vec<u8>) -> Result<String, AppError> {
+    let model = parse_mdx(mdx_data)?;
+    Ok(mdl_writer::write_mdl(&model))
+}
+
+/// 导出模型为 glTF 2.0 二进制（.glb）
+///
+/// 提供 `archive_path` 时从该档案读取纹理（可替换纹理取 `resolved_path`），转为 PNG 内嵌；
+/// 读取或解码失败的纹理不生成贴图
+#[tauri::command]
+fn export_mdx_to_gltf(mdx_data: Vec<u8>, archive_path: Option<String>) -> Result<Vec<u8>, AppError> {
+    let model = parse_mdx(mdx_data)?;
+
+    let mut images = HashMap::new();
+    if let Some(archive_path) = archive_path {
+        // 档案本身无法打开时报错，而不是静默导出无纹理的模型
+        with_archive(&archive_path, |_| Ok(()))?;
+        for (index, texture) in model.textures.iter().enumerate() {
+            let path = texture.resolved_path.as_deref().unwrap_or(&texture.path);
+            if path.is_empty() {
+                continue;
+            }
+            let png = with_archive(&archive_path, |archive| read_archive_file(archive, path))
+                .ok()
+                .and_then(|data| blp_handler::convert_blp(&data, "png").ok());
+            if let Some(png) = png {
+                images.insert(index, png);
+            }
+        }
+    }
+    gltf_export::export_glb(&model, &images).map_err(AppError::Other)
+}
+
+/// 查找退化三角面，返回 (geoset 索引, 面索引) 列表
+#[tauri::command]
+fn find_degenerate_faces(mdx_data: Vec<u8>) -> Result<Vec<(usize, usize)>, AppError> {
+    mdx_tools::find_degenerate_faces(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 查找顶点索引越界的面，返回 (geoset 索引, 面索引) 列表
+#[tauri::command]
+fn check_face_indices(mdx_data: Vec<u8>) -> Result<Vec<(usize, usize)>, AppError> {
+    mdx_tools::check_face_indices(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 列出模型依赖的游戏自带资源（非 war3mapImported 路径）
+#[tauri::command]
+fn list_stock_dependencies(mdx_data: Vec<u8>) -> Result<Vec<String>, AppError> {
+    mdx_tools::list_stock_dependencies(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 把模型及其引用的导入资源（跳过游戏自带资源）按原相对路径导出到 `output_dir`，返回写出的文件
+#[tauri::command]
+fn package_model(archive_path: String, mdx_name: String, output_dir: String) -> Result<Vec<String>, AppError> {
+    let mdx_data = read_mpq_file(archive_path.clone(), mdx_name.clone())?;
+    let model = parse_mdx(mdx_data.clone())?;
+
+    let mut files = vec![(mdx_name, mdx_data)];
+    for path in mdx_tools::imported_dependencies(&model) {
+        let data = read_mpq_file(archive_path.clone(), path.clone())?;
+        files.push((path, data));
+    }
+
+    files
+        .into_iter()
+        .map(|(name, data)| write_archive_file(&output_dir, &name, &data))
+        .collect()
+}
+
+/// 按档案内路径把文件写到 `output_dir` 下（自动创建中间目录），返回写出的路径
+fn write_archive_file(output_dir: &str, name: &str, data: &[u8]) -> Result<String, AppError> {
+    use std::path::{Component, Path, PathBuf};
+
+    // 档案内路径使用反斜杠，拒绝 ".." 等越出输出目录的路径
+    let relative: PathBuf = name.split(['\\', '/']).filter(|part| !part.is_empty()).collect();
+    if !relative.components().all(|c| matches!(c, Component::Normal(_))) {
+        return Err(AppError::Other(format!("非法的文件路径: {}", name)));
+    }
+
+    let target = Path::new(output_dir).join(relative);
+    if let Some(parent) = target.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| AppError::Io(format!("创建目录失败: {}", e)))?;
+    }
+    std::fs::write(&target, data).map_err(|e| AppError::Io(format!("写入文件失败: {}", e)))?;
+    Ok(target.to_string_lossy().to_string())
+}
+
+/// 批量解压的结果
+#[derive(serde::Serialize)]
+struct ExtractResult {
+    written: Vec<String>,           // 成功写出的路径
+    skipped: Vec<(String, String)>, // (档案内路径, 失败原因)
+}
+
+/// 批量解压档案文件到 `output_dir`，保持档案内目录结构；`names` 为空时解压 listfile 中的全部文件
+/// 单个文件读取或写入失败时跳过，并在结果中列出跳过的文件及原因
+#[tauri::command]
+fn extract_mpq_files(archive_path: String, output_dir: String, names: Option<Vec<String>>) -> Result<ExtractResult, AppError> {
+    let names = match names {
+        Some(names) => names,
+        None => load_archive_file_list(archive_path.clone(), false)?
+            .into_iter()
+            .map(|file| file.name)
+            .collect(),
+    };
+
+    let mut seen = std::collections::HashSet::new();
+    let mut result = ExtractResult {
+        written: Vec::new(),
+        skipped: Vec::new(),
+    };
+    for name in names {
+        // 同一文件的多个语言版本只解压一次
+        if !seen.insert(name.to_ascii_lowercase()) {
+            continue;
+        }
+        // 逐个文件加锁，避免长时间阻塞其他读取
+        match with_archive(&archive_path, |archive| read_archive_file(archive, &name))
+            .and_then(|data| write_archive_file(&output_dir, &name, &data))
+        {
+            Ok(path) => result.written.push(path),
+            Err(e) => result.skipped.push((name, e.to_string())),
+        }
+    }
+    Ok(result)
+}
+
+/// 查找被多个模型共用的纹理，返回 纹理路径 -> 模型列表（用于精简导入文件前评估）
+#[tauri::command]
+fn find_shared_textures(archive_path: String, model_names: Vec<String>) -> Result<HashMap<String, Vec<String>>, AppError> {
+    let sources = with_archive(&archive_path, |archive| {
+        model_names
+            .into_iter()
+            .map(|name| {
+                let data = read_archive_file(archive, &name)?;
+                Ok((name, data))
+            })
+            .collect::<Result<Vec<_>, String>>()
+    })?;
+
+    let models = run_in_worker_pool(|| {
+        sources
+            .into_par_iter()
+            .map(|(name, data)| {
+                let model = MdxParser::new(data)
+                    .and_then(|mut parser| parser.parse())
+                    .map_err(|e| format!("解析 {} 失败: {}", name, e))?;
+                Ok((name, model))
+            })
+            .collect::<Result<Vec<_>, String>>()
+    })
+    .map_err(AppError::MdxParse)?;
+
+    Ok(mdx_tools::shared_textures(&models))
+}
+
+/// 检查单个 MDX 模型的结构问题（越界索引、缺失的纹理/材质/父节点等），无问题时返回空列表
+#[tauri::command]
+fn validate_mdx(mdx_data: Vec<u8>) -> Result<Vec<String>, AppError> {
+    let model = parse_mdx(mdx_data)?;
+    Ok(mdx_tools::validate_model(&model)
+        .into_iter()
+        .map(|issue| issue.message)
+        .collect())
+}
+
+/// 批量检查档案中的所有模型，返回 (文件名, 问题列表)；解析失败也记为问题而不中断
+#[tauri::command]
+fn validate_all_models(archive_path: String) -> Result<Vec<(String, Vec<mdx_tools::ValidationIssue>)>, AppError> {
+    use mdx_tools::ValidationIssue;
+
+    let files = load_archive_file_list(archive_path.clone(), false)?;
+    let models = with_archive(&archive_path, |archive| {
+        Ok(files
+            .into_iter()
+            .filter(|file| {
+                let lower = file.name.to_ascii_lowercase();
+                lower.ends_with(".mdx") || lower.ends_with(".mdl")
+            })
+            .map(|file| {
+                let data = read_archive_file(archive, &file.name);
+                (file.name, data)
+            })
+            .collect::<Vec<_>>())
+    })?;
+
+    let results = run_in_worker_pool(|| {
+        models
+            .into_par_iter()
+            .map(|(name, data)| {
+                let issues = match data {
+                    Ok(data) => match parse_model_data(data, false) {
+                        Ok(model) => mdx_tools::validate_model(&model),
+                        Err(e) => vec![ValidationIssue::error(format!("解析失败: {}", e))],
+                    },
+                    Err(e) => vec![ValidationIssue::error(format!("读取失败: {}", e))],
+                };
+                (name, issues)
+            })
+            .collect()
+    });
+
+    Ok(results)
+}
+
+/// 只返回模型的动画序列（名称、区间、速度、循环、稀有度与各序列包围盒），不解析几何数据
+#[tauri::command]
+fn get_mdx_sequences(mdx_data: Vec<u8>) -> Result<Vec<mdx_parser::Sequence>, AppError> {
+    MdxParser::new(mdx_data)
+        .and_then(|mut parser| parser.parse_sequence_list())
+        .map_err(AppError::MdxParse)
+}
+
+/// 计算指定动画中根骨骼的位移 [dx, dy, dz]，用于判断预览时原地循环还是移动模型
+#[tauri::command]
+fn get_root_motion(mdx_data: Vec<u8>, sequence_name: String) -> Result<Vec<f32>, AppError> {
+    let model = parse_mdx(mdx_data)?;
+    let sequence = model
+        .sequences
+        .iter()
+        .find(|s| s.name.eq_ignore_ascii_case(&sequence_name))
+        .ok_or_else(|| AppError::Unsupported(format!("找不到动画: {}", sequence_name)))?;
+    Ok(mdx_animation::root_motion(&model, sequence.interval).to_vec())
+}
+
+/// 去掉模型的序列动画，生成只有单个伪序列的静态模型（全局序列保留）
+#[tauri::command]
+fn make_static(mdx_data: Vec<u8>) -> Result<Vec<u8>, AppError> {
+    mdx_tools::make_static(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 估算模型的绘制调用次数（geoset 实际使用的不同材质数）
+#[tauri::command]
+fn get_draw_call_estimate(mdx_data: Vec<u8>) -> Result<usize, AppError> {
+    mdx_tools::get_draw_call_estimate(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 获取模型碰撞体的预览几何（长方体角点、球心与半径、圆柱端点与半径）
+#[tauri::command]
+fn get_collision_geometry(mdx_data: Vec<u8>) -> Result<Vec<mdx_tools::CollisionGeom>, AppError> {
+    mdx_tools::get_collision_geometry(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 把可替换纹理 id 解析为 `team_color` 队伍下的标准 BLP 路径
+#[tauri::command]
+fn resolve_replaceable_texture(id: u32, team_color: u32) -> Option<String> {
+    mdx_parser::resolve_replaceable_texture(id, team_color)
+}
+
+/// 返回模型的头像镜头（名称含 "Portrait" 者优先，否则为第一个镜头），没有镜头时为 None
+#[tauri::command]
+fn get_mdx_portrait_camera(mdx_data: Vec<u8>) -> Result<Option<mdx_parser::Camera>, AppError> {
+    let model = parse_mdx(mdx_data)?;
+    Ok(mdx_tools::find_portrait_camera(&model).cloned())
+}
+
+/// 列出每个全局序列的时长及引用它的轨道
+#[tauri::command]
+fn get_global_sequence_usage(mdx_data: Vec<u8>) -> Result<Vec<mdx_tools::GlobalSeqUsage>, AppError> {
+    mdx_tools::get_global_sequence_usage(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 获取 geoset × 序列 的可见性矩阵（根据 GEOA 透明度轨道）
+#[tauri::command]
+fn get_geoset_visibility_matrix(mdx_data: Vec<u8>) -> Result<Vec<Vec<bool>>, AppError> {
+    mdx_tools::get_geoset_visibility_matrix(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 把模型平移到几何中心位于原点，返回新的 MDX 数据与平移量
+#[tauri::command]
+fn recenter_model(mdx_data: Vec<u8>) -> Result<(Vec<u8>, [f32; 3]), AppError> {
+    mdx_tools::recenter_model(&mdx_data).map_err(AppError::MdxParse)
+}
+
+/// 获取骨骼（object_id）对 geoset 各顶点的影响权重，用于权重热力图
+#[tauri::command]
+fn get_bone_weight_heatmap(mdx_data: Vec<u8>, geoset_index: usize, bone_index: u32) -> Result<Vec<f32>, AppError> {
+    mdx_tools::get_bone_weight_heatmap(&mdx_data, geoset_index, bone_index).map_err(AppError::MdxParse)
+}
+
+/// 列出预览渲染器暂不支持、但模型中存在的特性（粒子、丝带、纹理动画、灯光、相机）
+#[tauri::command]
+fn get_preview_limitations(mdx_data: Vec<u8>) -> Result<Vec<String>, AppError> {
+    mdx_tools::get_preview_limitations(&mdx_data).map_err(AppError::MdxParse)
+}
+
+// UV 线框图最大边长（像素）
+const MAX_UV_LAYOUT_SIZE: u32 = 4096;
+
+/// 把 geoset 的 UV 展开画成 `size`×`size` 的透明线框图，返回 PNG 数据
+#[tauri::command]
+fn render_uv_layout(mdx_data: Vec<u8>, geoset_index: usize, size: u32) -> Result<Vec<u8>, AppError> {
+    if size == 0 || size > MAX_UV_LAYOUT_SIZE {
+        return Err(AppError::Unsupported(format!("图像尺寸必须在 1 到 {} 之间", MAX_UV_LAYOUT_SIZE)));
+    }
+    let img = mdx_tools::render_uv_layout(&mdx_data, geoset_index, size).map_err(AppError::MdxParse)?;
+    blp_handler::encode_png(&img).map_err(AppError::Other)
+}
+
+/// 估算模型引用的纹理显存占用（字节），同一纹理只计算一次，档案中找不到的纹理忽略
+#[tauri::command]
+fn get_texture_memory(archive_path: String, mdx_data: Vec<u8>) -> Result<u64, AppError> {
+    let model = parse_mdx(mdx_data)?;
+
+    // 档案读取是串行的，先读出所有纹理再并行解析
+    let mut seen = std::collections::HashSet::new();
+    let blobs = with_archive(&archive_path, |archive| {
+        Ok(model
+            .textures
+            .iter()
+            .filter(|texture| !texture.path.is_empty() && seen.insert(texture.path.to_ascii_lowercase()))
+            .filter_map(|texture| read_archive_file(archive, &texture.path).ok())
+            .collect::<Vec<_>>())
+    })?;
+
+    run_in_worker_pool(|| {
+        blobs
+            .par_iter()
+            .map(|blp_data| blp_handler::estimate_gpu_memory(blp_data))
+            .sum::<Result<u64, String>>()
+    })
+    .map_err(AppError::BlpDecode)
+}
+
+/// 从地图档案中读取并解析 war3map.w3i
+fn load_map_info(archive_path: &str) -> Result<w3i_parser::MapInfo, AppError> {
+    let data = read_mpq_file(archive_path.to_string(), "war3map.w3i".to_string())?;
+    w3i_parser::W3iParser::new(data).parse().map_err(AppError::MapParse)
+}
+
+// 物体编辑器数据文件，格式版本 3 起（1.33）支持多等级数据集
+const OBJECT_DATA_FILES: &[&str] = &[
+    "war3map.w3u",
+    "war3map.w3t",
+    "war3map.w3b",
+    "war3map.w3d",
+    "war3map.w3a",
+    "war3map.w3h",
+    "war3map.w3q",
+];
+
+/// 推断地图可运行的最低游戏版本（如 "1.32+"），综合 w3i、物体数据与 MPQ 档案头的格式版本
+#[tauri::command]
+fn get_required_game_version(archive_path: String) -> Result<String, AppError> {
+    let (mut required, w3i) = with_archive(&archive_path, |archive| {
+        // 扩展格式（v2 及以上，含 v4 表）的档案头需要 1.27+
+        let mut required = match archive.header().format_version {
+            wow_mpq::FormatVersion::V1 => (1, 0),
+            _ => (1, 27),
+        };
+
+        for name in OBJECT_DATA_FILES {
+            // 物体数据文件是可选的
+            let Ok(data) = read_archive_file(archive, name) else {
+                continue;
+            };
+            if let Some(version) = data.get(..4) {
+                if u32::from_le_bytes([version[0], version[1], version[2], version[3]]) >= 3 {
+                    required = required.max((1, 33));
+                }
+            }
+        }
+
+        Ok((required, read_archive_file(archive, "war3map.w3i")?))
+    })?;
+
+    let info = w3i_parser::W3iParser::new(w3i)
+        .parse()
+        .map_err(AppError::MapParse)?;
+    required = required.max(w3i_parser::required_game_version(info.format_version));
+
+    Ok(format!("{}.{:02}+", required.0, required.1))
+}
+
+/// 读取地图信息（名称、作者、描述、建议玩家、可玩区域、地形主题、玩家与队伍）
+#[tauri::command]
+fn read_map_info(archive_path: String) -> Result<w3i_parser::MapInfo, AppError> {
+    load_map_info(&archive_path)
+}
+
+/// 获取地图镜头可视范围 [min_x, min_y, max_x, max_y]
+#[tauri::command]
+fn get_camera_bounds(archive_path: String) -> Result<[f32; 4], AppError> {
+    Ok(load_map_info(&archive_path)?.camera_rect())
+}
+
+/// 获取地图的队伍定义（名称、同盟/共享视野标志、成员玩家）
+#[tauri::command]
+fn get_map_forces(archive_path: String) -> Result<Vec<w3i_parser::Force>, AppError> {
+    Ok(load_map_info(&archive_path)?.forces)
+}
+
+// 每个地形格子的边长（游戏单位）
+const TILE_SIZE: f32 = 128.0;
+
+/// 地图尺寸：地形格子数、可玩区域格子数与世界坐标范围
+#[derive(serde::Serialize)]
+struct MapDimensions {
+    width: u32,  // 地形格子数（w3e 地形点数 - 1）
+    height: u32,
+    playable_width: u32,
+    playable_height: u32,
+    world_bounds: [f32; 4], // [min_x, min_y, max_x, max_y]
+}
+
+/// 获取地图尺寸（格子与世界坐标），用于地形预览与小地图坐标换算
+#[tauri::command]
+fn get_map_dimensions(archive_path: String) -> Result<MapDimensions, AppError> {
+    let data = read_mpq_file(archive_path.clone(), "war3map.w3e".to_string())?;
+    let terrain = w3e_parser::W3eParser::new(data)
+        .parse_header()
+        .map_err(AppError::MapParse)?;
+    let info = load_map_info(&archive_path)?;
+
+    let width = terrain.width.saturating_sub(1);
+    let height = terrain.height.saturating_sub(1);
+    let [min_x, min_y] = terrain.center_offset;
+    Ok(MapDimensions {
+        width,
+        height,
+        playable_width: info.playable_width,
+        playable_height: info.playable_height,
+        world_bounds: [
+            min_x,
+            min_y,
+            min_x + width as f32 * TILE_SIZE,
+            min_y + height as f32 * TILE_SIZE,
+        ],
+    })
+}
+
+/// 获取地图的地形主题名称（如 "Lordaeron Summer"）
+#[tauri::command]
+fn get_map_tileset(archive_path: String) -> Result<String, AppError> {
+    let data = read_mpq_file(archive_path, "war3map.w3e".to_string())?;
+    let header = w3e_parser::W3eParser::new(data)
+        .parse_header()
+        .map_err(AppError::MapParse)?;
+    Ok(w3e_parser::tileset_name(header.tileset).to_string())
+}
+
+// 小地图最大边长（像素）
+const MAX_MINIMAP_SIZE: u32 = 2048;
+
+/// 根据地形数据生成 `size`×`size` 的小地图 PNG（用于缺少 war3mapMap.blp 的地图）
+#[tauri::command]
+fn generate_minimap(archive_path: String, size: u32) -> Result<Vec<u8>, AppError> {
+    if size == 0 || size > MAX_MINIMAP_SIZE {
+        return Err(AppError::Unsupported(format!("小地图尺寸必须在 1 到 {} 之间", MAX_MINIMAP_SIZE)));
+    }
+    let data = read_mpq_file(archive_path, "war3map.w3e".to_string())?;
+    let terrain = w3e_parser::W3eParser::new(data)
+        .parse()
+        .map_err(AppError::MapParse)?;
+    blp_handler::encode_png(&minimap::render_minimap(&terrain, size)).map_err(AppError::Other)
+}
+
+/// 获取地图脚本信息：脚本文件、语言（JASS/Lua）、行数以及是否疑似混淆
+#[tauri::command]
+fn get_map_script_info(archive_path: String) -> Result<map_script::ScriptInfo, AppError> {
+    with_archive(&archive_path, |archive| {
+        map_script::SCRIPT_FILES
+            .iter()
+            .find_map(|file| {
+                let data = read_archive_file(archive, file).ok()?;
+                Some(map_script::analyze_script(file, &data))
+            })
+            .ok_or_else(|| "地图中没有找到脚本文件".to_string())
+    })
 }
 
 /// 获取当前用户名 (用于 KKWE 路径检测)
 #[tauri::command]
-fn get_username() -> Result<String, String> {
+fn get_username() -> Result<String, AppError> {
     std::env::var("USERNAME")
         .or_else(|_| std::env::var("USER"))
-        .map_err(|e| format!("无法获取用户名: {}", e))
+        .map_err(|e| AppError::Other(format!("无法获取用户名: {}", e)))
 }
 
-/// 使用 KKWE 启动器启动 War3 地图
+// 判断 War3 安装目录时查找的文件（1.27 及以前的 MPQ、各版本主程序、重制版的 _retail_ 目录）
+const WAR3_DIR_MARKERS: &[&str] = &[
+    "war3.mpq",
+    "War3.exe",
+    "Warcraft III.exe",
+    "Frozen Throne.exe",
+    "_retail_",
+];
+
+/// 查找可能的 War3 安装目录：环境变量 WARCRAFT3_PATH、注册表与常见安装位置，找不到时返回空列表
 #[tauri::command]
-fn launch_kkwe(launcher_path: String, map_path: String) -> Result<u32, String> {
+fn detect_war3_path() -> Result<Vec<String>, AppError> {
+    let mut candidates: Vec<String> = Vec::new();
+    if let Ok(path) = std::env::var("WARCRAFT3_PATH") {
+        candidates.push(path);
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use std::process::Command;
+
+        // reg query 输出形如 "    InstallPath    REG_SZ    C:\Warcraft III"
+        for key in [
+            r"HKCU\Software\Blizzard Entertainment\Warcraft III",
+            r"HKLM\SOFTWARE\Blizzard Entertainment\Warcraft III",
+            r"HKLM\SOFTWARE\WOW6432Node\Blizzard Entertainment\Warcraft III",
+        ] {
+            let Ok(output) = Command::new("reg").args(["query", key]).output() else {
+                continue;
+            };
+            for line in String::from_utf8_lossy(&output.stdout).lines() {
+                let line = line.trim();
+                if line.starts_with("InstallPath") {
+                    if let Some((_, value)) = line.split_once("REG_SZ") {
+                        candidates.push(value.trim().to_string());
+                    }
+                }
+            }
+        }
+
+        for drive in ["C:", "D:", "E:"] {
+            for dir in [
+                r"\Program Files (x86)\Warcraft III",
+                r"\Program Files\Warcraft III",
+                r"\Warcraft III",
+            ] {
+                candidates.push(format!("{}{}", drive, dir));
+            }
+        }
+    }
+
+    let mut found: Vec<String> = Vec::new();
+    for candidate in candidates {
+        let dir = std::path::Path::new(candidate.trim_end_matches(['\\', '/']));
+        let is_war3 = WAR3_DIR_MARKERS.iter().any(|marker| dir.join(marker).exists());
+        let path = dir.to_string_lossy().to_string();
+        if is_war3 && !found.iter().any(|existing| existing.eq_ignore_ascii_case(&path)) {
+            found.push(path);
+        }
+    }
+    Ok(found)
+}
+
+/// 把地图路径代入启动参数：替换参数中出现的占位符，没有占位符（或未出现）时追加到末尾
+fn launcher_args(args: Vec<String>, map_path: &str, map_arg_placeholder: Option<&str>) -> Vec<String> {
+    let mut substituted = false;
+    let mut args: Vec<String> = args
+        .into_iter()
+        .map(|arg| match map_arg_placeholder {
+            Some(placeholder) if !placeholder.is_empty() && arg.contains(placeholder) => {
+                substituted = true;
+                arg.replace(placeholder, map_path)
+            }
+            _ => arg,
+        })
+        .collect();
+    if !substituted {
+        args.push(map_path.to_string());
+    }
+    args
+}
+
+/// 使用任意启动器（W3Champions、Sharpcraft 等）启动地图，返回启动器进程的 PID
+///
+/// `wait_exit_code` 为 true 时等待启动器退出并返回其退出码（KKWE 以退出码返回 War3.exe 的 PID）
+#[tauri::command]
+fn launch_map(
+    launcher_path: String,
+    args: Vec<String>,
+    map_path: String,
+    map_arg_placeholder: Option<String>,
+    wait_exit_code: Option<bool>,
+) -> Result<u32, AppError> {
     use std::process::Command;
-    
+
     let mut child = Command::new(&launcher_path)
-        .args(&["-launchwar3", "-loadfile", &map_path])
+        .args(launcher_args(args, &map_path, map_arg_placeholder.as_deref()))
         .spawn()
-        .map_err(|e| format!("启动 KKWE 失败: {}", e))?;
-    
-    // 等待启动器退出，其退出码就是War3.exe的PID
+        .map_err(|e| AppError::ProcessSpawn(format!("启动 {} 失败: {}", launcher_path, e)))?;
+    if !wait_exit_code.unwrap_or(false) {
+        return Ok(child.id());
+    }
+
     match child.wait() {
         Ok(status) => {
             if let Some(exit_code) = status.code() {
                 Ok(exit_code as u32)
             } else {
-                Err("启动器进程被信号终止".to_string())
+                Err(AppError::Other("启动器进程被信号终止".to_string()))
             }
         }
-        Err(e) => Err(format!("等待启动器退出失败: {}", e))
+        Err(e) => Err(AppError::Other(format!("等待启动器退出失败: {}", e)))
     }
 }
 
+// KKWE 启动参数，{map} 替换为地图路径
+const KKWE_ARGS: &[&str] = &["-launchwar3", "-loadfile", "{map}"];
+const KKWE_MAP_PLACEHOLDER: &str = "{map}";
+
+/// 使用 KKWE 启动器启动 War3 地图，返回 War3.exe 的 PID
+#[tauri::command]
+fn launch_kkwe(launcher_path: String, map_path: String) -> Result<u32, AppError> {
+    launch_map(
+        launcher_path,
+        KKWE_ARGS.iter().map(|arg| arg.to_string()).collect(),
+        map_path,
+        Some(KKWE_MAP_PLACEHOLDER.to_string()),
+        Some(true),
+    )
+}
+
 /// 检查进程是否存在
 #[tauri::command]
 fn is_process_running(pid: u32) -> bool {
@@ -228,9 +1719,41 @@ fn is_process_running(pid: u32) -> bool {
     false
 }
 
+// 进程监视的轮询间隔
+const PROCESS_WATCH_INTERVAL: std::time::Duration = std::time::Duration::from_millis(1000);
+
+// 应用退出时置位，通知进程监视线程停止
+static PROCESS_WATCH_CANCELLED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+/// 在后台监视进程，退出时发送 `war3-exited` 事件（载荷为 PID）
+///
+/// 被监视的不是本进程的子进程，拿不到退出码，只报告退出本身。
+#[tauri::command]
+async fn watch_process(window: tauri::Window, pid: u32) -> Result<(), AppError> {
+    use std::sync::atomic::Ordering;
+    use tauri::Emitter;
+
+    if cfg!(not(target_os = "windows")) {
+        return Err(AppError::Unsupported("仅支持 Windows 平台".to_string()));
+    }
+
+    std::thread::spawn(move || {
+        while is_process_running(pid) {
+            if PROCESS_WATCH_CANCELLED.load(Ordering::Relaxed) {
+                return;
+            }
+            std::thread::sleep(PROCESS_WATCH_INTERVAL);
+        }
+        if !PROCESS_WATCH_CANCELLED.load(Ordering::Relaxed) {
+            let _ = window.emit("war3-exited", pid);
+        }
+    });
+    Ok(())
+}
+
 /// 结束指定进程
 #[tauri::command]
-fn kill_process(pid: u32) -> Result<(), String> {
+fn kill_process(pid: u32) -> Result<(), AppError> {
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
@@ -238,23 +1761,23 @@ fn kill_process(pid: u32) -> Result<(), String> {
         let output = Command::new("taskkill")
             .args(&["/F", "/PID", &pid.to_string()])
             .output()
-            .map_err(|e| format!("结束进程失败: {}", e))?;
+            .map_err(|e| AppError::ProcessSpawn(format!("结束进程失败: {}", e)))?;
         
         if output.status.success() {
             Ok(())
         } else {
             let stderr = String::from_utf8_lossy(&output.stderr);
-            Err(format!("taskkill执行失败: {}", stderr))
+            Err(AppError::Other(format!("taskkill执行失败: {}", stderr)))
         }
     }
     
     #[cfg(not(target_os = "windows"))]
-    Err("仅支持 Windows 平台".to_string())
+    Err(AppError::Unsupported("仅支持 Windows 平台".to_string()))
 }
 
 /// 使用管理员权限结束指定进程（通过PowerShell提升权限）
 #[tauri::command]
-fn kill_process_elevated(pid: u32) -> Result<(), String> {
+fn kill_process_elevated(pid: u32) -> Result<(), AppError> {
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
@@ -268,7 +1791,7 @@ fn kill_process_elevated(pid: u32) -> Result<(), String> {
         let output = Command::new("powershell")
             .args(&["-NoProfile", "-Command", &ps_command])
             .output()
-            .map_err(|e| format!("PowerShell执行失败: {}", e))?;
+            .map_err(|e| AppError::ProcessSpawn(format!("PowerShell执行失败: {}", e)))?;
         
         // PowerShell的Start-Process -Verb RunAs会弹出UAC提示
         if output.status.success() {
@@ -278,15 +1801,15 @@ fn kill_process_elevated(pid: u32) -> Result<(), String> {
             
             // 检查是否是用户取消了UAC
             if stderr.contains("canceled") || stderr.contains("取消") {
-                Err("用户取消了权限提升".to_string())
+                Err(AppError::UserCancelled("用户取消了权限提升".to_string()))
             } else {
-                Err(format!("管理员权限结束进程失败: {}", stderr))
+                Err(AppError::Other(format!("管理员权限结束进程失败: {}", stderr)))
             }
         }
     }
     
     #[cfg(not(target_os = "windows"))]
-    Err("仅支持 Windows 平台".to_string())
+    Err(AppError::Unsupported("仅支持 Windows 平台".to_string()))
 }
 
 /// 检查War3.exe进程是否正在运行
@@ -314,7 +1837,7 @@ fn is_war3_running() -> bool {
 
 /// 结束所有War3.exe进程
 #[tauri::command]
-fn kill_war3_processes() -> Result<(), String> {
+fn kill_war3_processes() -> Result<(), AppError> {
     #[cfg(target_os = "windows")]
     {
         use std::process::Command;
@@ -322,7 +1845,7 @@ fn kill_war3_processes() -> Result<(), String> {
         let output = Command::new("taskkill")
             .args(&["/F", "/IM", "war3.exe"])
             .output()
-            .map_err(|e| format!("结束War3进程失败: {}", e))?;
+            .map_err(|e| AppError::ProcessSpawn(format!("结束War3进程失败: {}", e)))?;
         
         if output.status.success() {
             Ok(())
@@ -332,42 +1855,115 @@ fn kill_war3_processes() -> Result<(), String> {
             if stderr.contains("找不到") || stderr.contains("not found") {
                 Ok(())
             } else {
-                Err(format!("taskkill执行失败: {}", stderr))
+                Err(AppError::Other(format!("taskkill执行失败: {}", stderr)))
             }
         }
     }
     
     #[cfg(not(target_os = "windows"))]
-    Err("仅支持 Windows 平台".to_string())
+    Err(AppError::Unsupported("仅支持 Windows 平台".to_string()))
+}
+
+/// 内置模板地图
+struct TemplateMap {
+    id: &'static str, // 即 public/maps 下的文件名
+    name: &'static str,
+    war3_version: &'static str,
+    data: &'static [u8],
+}
+
+// 嵌入的模板地图数据 (编译时包含)，新增模板时把地图放入 public/maps 并在此登记
+const TEMPLATE_MAPS: &[TemplateMap] = &[
+    TemplateMap {
+        id: "test.1.27.w3x",
+        name: "热重载测试地图 (KKWE)",
+        war3_version: "1.27",
+        data: include_bytes!("../../public/maps/test.1.27.w3x"),
+    },
+    TemplateMap {
+        id: "test.1.36.w3x",
+        name: "热重载测试地图 (重制版 Lua)",
+        war3_version: "1.36",
+        data: include_bytes!("../../public/maps/test.1.36.w3x"),
+    },
+];
+
+/// 模板地图信息（不含地图数据）
+#[derive(serde::Serialize)]
+struct TemplateMapInfo {
+    id: String,
+    name: String,
+    war3_version: String,
+}
+
+/// 列出内置的模板地图
+#[tauri::command]
+fn list_template_maps() -> Result<Vec<TemplateMapInfo>, AppError> {
+    Ok(TEMPLATE_MAPS
+        .iter()
+        .map(|map| TemplateMapInfo {
+            id: map.id.to_string(),
+            name: map.name.to_string(),
+            war3_version: map.war3_version.to_string(),
+        })
+        .collect())
+}
+
+/// 检查相对路径只由普通目录名组成（不含 ..、根目录或盘符），防止写到 Maps 目录之外
+///
+/// 非 Windows 平台不会把 `C:foo` 解析为盘符，因此额外拒绝含 `:` 的名称（Windows 文件名本就不允许）
+fn is_plain_relative_path(path: &std::path::Path) -> bool {
+    use std::path::Component;
+
+    path.components().next().is_some()
+        && path.components().all(|component| match component {
+            Component::Normal(name) => !name.to_string_lossy().contains(':'),
+            _ => false,
+        })
 }
 
 /// 复制内置模板地图到War3目录
+///
+/// 默认写入 `Maps/Test/test.w3x`，`subdir`（相对 Maps）与 `out_name` 可改变目标位置。
 #[tauri::command]
-fn extract_template_map(_app_handle: tauri::AppHandle, war3_path: String, map_name: String) -> Result<String, String> {
+fn extract_template_map(
+    _app_handle: tauri::AppHandle,
+    war3_path: String,
+    map_name: String,
+    subdir: Option<String>,
+    out_name: Option<String>,
+) -> Result<String, AppError> {
     use std::fs;
     use std::path::Path;
-    
-    // 检查是否是支持的模板地图
-    if map_name != "test.1.27.w3x" {
-        return Err(format!("不支持的模板地图: {}", map_name));
+
+    let subdir = subdir.unwrap_or_else(|| "Test".to_string());
+    if !is_plain_relative_path(Path::new(&subdir)) {
+        return Err(AppError::Unsupported(format!("无效的子目录: {}", subdir)));
+    }
+    let out_name = out_name.unwrap_or_else(|| "test.w3x".to_string());
+    if !is_plain_relative_path(Path::new(&out_name)) || Path::new(&out_name).components().count() != 1 {
+        return Err(AppError::Unsupported(format!("无效的文件名: {}", out_name)));
     }
     
-    // 嵌入的模板地图数据 (编译时包含)
-    const TEMPLATE_MAP_DATA: &[u8] = include_bytes!("../../public/maps/test.1.27.w3x");
+    // 按 id 查找模板地图
+    let template = TEMPLATE_MAPS
+        .iter()
+        .find(|map| map.id == map_name)
+        .ok_or_else(|| AppError::Unsupported(format!("不支持的模板地图: {}", map_name)))?;
     
-    // 目标路径: War3目录/Maps/Test/
-    let target_dir = Path::new(&war3_path).join("Maps").join("Test");
+    // 目标路径: War3目录/Maps/{subdir}/
+    let target_dir = Path::new(&war3_path).join("Maps").join(&subdir);
     
     // 创建目标目录
     fs::create_dir_all(&target_dir)
-        .map_err(|e| format!("创建目标目录失败: {}", e))?;
+        .map_err(|e| AppError::Io(format!("创建目标目录失败: {}", e)))?;
     
     // 目标文件路径
-    let target_file = target_dir.join("test.w3x");
+    let target_file = target_dir.join(&out_name);
     
     // 写入文件
-    fs::write(&target_file, TEMPLATE_MAP_DATA)
-        .map_err(|e| format!("写入地图文件失败: {}", e))?;
+    fs::write(&target_file, template.data)
+        .map_err(|e| AppError::Io(format!("写入地图文件失败: {}", e)))?;
     
     // 返回目标文件路径
     Ok(target_file.to_string_lossy().to_string())
@@ -382,25 +1978,275 @@ pub fn run() {
         .plugin(tauri_plugin_updater::Builder::new().build())
         .invoke_handler(tauri::generate_handler![
             greet,
+            set_worker_threads,
             load_mpq_archive,
+            load_mpq_archive_with_listfile,
+            list_mpq_files_filtered,
             read_mpq_file,
+            read_mpq_files,
+            read_from_mpq_chain,
+            list_mpq_chain,
+            read_mpq_file_by_hash,
+            file_exists_in_mpq,
+            get_mpq_file_meta,
+            list_ui_files,
+            parse_fdf_from_mpq,
+            save_fdf,
+            parse_toc_from_mpq,
             clear_mpq_cache,
+            set_mpq_cache_limit,
+            get_mpq_cache_stats,
+            close_mpq_archive,
+            write_mpq_file,
+            create_mpq,
             decode_blp_to_png,
+            convert_blp,
             decode_blp_to_rgba,
+            decode_blp_ex,
             get_blp_file_info,
             decode_blp_mipmap_level,
+            decode_blp_all_mipmaps,
+            encode_rgba_to_blp,
+            is_mask_texture,
+            extract_texture_frame,
+            verify_blp_roundtrip,
+            get_blp_mipmap_raw,
+            get_blp_palette,
+            extract_blp_jpeg,
+            decode_blp_thumbnail,
+            decode_blp_batch,
+            build_icon_atlas,
+            preview_layer_blend,
             parse_mdx_file,
+            parse_model_file,
             parse_mdx_from_mpq,
             parse_mdx_from_file,
+            serialize_mdx,
+            remap_textures,
+            repair_sequence_extents,
+            check_attachment_names,
+            get_mdx_summary,
+            get_mdx_timeline,
+            mdx_to_text_summary,
+            export_mdx_to_obj,
+            export_mdx_to_mdl,
+            export_mdx_to_gltf,
+            find_degenerate_faces,
+            check_face_indices,
+            list_stock_dependencies,
+            package_model,
+            extract_mpq_files,
+            find_shared_textures,
+            validate_mdx,
+            get_mdx_sequences,
+            validate_all_models,
+            get_root_motion,
+            make_static,
+            recenter_model,
+            get_draw_call_estimate,
+            get_collision_geometry,
+            get_mdx_portrait_camera,
+            resolve_replaceable_texture,
+            get_global_sequence_usage,
+            get_geoset_visibility_matrix,
+            render_uv_layout,
+            get_bone_weight_heatmap,
+            get_preview_limitations,
+            get_texture_memory,
+            read_map_info,
+            get_camera_bounds,
+            get_required_game_version,
+            get_map_tileset,
+            get_map_dimensions,
+            get_map_forces,
+            generate_minimap,
+            get_map_script_info,
             get_username,
+            detect_war3_path,
+            launch_map,
             launch_kkwe,
             is_process_running,
+            watch_process,
             kill_process,
             kill_process_elevated,
             is_war3_running,
             kill_war3_processes,
+            list_template_maps,
             extract_template_map
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while running tauri application")
+        .run(|_app, event| {
+            if let tauri::RunEvent::Exit = event {
+                PROCESS_WATCH_CANCELLED.store(true, std::sync::atomic::Ordering::Relaxed);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_model_data_detects_format() {
+        let mdl = b"Version {\n    FormatVersion 800,\n}\nModel \"Box\" {\n}\n".to_vec();
+        let model = parse_model_data(mdl.clone(), false).unwrap();
+        assert_eq!(model.version, 800);
+        assert_eq!(model.name, "Box");
+
+        let mut mdx = b"MDLXVERS".to_vec();
+        mdx.extend_from_slice(&4u32.to_le_bytes());
+        mdx.extend_from_slice(&900u32.to_le_bytes());
+        assert_eq!(parse_model_data(mdx.clone(), false).unwrap().version, 900);
+
+        // is_text 强制按 MDL 解析
+        assert_eq!(parse_model_data(mdl, true).unwrap().name, "Box");
+        assert_ne!(parse_model_data(mdx, true).unwrap().version, 900);
+    }
+
+    #[test]
+    fn test_is_plain_relative_path() {
+        use std::path::Path;
+
+        assert!(is_plain_relative_path(Path::new("Test")));
+        assert!(is_plain_relative_path(Path::new("a/b")));
+        assert!(!is_plain_relative_path(Path::new("")));
+        assert!(!is_plain_relative_path(Path::new("..")));
+        assert!(!is_plain_relative_path(Path::new("a/../../b")));
+        assert!(!is_plain_relative_path(Path::new("/etc")));
+        assert!(!is_plain_relative_path(Path::new("C:foo")));
+        assert!(!is_plain_relative_path(Path::new("C:\\Windows")));
+    }
+
+    #[test]
+    fn test_package_model() {
+        let dir = std::env::temp_dir().join(format!("package_model_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let model = mdx_parser::MdxModel {
+            version: 800,
+            textures: ["Textures\\Gutz.blp", "war3mapImported\\Skin.blp"]
+                .iter()
+                .map(|path| mdx_parser::Texture::new(0, path.to_string(), 0))
+                .collect(),
+            ..Default::default()
+        };
+        let mdx = mdx_writer::write_model(&model).unwrap();
+        let archive = dir.join("map.w3x");
+        wow_mpq::ArchiveBuilder::new()
+            .add_file_data(mdx.clone(), "war3mapImported\\Hero.mdx")
+            .add_file_data(b"skin".to_vec(), "war3mapImported\\Skin.blp")
+            .build(&archive)
+            .unwrap();
+
+        // 只导出模型与导入的纹理，游戏自带的 Textures\Gutz.blp 被跳过
+        let out = dir.join("out");
+        let written = package_model(
+            archive.to_string_lossy().to_string(),
+            "war3mapImported\\Hero.mdx".to_string(),
+            out.to_string_lossy().to_string(),
+        )
+        .unwrap();
+        assert_eq!(written.len(), 2);
+        let imported = out.join("war3mapImported");
+        assert_eq!(std::fs::read(imported.join("Hero.mdx")).unwrap(), mdx);
+        assert_eq!(std::fs::read(imported.join("Skin.blp")).unwrap(), b"skin");
+        assert!(!out.join("Textures").exists());
+
+        // 档案内路径不能越出输出目录
+        assert!(write_archive_file(&out.to_string_lossy(), "..\\evil.txt", b"").is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_template_maps_match_target_version() {
+        let dir = std::env::temp_dir().join(format!("template_maps_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        for (id, required, language) in [
+            ("test.1.27.w3x", "1.07+", "jass"),
+            ("test.1.36.w3x", "1.32+", "lua"),
+        ] {
+            let template = TEMPLATE_MAPS.iter().find(|map| map.id == id).unwrap();
+            let path = dir.join(id);
+            std::fs::write(&path, template.data).unwrap();
+            let path = path.to_string_lossy().to_string();
+
+            assert_eq!(get_required_game_version(path.clone()).unwrap(), required);
+            assert_eq!(get_map_script_info(path.clone()).unwrap().language, language);
+            let info = load_map_info(&path).unwrap();
+            assert_eq!(info.players.len(), 1);
+            assert_eq!(info.camera_rect(), [-2816.0, -3328.0, 2816.0, 2816.0]);
+        }
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_map_parse_errors_are_typed() {
+        let dir = std::env::temp_dir().join(format!("map_parse_error_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = dir.join("broken.w3x");
+        wow_mpq::ArchiveBuilder::new()
+            .add_file_data(25u32.to_le_bytes().to_vec(), "war3map.w3i")
+            .add_file_data(b"W3E!".to_vec(), "war3map.w3e")
+            .build(&archive)
+            .unwrap();
+        let path = archive.to_string_lossy().to_string();
+
+        assert!(matches!(read_map_info(path.clone()), Err(AppError::MapParse(_))));
+        assert!(matches!(get_required_game_version(path.clone()), Err(AppError::MapParse(_))));
+        assert!(matches!(get_map_tileset(path.clone()), Err(AppError::MapParse(_))));
+        let missing = dir.join("missing.w3x").to_string_lossy().to_string();
+        assert!(matches!(read_map_info(missing), Err(AppError::MpqOpen(_))));
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_launch_map_waits_for_exit_code() {
+        // 地图路径追加在末尾，作为 sh -c 的 $0
+        let args = vec!["-c".to_string(), "exit 7".to_string()];
+        let launch = |wait| launch_map("sh".to_string(), args.clone(), "test.w3x".to_string(), None, wait);
+        assert_eq!(launch(Some(true)).unwrap(), 7);
+        assert!(launch(None).unwrap() > 0);
+
+        let missing = launch_kkwe("/nonexistent/YDWEConfig.exe".to_string(), "test.w3x".to_string());
+        assert!(matches!(missing, Err(AppError::ProcessSpawn(_))));
+    }
+
+    #[test]
+    fn test_mpq_chain_priority_and_missing_archives() {
+        let dir = std::env::temp_dir().join(format!("mpq_chain_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let base = dir.join("base.mpq");
+        let patch = dir.join("patch.mpq");
+        wow_mpq::ArchiveBuilder::new()
+            .add_file_data(b"base".to_vec(), "a.txt")
+            .add_file_data(b"only base".to_vec(), "b.txt")
+            .build(&base)
+            .unwrap();
+        wow_mpq::ArchiveBuilder::new()
+            .add_file_data(b"patch".to_vec(), "a.txt")
+            .build(&patch)
+            .unwrap();
+        let path = |p: &std::path::Path| p.to_string_lossy().to_string();
+        let missing = path(&dir.join("missing.mpq"));
+        let chain = vec![path(&base), missing.clone(), path(&patch)];
+
+        // 靠后的档案优先，无法打开的档案被跳过
+        let read = |name: &str| read_from_mpq_chain(chain.clone(), name.to_string());
+        assert_eq!(read("a.txt").unwrap(), b"patch");
+        assert_eq!(read("b.txt").unwrap(), b"only base");
+        assert!(read("c.txt").is_err());
+
+        let files = list_mpq_chain(chain.clone()).unwrap();
+        let archive_of = |name: &str| files.iter().find(|f| f.name == name).map(|f| f.archive.clone());
+        assert_eq!(archive_of("a.txt"), Some(path(&patch)));
+        assert_eq!(archive_of("b.txt"), Some(path(&base)));
+        assert!(list_mpq_chain(vec![missing]).is_err());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
 }
@@ -0,0 +1,74 @@
+// 地图脚本（war3map.j / war3map.lua）分析
+
+use serde::Serialize;
+
+// 按优先级排列的脚本文件位置（部分地图把脚本放在 scripts\ 目录下）
+pub const SCRIPT_FILES: &[&str] = &[
+    "war3map.j",
+    "scripts\\war3map.j",
+    "war3map.lua",
+    "scripts\\war3map.lua",
+];
+// 单行超过该长度视为被压缩成一行
+const MAX_NORMAL_LINE_LENGTH: usize = 10_000;
+// 不可打印字符占比超过该值视为被加密或混淆
+const MAX_CONTROL_CHAR_RATIO: f32 = 0.01;
+
+/// 地图脚本信息
+#[derive(Debug, Serialize, Clone)]
+pub struct ScriptInfo {
+    pub file: String,
+    pub language: String, // "jass" 或 "lua"
+    pub size: usize,
+    pub line_count: usize,
+    pub obfuscated: bool,
+}
+
+/// 分析脚本内容：语言由文件扩展名决定，混淆判断基于超长行与不可打印字符
+pub fn analyze_script(file: &str, data: &[u8]) -> ScriptInfo {
+    let language = if file.to_ascii_lowercase().ends_with(".lua") {
+        "lua"
+    } else {
+        "jass"
+    };
+
+    // 末尾换行不算新的一行
+    let body = data.strip_suffix(b"\n").unwrap_or(data);
+    let line_lengths: Vec<usize> = if data.is_empty() {
+        Vec::new()
+    } else {
+        body.split(|&b| b == b'\n').map(|line| line.len()).collect()
+    };
+    let longest_line = line_lengths.iter().copied().max().unwrap_or(0);
+    let control_chars = data
+        .iter()
+        .filter(|&&b| b < 0x20 && !matches!(b, b'\t' | b'\r' | b'\n'))
+        .count();
+    let control_ratio = control_chars as f32 / data.len().max(1) as f32;
+
+    ScriptInfo {
+        file: file.to_string(),
+        language: language.to_string(),
+        size: data.len(),
+        line_count: line_lengths.len(),
+        obfuscated: longest_line > MAX_NORMAL_LINE_LENGTH || control_ratio > MAX_CONTROL_CHAR_RATIO,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_analyze_script() {
+        let jass =
+            b"globals\nendglobals\nfunction main takes nothing returns nothing\nendfunction\n";
+        let info = analyze_script("war3map.j", jass);
+        assert_eq!(info.language, "jass");
+        assert_eq!(info.line_count, 4);
+        assert!(!info.obfuscated);
+
+        let packed = vec![b'x'; MAX_NORMAL_LINE_LENGTH + 1];
+        assert!(analyze_script("war3map.lua", &packed).obfuscated);
+    }
+}
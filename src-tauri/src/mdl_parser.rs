@@ -0,0 +1,938 @@
+// MDL（文本格式模型）解析器，生成与 MDX 解析器相同的 MdxModel
+// 格式: `Keyword ["名称"] [数量...] { ... }`，块内条目以逗号分隔，块本身结束一个条目；
+// 关键帧写作 `帧: 值,`，`//` 开始行注释。粒子发射器等暂未建模的块被忽略
+
+use crate::mdx_parser::{
+    calculate_bounds, split_primitive_groups, Attachment, Bone, CollisionShape, Face, Geoset,
+    GeosetAnimation, Keyframe, Layer, Material, MdxModel, Node, Normal, Sequence, Texture,
+    TextureAnimation, Track, Vertex, COLLISION_BOX, COLLISION_CYLINDER, COLLISION_PLANE,
+    COLLISION_SPHERE, NODE_ATTACHMENT, NODE_BONE, NODE_COLLISION_SHAPE, NO_PARENT,
+    NodeFlags, PRIMITIVE_TRIANGLES, UV,
+};
+
+// 节点标志（与 MDX 中的节点 flags 相同）
+const NODE_FLAGS: &[(&str, u32)] = &[
+    ("Billboarded", 0x8),
+    ("BillboardedLockX", 0x10),
+    ("BillboardedLockY", 0x20),
+    ("BillboardedLockZ", 0x40),
+    ("CameraAnchored", 0x80),
+];
+// `DontInherit { Translation, Rotation, Scaling }`
+const DONT_INHERIT_FLAGS: &[(&str, u32)] =
+    &[("Translation", 0x1), ("Rotation", 0x2), ("Scaling", 0x4)];
+
+const FILTER_MODES: &[&str] = &[
+    "None",
+    "Transparent",
+    "Blend",
+    "Additive",
+    "AddAlpha",
+    "Modulate",
+    "Modulate2x",
+];
+const SHADING_FLAGS: &[(&str, u32)] = &[
+    ("Unshaded", 0x1),
+    ("SphereEnvMap", 0x2),
+    ("TwoSided", 0x10),
+    ("Unfogged", 0x20),
+    ("NoDepthTest", 0x40),
+    ("NoDepthSet", 0x80),
+];
+const MATERIAL_FLAGS: &[(&str, u32)] = &[
+    ("ConstantColor", 0x1),
+    ("SortPrimsFarZ", 0x10),
+    ("FullResolution", 0x20),
+];
+const INTERPOLATIONS: &[&str] = &["DontInterp", "Linear", "Hermite", "Bezier"];
+
+// GEOA flags
+const GEOSET_ANIM_DROP_SHADOW: u32 = 0x1;
+const GEOSET_ANIM_COLOR: u32 = 0x2;
+// Geoset selection flags
+const UNSELECTABLE: u32 = 0x4;
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Word(String),
+    Str(String),
+    Comma,
+    Colon,
+    Open,
+    Close,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    line: usize,
+}
+
+#[derive(Debug, Clone)]
+enum Atom {
+    Word(String),
+    Str(String),
+    Colon,
+    Block(Vec<Item>),
+}
+
+/// 块内的一个条目：关键字 + 参数，`static` 前缀单独记录
+#[derive(Debug, Clone)]
+struct Item {
+    key: String, // 匿名条目（如顶点 `{ x, y, z }`）为空
+    is_static: bool,
+    args: Vec<Atom>,
+    line: usize,
+}
+
+impl Item {
+    fn is(&self, key: &str) -> bool {
+        self.key.eq_ignore_ascii_case(key)
+    }
+
+    fn block(&self) -> &[Item] {
+        match self.args.last() {
+            Some(Atom::Block(items)) => items,
+            _ => &[],
+        }
+    }
+
+    fn has_block(&self) -> bool {
+        matches!(self.args.last(), Some(Atom::Block(_)))
+    }
+
+    fn string(&self) -> String {
+        self.args
+            .iter()
+            .find_map(|atom| match atom {
+                Atom::Str(value) => Some(value.clone()),
+                _ => None,
+            })
+            .unwrap_or_default()
+    }
+
+    fn word(&self) -> Option<&str> {
+        self.args.iter().find_map(|atom| match atom {
+            Atom::Word(value) => Some(value.as_str()),
+            _ => None,
+        })
+    }
+
+    fn float(&self) -> Result<f32, String> {
+        let word = self
+            .word()
+            .ok_or_else(|| format!("Expected number after {} on line {}", self.key, self.line))?;
+        parse_float(word, self.line)
+    }
+
+    /// 整数参数；-1 按 u32 回绕为 0xFFFFFFFF，`None`/`Multiple` 等非数字同样视为 0xFFFFFFFF
+    fn int(&self) -> u32 {
+        self.word()
+            .and_then(|word| word.parse::<i64>().ok())
+            .map_or(u32::MAX, |value| value as u32)
+    }
+
+    fn vector(&self) -> Result<Vec<f32>, String> {
+        numbers(self.block())
+    }
+}
+
+fn parse_float(word: &str, line: usize) -> Result<f32, String> {
+    word.parse::<f32>()
+        .map_err(|_| format!("Invalid number '{}' on line {}", word, line))
+}
+
+/// `{ 1, 2, 3 }` 形式的数字列表
+fn numbers(items: &[Item]) -> Result<Vec<f32>, String> {
+    items
+        .iter()
+        .map(|item| parse_float(&item.key, item.line))
+        .collect()
+}
+
+fn vec_n<const N: usize>(item: &Item) -> Result<[f32; N], String> {
+    let values = item.vector()?;
+    values.try_into().map_err(|values: Vec<f32>| {
+        format!(
+            "Expected {} values for {} on line {}, got {}",
+            N,
+            item.key,
+            item.line,
+            values.len()
+        )
+    })
+}
+
+fn to_vertex(item: &Item) -> Result<Vertex, String> {
+    let [x, y, z] = vec_n::<3>(item)?;
+    Ok(Vertex { x, y, z })
+}
+
+fn flags_of(items: &[Item], table: &[(&str, u32)]) -> u32 {
+    items
+        .iter()
+        .filter(|item| item.args.is_empty())
+        .filter_map(|item| table.iter().find(|(name, _)| item.is(name)))
+        .fold(0, |flags, (_, bit)| flags | bit)
+}
+
+/// MDL 文本解析器
+pub struct MdlParser {
+    text: String,
+}
+
+impl MdlParser {
+    pub fn new(data: Vec<u8>) -> Result<Self, String> {
+        Ok(MdlParser {
+            text: String::from_utf8_lossy(&data).into_owned(),
+        })
+    }
+
+    pub fn parse(&mut self) -> Result<MdxModel, String> {
+        let tokens = tokenize(&self.text)?;
+        let mut pos = 0;
+        let items = parse_items(&tokens, &mut pos)?;
+        if pos < tokens.len() {
+            return Err(format!("Unexpected '}}' on line {}", tokens[pos].line));
+        }
+
+        let mut model = MdxModel::default();
+        for item in &items {
+            match item.key.as_str() {
+                "Version" => {
+                    if let Some(version) = item.block().iter().find(|i| i.is("FormatVersion")) {
+                        model.version = version.int();
+                    }
+                }
+                "Model" => {
+                    model.name = item.string();
+                    for entry in item.block() {
+                        match entry.key.as_str() {
+                            "MinimumExtent" => model.overall_bounds.min = to_vertex(entry)?,
+                            "MaximumExtent" => model.overall_bounds.max = to_vertex(entry)?,
+                            _ => {}
+                        }
+                    }
+                }
+                "Sequences" => {
+                    for anim in item.block().iter().filter(|i| i.is("Anim")) {
+                        model.sequences.push(parse_sequence(anim)?);
+                    }
+                }
+                "GlobalSequences" => {
+                    for duration in item.block().iter().filter(|i| i.is("Duration")) {
+                        model.global_sequences.push(duration.int());
+                    }
+                }
+                "Textures" => {
+                    for bitmap in item.block().iter().filter(|i| i.is("Bitmap")) {
+                        model.textures.push(parse_texture(bitmap));
+                    }
+                }
+                "Materials" => {
+                    for material in item.block().iter().filter(|i| i.is("Material")) {
+                        model.materials.push(parse_material(material)?);
+                    }
+                }
+                "TextureAnims" => {
+                    for anim in item.block().iter().filter(|i| i.is("TVertexAnim")) {
+                        model
+                            .texture_animations
+                            .push(parse_texture_animation(anim)?);
+                    }
+                }
+                "Geoset" => model.geosets.push(parse_geoset(item)?),
+                "GeosetAnim" => model.geoset_animations.push(parse_geoset_animation(item)?),
+                "Bone" => {
+                    let node = parse_node(item, NODE_BONE)?;
+                    let field = |key: &str| {
+                        item.block()
+                            .iter()
+                            .find(|i| i.is(key))
+                            .map_or(u32::MAX, Item::int)
+                    };
+                    model.bones.push(Bone {
+                        node,
+                        geoset_id: field("GeosetId"),
+                        geoset_animation_id: field("GeosetAnimId"),
+                    });
+                }
+                "Helper" => model.helpers.push(parse_node(item, 0)?),
+                "Attachment" => {
+                    let node = parse_node(item, NODE_ATTACHMENT)?;
+                    let block = item.block();
+                    model.attachments.push(Attachment {
+                        node,
+                        path: block
+                            .iter()
+                            .find(|i| i.is("Path"))
+                            .map(Item::string)
+                            .unwrap_or_default(),
+                        attachment_id: block
+                            .iter()
+                            .find(|i| i.is("AttachmentID"))
+                            .map_or(0, Item::int),
+                    });
+                }
+                "PivotPoints" => {
+                    for point in item.block() {
+                        model.pivot_points.push(to_vertex(point)?);
+                    }
+                }
+                "CollisionShape" => model.collision_shapes.push(parse_collision_shape(item)?),
+                _ => {}
+            }
+        }
+
+        model.update_bounds();
+        Ok(model)
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            '\n' => {
+                line += 1;
+                chars.next();
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '/' => {
+                chars.next();
+                if chars.peek() != Some(&'/') {
+                    return Err(format!("Unexpected '/' on line {}", line));
+                }
+                while chars.peek().is_some_and(|&c| c != '\n') {
+                    chars.next();
+                }
+            }
+            '"' => {
+                chars.next();
+                let mut value = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\n') | None => {
+                            return Err(format!("Unterminated string on line {}", line))
+                        }
+                        Some(c) => value.push(c),
+                    }
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Str(value),
+                    line,
+                });
+            }
+            ',' | ':' | '{' | '}' => {
+                chars.next();
+                let kind = match c {
+                    ',' => TokenKind::Comma,
+                    ':' => TokenKind::Colon,
+                    '{' => TokenKind::Open,
+                    _ => TokenKind::Close,
+                };
+                tokens.push(Token { kind, line });
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, ',' | ':' | '{' | '}' | '"') {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(Token {
+                    kind: TokenKind::Word(word),
+                    line,
+                });
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// 把收集到的记号组成条目（空条目如块后多余的逗号被忽略）
+fn finish(atoms: &mut Vec<Atom>, line: usize, items: &mut Vec<Item>) {
+    if atoms.is_empty() {
+        return;
+    }
+    let mut atoms = std::mem::take(atoms);
+    let is_static = matches!(atoms.first(), Some(Atom::Word(w)) if w == "static");
+    if is_static {
+        atoms.remove(0);
+    }
+    let key = match atoms.first() {
+        Some(Atom::Word(word)) => {
+            let key = word.clone();
+            atoms.remove(0);
+            key
+        }
+        _ => String::new(),
+    };
+    items.push(Item {
+        key,
+        is_static,
+        args: atoms,
+        line,
+    });
+}
+
+/// 读取条目直到遇到 `}` 或输入结束；条目以逗号或其末尾的块结束
+fn parse_items(tokens: &[Token], pos: &mut usize) -> Result<Vec<Item>, String> {
+    let mut items = Vec::new();
+    let mut atoms: Vec<Atom> = Vec::new();
+    let mut line = 0;
+
+    while let Some(token) = tokens.get(*pos) {
+        if atoms.is_empty() {
+            line = token.line;
+        }
+        *pos += 1;
+        match &token.kind {
+            TokenKind::Word(word) => atoms.push(Atom::Word(word.clone())),
+            TokenKind::Str(value) => atoms.push(Atom::Str(value.clone())),
+            TokenKind::Colon => atoms.push(Atom::Colon),
+            TokenKind::Comma => finish(&mut atoms, line, &mut items),
+            TokenKind::Open => {
+                let block = parse_items(tokens, pos)?;
+                if tokens.get(*pos).map(|t| &t.kind) != Some(&TokenKind::Close) {
+                    return Err(format!("Unclosed block starting on line {}", token.line));
+                }
+                *pos += 1;
+                atoms.push(Atom::Block(block));
+                // `帧: { x, y, z }` 之后的逗号也结束条目；其余块直接结束条目
+                if !matches!(atoms.get(atoms.len().wrapping_sub(2)), Some(Atom::Colon)) {
+                    finish(&mut atoms, line, &mut items);
+                }
+            }
+            TokenKind::Close => {
+                *pos -= 1;
+                break;
+            }
+        }
+    }
+    finish(&mut atoms, line, &mut items);
+    Ok(items)
+}
+
+/// 解析动画轨道块：插值方式、GlobalSeqId、`帧: 值` 关键帧及其 InTan/OutTan
+fn parse_track<T>(
+    item: &Item,
+    convert: impl Fn(Vec<f32>) -> Option<T>,
+) -> Result<Track<T>, String> {
+    let mut track = Track {
+        interpolation: 0,
+        global_sequence_id: -1,
+        keys: Vec::new(),
+    };
+    let value_of = |entry: &Item, args: &[Atom]| -> Result<T, String> {
+        let values = match args.last() {
+            Some(Atom::Block(items)) => numbers(items)?,
+            Some(Atom::Word(word)) => vec![parse_float(word, entry.line)?],
+            _ => Vec::new(),
+        };
+        convert(values).ok_or_else(|| format!("Invalid keyframe value on line {}", entry.line))
+    };
+
+    for entry in item.block() {
+        if let Some(index) = INTERPOLATIONS.iter().position(|name| entry.is(name)) {
+            track.interpolation = index as u32;
+        } else if entry.is("GlobalSeqId") {
+            track.global_sequence_id = entry.int() as i32;
+        } else if entry.is("InTan") || entry.is("OutTan") {
+            let value = value_of(entry, &entry.args)?;
+            let key = track
+                .keys
+                .last_mut()
+                .ok_or_else(|| format!("Tangent before keyframe on line {}", entry.line))?;
+            if entry.is("InTan") {
+                key.in_tan = Some(value);
+            } else {
+                key.out_tan = Some(value);
+            }
+        } else if matches!(entry.args.first(), Some(Atom::Colon)) {
+            let frame = entry
+                .key
+                .parse::<i64>()
+                .map_err(|_| format!("Invalid frame '{}' on line {}", entry.key, entry.line))?;
+            track.keys.push(Keyframe {
+                frame: frame as u32,
+                value: value_of(entry, &entry.args[1..])?,
+                in_tan: None,
+                out_tan: None,
+            });
+        }
+    }
+    Ok(track)
+}
+
+fn float_track<const N: usize>(item: &Item) -> Result<Track<[f32; N]>, String> {
+    parse_track(item, |values| values.try_into().ok())
+}
+
+// 属性的静态值或动画轨道，二者只有一个为 Some
+type StaticOrTrack<const N: usize> = (Option<[f32; N]>, Option<Track<[f32; N]>>);
+
+/// 可为静态值或动画轨道的属性（如 `static Alpha 1` 与 `Alpha 2 { ... }`）
+fn static_or_track<const N: usize>(item: &Item) -> Result<StaticOrTrack<N>, String> {
+    let has_count = item.args.iter().any(|atom| matches!(atom, Atom::Word(_)));
+    if !item.has_block() {
+        Ok((Some([item.float()?; N]), None))
+    } else if item.is_static || !has_count {
+        // `static Color { b, g, r }` 形式的静态向量
+        Ok((Some(vec_n::<N>(item)?), None))
+    } else {
+        Ok((None, Some(float_track::<N>(item)?)))
+    }
+}
+
+fn parse_sequence(item: &Item) -> Result<Sequence, String> {
+    let mut sequence = Sequence {
+        name: item.string(),
+        interval: [0, 0],
+        move_speed: 0.0,
+        non_looping: false,
+        rarity: 0.0,
+        sync_point: 0,
+        bounds_radius: 0.0,
+        minimum_extent: Vertex::default(),
+        maximum_extent: Vertex::default(),
+    };
+    for entry in item.block() {
+        match entry.key.as_str() {
+            "Interval" => {
+                let [start, end] = vec_n::<2>(entry)?;
+                sequence.interval = [start as u32, end as u32];
+            }
+            "NonLooping" => sequence.non_looping = true,
+            "MoveSpeed" => sequence.move_speed = entry.float()?,
+            "Rarity" => sequence.rarity = entry.float()?,
+            "SyncPoint" => sequence.sync_point = entry.int(),
+            "BoundsRadius" => sequence.bounds_radius = entry.float()?,
+            "MinimumExtent" => sequence.minimum_extent = to_vertex(entry)?,
+            "MaximumExtent" => sequence.maximum_extent = to_vertex(entry)?,
+            _ => {}
+        }
+    }
+    Ok(sequence)
+}
+
+fn parse_texture(item: &Item) -> Texture {
+    let block = item.block();
+    Texture::new(
+        block
+            .iter()
+            .find(|i| i.is("ReplaceableId"))
+            .map_or(0, Item::int),
+        block
+            .iter()
+            .find(|i| i.is("Image"))
+            .map(Item::string)
+            .unwrap_or_default(),
+        flags_of(block, &[("WrapWidth", 0x1), ("WrapHeight", 0x2)]),
+    )
+}
+
+fn parse_material(item: &Item) -> Result<Material, String> {
+    let block = item.block();
+    let mut material = Material {
+        priority_plane: 0,
+        flags: flags_of(block, MATERIAL_FLAGS),
+        shader: String::new(),
+        layers: Vec::new(),
+    };
+    for entry in block {
+        match entry.key.as_str() {
+            "PriorityPlane" => material.priority_plane = entry.int(),
+            "Shader" => material.shader = entry.string(),
+            "Layer" => material.layers.push(parse_layer(entry)?),
+            _ => {}
+        }
+    }
+    Ok(material)
+}
+
+fn parse_layer(item: &Item) -> Result<Layer, String> {
+    let block = item.block();
+    let mut layer = Layer {
+        filter_mode: 0,
+        shading_flags: flags_of(block, SHADING_FLAGS),
+        texture_id: 0,
+        texture_animation_id: u32::MAX,
+        coord_id: 0,
+        alpha: 1.0,
+        alpha_track: None,
+        texture_track: None,
+        emissive_gain: None,
+        fresnel_color: None,
+        fresnel_opacity: None,
+        fresnel_team_color: None,
+    };
+    for entry in block {
+        match entry.key.as_str() {
+            "FilterMode" => {
+                let mode = entry.word().unwrap_or("None");
+                layer.filter_mode = FILTER_MODES
+                    .iter()
+                    .position(|name| name.eq_ignore_ascii_case(mode))
+                    .unwrap_or(0) as u32;
+            }
+            "TextureID" if entry.is_static || !entry.has_block() => layer.texture_id = entry.int(),
+            "TextureID" => {
+                let track = parse_track(entry, |values| values.first().map(|&v| v as u32))?;
+                layer.texture_id = track.keys.first().map_or(0, |key| key.value);
+                layer.texture_track = Some(track);
+            }
+            "TVertexAnimId" => layer.texture_animation_id = entry.int(),
+            "CoordId" => layer.coord_id = entry.int(),
+            "Alpha" => {
+                let (alpha, track) = static_or_track::<1>(entry)?;
+                if let Some([alpha]) = alpha {
+                    layer.alpha = alpha;
+                }
+                layer.alpha_track = track;
+            }
+            "EmissiveGain" => layer.emissive_gain = static_or_track::<1>(entry)?.0.map(|[v]| v),
+            "FresnelColor" => layer.fresnel_color = static_or_track::<3>(entry)?.0,
+            "FresnelOpacity" => layer.fresnel_opacity = static_or_track::<1>(entry)?.0.map(|[v]| v),
+            "FresnelTeamColor" => {
+                layer.fresnel_team_color = static_or_track::<1>(entry)?.0.map(|[v]| v)
+            }
+            _ => {}
+        }
+    }
+    Ok(layer)
+}
+
+fn parse_texture_animation(item: &Item) -> Result<TextureAnimation, String> {
+    let mut animation = TextureAnimation::default();
+    for entry in item.block() {
+        match entry.key.as_str() {
+            "Translation" => animation.translation = Some(float_track::<3>(entry)?),
+            "Rotation" => animation.rotation = Some(float_track::<4>(entry)?),
+            "Scaling" => animation.scaling = Some(float_track::<3>(entry)?),
+            _ => {}
+        }
+    }
+    Ok(animation)
+}
+
+fn parse_geoset(item: &Item) -> Result<Geoset, String> {
+    let mut geoset = Geoset::default();
+    for entry in item.block() {
+        match entry.key.as_str() {
+            "Vertices" => {
+                for vertex in entry.block() {
+                    geoset.vertices.push(to_vertex(vertex)?);
+                }
+            }
+            "Normals" => {
+                for normal in entry.block() {
+                    let [x, y, z] = vec_n::<3>(normal)?;
+                    geoset.normals.push(Normal { x, y, z });
+                }
+            }
+            // 每个 TVertices 块是一层 UV
+            "TVertices" => {
+                let mut uvs = Vec::new();
+                for uv in entry.block() {
+                    let [u, v] = vec_n::<2>(uv)?;
+                    uvs.push(UV { u, v });
+                }
+                geoset.uvs.push(uvs);
+            }
+            "VertexGroup" => {
+                geoset.vertex_groups = numbers(entry.block())?
+                    .into_iter()
+                    .map(|group| group as u8)
+                    .collect();
+            }
+            "Faces" => {
+                let (mut indices, mut types, mut counts) = (Vec::new(), Vec::new(), Vec::new());
+                for group in entry.block().iter().filter(|g| g.is("Triangles")) {
+                    let mut group_indices = Vec::new();
+                    for list in group.block() {
+                        group_indices.extend(list.vector()?.into_iter().map(|i| i as u16));
+                    }
+                    types.push(PRIMITIVE_TRIANGLES);
+                    counts.push(group_indices.len() as u32);
+                    indices.extend(group_indices);
+                }
+                let mut faces: Vec<Face> = Vec::new();
+                geoset.primitive_groups =
+                    split_primitive_groups(&indices, &types, &counts, &mut faces);
+                geoset.faces = faces;
+            }
+            "Groups" => {
+                for matrices in entry.block().iter().filter(|g| g.is("Matrices")) {
+                    let bones = matrices.vector()?;
+                    geoset.matrix_groups.push(bones.len() as u32);
+                    geoset
+                        .matrix_indices
+                        .extend(bones.into_iter().map(|bone| bone as u32));
+                }
+            }
+            "MaterialID" => geoset.material_id = entry.int(),
+            "SelectionGroup" => geoset.selection_group = entry.int(),
+            "Unselectable" => geoset.selection_flags |= UNSELECTABLE,
+            _ => {}
+        }
+    }
+    geoset.bounds = calculate_bounds(&geoset.vertices);
+    Ok(geoset)
+}
+
+fn parse_geoset_animation(item: &Item) -> Result<GeosetAnimation, String> {
+    let mut animation = GeosetAnimation {
+        alpha: 1.0,
+        flags: 0,
+        color: [1.0, 1.0, 1.0],
+        geoset_id: 0,
+        alpha_track: None,
+        color_track: None,
+    };
+    for entry in item.block() {
+        match entry.key.as_str() {
+            "DropShadow" => animation.flags |= GEOSET_ANIM_DROP_SHADOW,
+            "GeosetId" => animation.geoset_id = entry.int(),
+            "Alpha" => {
+                let (alpha, track) = static_or_track::<1>(entry)?;
+                if let Some([alpha]) = alpha {
+                    animation.alpha = alpha;
+                }
+                animation.alpha_track = track;
+            }
+            "Color" => {
+                animation.flags |= GEOSET_ANIM_COLOR;
+                let (color, track) = static_or_track::<3>(entry)?;
+                if let Some(color) = color {
+                    animation.color = color;
+                }
+                animation.color_track = track;
+            }
+            _ => {}
+        }
+    }
+    Ok(animation)
+}
+
+/// 节点公共部分：名称、ObjectId、Parent、标志与变换轨道
+fn parse_node(item: &Item, type_flag: u32) -> Result<Node, String> {
+    let block = item.block();
+    let mut node = Node {
+        name: item.string(),
+        object_id: 0,
+        parent_id: NO_PARENT,
+        flags: type_flag | flags_of(block, NODE_FLAGS),
+        decoded_flags: NodeFlags::default(),
+        translation: None,
+        rotation: None,
+        scaling: None,
+    };
+    for entry in block {
+        match entry.key.as_str() {
+            "DontInherit" => node.flags |= flags_of(entry.block(), DONT_INHERIT_FLAGS),
+            "ObjectId" => node.object_id = entry.int(),
+            "Parent" => node.parent_id = entry.int(),
+            "Translation" => node.translation = Some(float_track::<3>(entry)?),
+            "Rotation" => node.rotation = Some(float_track::<4>(entry)?),
+            "Scaling" => node.scaling = Some(float_track::<3>(entry)?),
+            _ => {}
+        }
+    }
+    node.decoded_flags = NodeFlags::from_bits(node.flags);
+    Ok(node)
+}
+
+fn parse_collision_shape(item: &Item) -> Result<CollisionShape, String> {
+    let block = item.block();
+    let shape_type = [
+        ("Box", COLLISION_BOX),
+        ("Plane", COLLISION_PLANE),
+        ("Sphere", COLLISION_SPHERE),
+        ("Cylinder", COLLISION_CYLINDER),
+    ]
+    .iter()
+    .find(|(name, _)| block.iter().any(|entry| entry.is(name)))
+    .map_or(COLLISION_BOX, |&(_, shape)| shape);
+
+    let mut vertices = Vec::new();
+    if let Some(entry) = block.iter().find(|entry| entry.is("Vertices")) {
+        for vertex in entry.block() {
+            vertices.push(to_vertex(vertex)?);
+        }
+    }
+    let radius = match block.iter().find(|entry| entry.is("BoundsRadius")) {
+        Some(entry) => entry.float()?,
+        None => 0.0,
+    };
+
+    Ok(CollisionShape {
+        node: parse_node(item, NODE_COLLISION_SHAPE)?,
+        shape_type,
+        vertices,
+        radius,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"// sample
+Version {
+    FormatVersion 800,
+}
+Model "Box" {
+    NumGeosets 1,
+}
+Sequences 1 {
+    Anim "Stand" {
+        Interval { 0, 1000 },
+        NonLooping,
+        Rarity 0.5,
+    }
+}
+Textures 1 {
+    Bitmap {
+        Image "Textures\Box.blp",
+        WrapWidth,
+    }
+}
+Materials 1 {
+    Material {
+        Layer {
+            FilterMode Blend,
+            TwoSided,
+            static TextureID 0,
+            Alpha 2 {
+                Linear,
+                0: 1,
+                500: 0,
+            }
+        }
+    }
+}
+Geoset {
+    Vertices 3 {
+        { 0, 0, 0 },
+        { 1, 0, 0 },
+        { 0, 1, 2 },
+    }
+    TVertices 3 {
+        { 0, 0 },
+        { 1, 0 },
+        { 0, 1 },
+    }
+    VertexGroup {
+        0, 0, 0,
+    }
+    Faces 1 3 {
+        Triangles {
+            { 0, 1, 2 },
+        }
+    }
+    Groups 1 1 {
+        Matrices { 0 },
+    }
+    MaterialID 0,
+    SelectionGroup 0,
+}
+Bone "Root" {
+    ObjectId 0,
+    GeosetId 0,
+    GeosetAnimId None,
+    Rotation 2 {
+        Hermite,
+        GlobalSeqId 0,
+        0: { 0, 0, 0, 1 },
+            InTan { 0, 0, 0, 1 },
+            OutTan { 0, 0, 0, 1 },
+        100: { 0, 0, 1, 0 },
+            InTan { 0, 0, 1, 0 },
+            OutTan { 0, 0, 1, 0 },
+    }
+}
+PivotPoints 1 {
+    { 0, 0, 0 },
+}
+"#;
+
+    #[test]
+    fn test_parse_mdl() {
+        let model = MdlParser::new(SAMPLE.as_bytes().to_vec())
+            .and_then(|mut parser| parser.parse())
+            .unwrap();
+
+        assert_eq!(model.version, 800);
+        assert_eq!(model.name, "Box");
+        assert_eq!(model.sequences[0].interval, [0, 1000]);
+        assert!(model.sequences[0].non_looping);
+        assert_eq!(model.textures[0].path, "Textures\\Box.blp");
+        assert_eq!(model.textures[0].flags, 0x1);
+
+        let layer = &model.materials[0].layers[0];
+        assert_eq!(layer.filter_mode, 2);
+        assert_eq!(layer.shading_flags, 0x10);
+        assert_eq!(layer.alpha_track.as_ref().unwrap().keys.len(), 2);
+
+        let geoset = &model.geosets[0];
+        assert_eq!(geoset.vertices.len(), 3);
+        assert_eq!(geoset.faces[0].indices, [0, 1, 2]);
+        assert_eq!(geoset.matrix_indices, vec![0]);
+        assert_eq!(geoset.bounds.max.z, 2.0);
+
+        let bone = &model.bones[0];
+        assert_eq!(bone.node.parent_id, NO_PARENT);
+        assert_eq!(bone.geoset_animation_id, u32::MAX);
+        let rotation = bone.node.rotation.as_ref().unwrap();
+        assert_eq!(rotation.interpolation, 2);
+        assert_eq!(rotation.global_sequence_id, 0);
+        assert_eq!(rotation.keys[1].value, [0.0, 0.0, 1.0, 0.0]);
+        assert!(rotation.keys[1].out_tan.is_some());
+        assert_eq!(model.pivot_points.len(), 1);
+    }
+
+    #[test]
+    fn test_malformed_input_is_rejected() {
+        let parse = |text: &str| MdlParser::new(text.as_bytes().to_vec()).unwrap().parse();
+        let track = |body: &str| {
+            format!(
+                "Bone \"Root\" {{\n    Rotation 1 {{\n        Linear,\n{}\n    }}\n}}\n",
+                body
+            )
+        };
+
+        // 缺少 `}` 与多余的 `}`
+        let unclosed = parse("Model \"Box\" {\n    NumGeosets 1,\n").unwrap_err();
+        assert!(
+            unclosed.contains("Unclosed block starting on line 1"),
+            "{}",
+            unclosed
+        );
+        let extra = parse("Model \"Box\" {\n}\n}\n").unwrap_err();
+        assert!(extra.contains("line 3"), "{}", extra);
+
+        // 关键帧的帧号不是整数
+        let frame = parse(&track("        1.5: { 0, 0, 0, 1 },")).unwrap_err();
+        assert!(frame.contains("Invalid frame '1.5'"), "{}", frame);
+
+        // 切线出现在任何关键帧之前
+        let tangent = parse(&track(
+            "        InTan { 0, 0, 0, 1 },\n        0: { 0, 0, 0, 1 },",
+        ))
+        .unwrap_err();
+        assert!(tangent.contains("Tangent before keyframe"), "{}", tangent);
+
+        // 关键帧值的分量数不对
+        assert!(parse(&track("        0: { 0, 0, 1 },")).is_err());
+        assert!(parse(&track("        0: { 0, 0, 0, 1 },")).is_ok());
+    }
+}
@@ -0,0 +1,736 @@
+// MDL（文本格式模型）写出工具
+// 从 MdxModel 生成 MDL 文本，块与关键字与 mdl_parser 读取的保持一致，便于 diff 与手工编辑。
+// 以下数据暂不写出，输出开头有注释说明：
+// - mdl_parser 尚不读取的灯光、粒子发射器（PREM/PRE2）、镜头
+// - SKIN 骨骼权重（写出的模型回退到 VertexGroup/Matrices 蒙皮）
+// - 非三角形列表的图元分组（只有三角形列表被展开为 faces）
+// - mdx_parser 读取时就丢弃的切线（TANG）、geoset LOD、节点可见性轨道、事件对象与带状发射器
+
+use crate::mdx_parser::{
+    BoundingBox, CollisionShape, Geoset, GeosetAnimation, Layer, Material, MdxModel, Node,
+    Sequence, Texture, TextureAnimation, Track, Vertex, COLLISION_BOX, COLLISION_CYLINDER,
+    COLLISION_PLANE, COLLISION_SPHERE, NO_PARENT, PRIMITIVE_TRIANGLES,
+};
+
+const DEFAULT_VERSION: u32 = 800;
+const BLEND_TIME: u32 = 150;
+
+const FILTER_MODES: &[&str] = &[
+    "None",
+    "Transparent",
+    "Blend",
+    "Additive",
+    "AddAlpha",
+    "Modulate",
+    "Modulate2x",
+];
+const SHADING_FLAGS: &[(&str, u32)] = &[
+    ("Unshaded", 0x1),
+    ("SphereEnvMap", 0x2),
+    ("TwoSided", 0x10),
+    ("Unfogged", 0x20),
+    ("NoDepthTest", 0x40),
+    ("NoDepthSet", 0x80),
+];
+const MATERIAL_FLAGS: &[(&str, u32)] = &[
+    ("ConstantColor", 0x1),
+    ("SortPrimsFarZ", 0x10),
+    ("FullResolution", 0x20),
+];
+const NODE_FLAGS: &[(&str, u32)] = &[
+    ("Billboarded", 0x8),
+    ("BillboardedLockX", 0x10),
+    ("BillboardedLockY", 0x20),
+    ("BillboardedLockZ", 0x40),
+    ("CameraAnchored", 0x80),
+];
+const DONT_INHERIT_FLAGS: &[(&str, u32)] =
+    &[("Translation", 0x1), ("Rotation", 0x2), ("Scaling", 0x4)];
+const INTERPOLATIONS: &[&str] = &["DontInterp", "Linear", "Hermite", "Bezier"];
+
+// GEOA flags
+const GEOSET_ANIM_DROP_SHADOW: u32 = 0x1;
+const GEOSET_ANIM_COLOR: u32 = 0x2;
+// Geoset selection flags
+const UNSELECTABLE: u32 = 0x4;
+
+/// 关键帧值的 MDL 写法
+trait MdlValue {
+    fn to_mdl(&self) -> String;
+}
+
+impl<const N: usize> MdlValue for [f32; N] {
+    fn to_mdl(&self) -> String {
+        if N == 1 {
+            float(self[0])
+        } else {
+            floats(*self)
+        }
+    }
+}
+
+impl MdlValue for u32 {
+    fn to_mdl(&self) -> String {
+        self.to_string()
+    }
+}
+
+/// `{ a, b, c }` 形式的列表
+fn list<T: ToString>(values: impl IntoIterator<Item = T>) -> String {
+    let values: Vec<String> = values.into_iter().map(|v| v.to_string()).collect();
+    format!("{{ {} }}", values.join(", "))
+}
+
+/// MDL 没有 NaN 与无穷大的写法：NaN 写作 0，无穷大截断为 f32 的最大有限值
+fn float(value: f32) -> String {
+    if value.is_nan() {
+        "0".to_string()
+    } else {
+        value.clamp(f32::MIN, f32::MAX).to_string()
+    }
+}
+
+fn floats(values: impl IntoIterator<Item = f32>) -> String {
+    list(values.into_iter().map(float))
+}
+
+fn vertex(v: &Vertex) -> String {
+    floats([v.x, v.y, v.z])
+}
+
+/// MDL 字符串没有转义，去掉名称中的引号与换行
+fn quoted(text: &str) -> String {
+    let text: String = text
+        .chars()
+        .filter(|c| !matches!(c, '"' | '\n' | '\r'))
+        .collect();
+    format!("\"{}\"", text)
+}
+
+/// 带缩进的文本输出
+struct MdlWriter {
+    out: String,
+    depth: usize,
+}
+
+impl MdlWriter {
+    fn line(&mut self, text: impl AsRef<str>) {
+        for _ in 0..self.depth {
+            self.out.push('\t');
+        }
+        self.out.push_str(text.as_ref());
+        self.out.push('\n');
+    }
+
+    /// 开始一个块，`header` 为块前的关键字与参数
+    fn open(&mut self, header: impl AsRef<str>) {
+        self.line(format!("{} {{", header.as_ref()));
+        self.depth += 1;
+    }
+
+    fn close(&mut self) {
+        self.depth -= 1;
+        self.line("}");
+    }
+
+    /// 按表写出已设置的标志位，每个一行
+    fn flags(&mut self, flags: u32, table: &[(&str, u32)]) {
+        for (name, bit) in table {
+            if flags & bit != 0 {
+                self.line(format!("{},", name));
+            }
+        }
+    }
+
+    fn extent(&mut self, bounds: &BoundingBox, radius: f32) {
+        self.line(format!("MinimumExtent {},", vertex(&bounds.min)));
+        self.line(format!("MaximumExtent {},", vertex(&bounds.max)));
+        self.line(format!("BoundsRadius {},", float(radius)));
+    }
+
+    fn track<T: MdlValue>(&mut self, key: &str, track: &Track<T>) {
+        self.open(format!("{} {}", key, track.keys.len()));
+        let interpolation = INTERPOLATIONS
+            .get(track.interpolation as usize)
+            .unwrap_or(&"DontInterp");
+        self.line(format!("{},", interpolation));
+        if track.global_sequence_id >= 0 {
+            self.line(format!("GlobalSeqId {},", track.global_sequence_id));
+        }
+        for key in &track.keys {
+            self.line(format!("{}: {},", key.frame, key.value.to_mdl()));
+            // Hermite/Bezier 的切线缩进一层，跟在所属关键帧之后
+            if track.interpolation >= 2 {
+                self.depth += 1;
+                if let Some(in_tan) = &key.in_tan {
+                    self.line(format!("InTan {},", in_tan.to_mdl()));
+                }
+                if let Some(out_tan) = &key.out_tan {
+                    self.line(format!("OutTan {},", out_tan.to_mdl()));
+                }
+                self.depth -= 1;
+            }
+        }
+        self.close();
+    }
+
+    /// 静态值或动画轨道（轨道优先）
+    fn static_or_track<const N: usize>(
+        &mut self,
+        key: &str,
+        value: [f32; N],
+        track: Option<&Track<[f32; N]>>,
+    ) {
+        match track {
+            Some(track) => self.track(key, track),
+            None => self.line(format!("static {} {},", key, value.to_mdl())),
+        }
+    }
+
+    fn sequence(&mut self, sequence: &Sequence) {
+        self.open(format!("Anim {}", quoted(&sequence.name)));
+        self.line(format!("Interval {},", list(sequence.interval)));
+        if sequence.non_looping {
+            self.line("NonLooping,");
+        }
+        if sequence.move_speed != 0.0 {
+            self.line(format!("MoveSpeed {},", float(sequence.move_speed)));
+        }
+        if sequence.rarity != 0.0 {
+            self.line(format!("Rarity {},", float(sequence.rarity)));
+        }
+        if sequence.sync_point != 0 {
+            self.line(format!("SyncPoint {},", sequence.sync_point));
+        }
+        let bounds = BoundingBox {
+            min: sequence.minimum_extent,
+            max: sequence.maximum_extent,
+        };
+        self.extent(&bounds, sequence.bounds_radius);
+        self.close();
+    }
+
+    fn texture(&mut self, texture: &Texture) {
+        self.open("Bitmap");
+        self.line(format!("Image {},", quoted(&texture.path)));
+        if texture.replaceable_id != 0 {
+            self.line(format!("ReplaceableId {},", texture.replaceable_id));
+        }
+        self.flags(texture.flags, &[("WrapWidth", 0x1), ("WrapHeight", 0x2)]);
+        self.close();
+    }
+
+    fn material(&mut self, material: &Material) {
+        self.open("Material");
+        self.flags(material.flags, MATERIAL_FLAGS);
+        if material.priority_plane != 0 {
+            self.line(format!("PriorityPlane {},", material.priority_plane));
+        }
+        if !material.shader.is_empty() {
+            self.line(format!("Shader {},", quoted(&material.shader)));
+        }
+        for layer in &material.layers {
+            self.layer(layer);
+        }
+        self.close();
+    }
+
+    fn layer(&mut self, layer: &Layer) {
+        self.open("Layer");
+        let filter_mode = FILTER_MODES
+            .get(layer.filter_mode as usize)
+            .unwrap_or(&"None");
+        self.line(format!("FilterMode {},", filter_mode));
+        self.flags(layer.shading_flags, SHADING_FLAGS);
+        match &layer.texture_track {
+            Some(track) => self.track("TextureID", track),
+            None => self.line(format!("static TextureID {},", layer.texture_id)),
+        }
+        if layer.texture_animation_id != u32::MAX {
+            self.line(format!("TVertexAnimId {},", layer.texture_animation_id));
+        }
+        if layer.coord_id != 0 {
+            self.line(format!("CoordId {},", layer.coord_id));
+        }
+        self.static_or_track("Alpha", [layer.alpha], layer.alpha_track.as_ref());
+        if let Some(gain) = layer.emissive_gain {
+            self.line(format!("static EmissiveGain {},", float(gain)));
+        }
+        if let Some(color) = layer.fresnel_color {
+            self.line(format!("static FresnelColor {},", floats(color)));
+        }
+        if let Some(opacity) = layer.fresnel_opacity {
+            self.line(format!("static FresnelOpacity {},", float(opacity)));
+        }
+        if let Some(team_color) = layer.fresnel_team_color {
+            self.line(format!("static FresnelTeamColor {},", team_color));
+        }
+        self.close();
+    }
+
+    fn texture_animation(&mut self, animation: &TextureAnimation) {
+        self.open("TVertexAnim");
+        if let Some(track) = &animation.translation {
+            self.track("Translation", track);
+        }
+        if let Some(track) = &animation.rotation {
+            self.track("Rotation", track);
+        }
+        if let Some(track) = &animation.scaling {
+            self.track("Scaling", track);
+        }
+        self.close();
+    }
+
+    fn geoset(&mut self, geoset: &Geoset) {
+        self.open("Geoset");
+        self.open(format!("Vertices {}", geoset.vertices.len()));
+        for v in &geoset.vertices {
+            self.line(format!("{},", vertex(v)));
+        }
+        self.close();
+        if !geoset.normals.is_empty() {
+            self.open(format!("Normals {}", geoset.normals.len()));
+            for n in &geoset.normals {
+                self.line(format!("{},", floats([n.x, n.y, n.z])));
+            }
+            self.close();
+        }
+        for uvs in &geoset.uvs {
+            self.open(format!("TVertices {}", uvs.len()));
+            for uv in uvs {
+                self.line(format!("{},", floats([uv.u, uv.v])));
+            }
+            self.close();
+        }
+        self.open("VertexGroup");
+        for group in &geoset.vertex_groups {
+            self.line(format!("{},", group));
+        }
+        self.close();
+
+        // 只有三角形列表被解析为 faces，合并写成一个 Triangles 组
+        if !geoset.faces.is_empty() {
+            let indices = geoset.faces.iter().flat_map(|face| face.indices);
+            self.open(format!("Faces 1 {}", geoset.faces.len() * 3));
+            self.open("Triangles");
+            self.line(format!("{},", list(indices)));
+            self.close();
+            self.close();
+        }
+
+        self.open(format!(
+            "Groups {} {}",
+            geoset.matrix_groups.len(),
+            geoset.matrix_indices.len()
+        ));
+        let mut bones = geoset.matrix_indices.iter();
+        for &count in &geoset.matrix_groups {
+            self.line(format!(
+                "Matrices {},",
+                list(bones.by_ref().take(count as usize))
+            ));
+        }
+        self.close();
+
+        self.extent(&geoset.bounds, geoset.bounds.radius());
+        self.line(format!("MaterialID {},", geoset.material_id));
+        self.line(format!("SelectionGroup {},", geoset.selection_group));
+        if geoset.selection_flags & UNSELECTABLE != 0 {
+            self.line("Unselectable,");
+        }
+        self.close();
+    }
+
+    fn geoset_animation(&mut self, animation: &GeosetAnimation) {
+        self.open("GeosetAnim");
+        if animation.flags & GEOSET_ANIM_DROP_SHADOW != 0 {
+            self.line("DropShadow,");
+        }
+        self.static_or_track("Alpha", [animation.alpha], animation.alpha_track.as_ref());
+        if animation.flags & GEOSET_ANIM_COLOR != 0 || animation.color_track.is_some() {
+            self.static_or_track("Color", animation.color, animation.color_track.as_ref());
+        }
+        self.line(format!("GeosetId {},", animation.geoset_id));
+        self.close();
+    }
+
+    /// 打开节点块并写出公共部分，调用方写完类型特有字段后关闭
+    fn node(&mut self, keyword: &str, node: &Node) {
+        self.open(format!("{} {}", keyword, quoted(&node.name)));
+        self.line(format!("ObjectId {},", node.object_id));
+        if node.parent_id != NO_PARENT {
+            self.line(format!("Parent {},", node.parent_id));
+        }
+        self.flags(node.flags, NODE_FLAGS);
+        let dont_inherit: Vec<&str> = DONT_INHERIT_FLAGS
+            .iter()
+            .filter(|(_, bit)| node.flags & bit != 0)
+            .map(|(name, _)| *name)
+            .collect();
+        if !dont_inherit.is_empty() {
+            self.line(format!("DontInherit {},", list(dont_inherit)));
+        }
+        if let Some(track) = &node.translation {
+            self.track("Translation", track);
+        }
+        if let Some(track) = &node.rotation {
+            self.track("Rotation", track);
+        }
+        if let Some(track) = &node.scaling {
+            self.track("Scaling", track);
+        }
+    }
+
+    fn collision_shape(&mut self, shape: &CollisionShape) {
+        self.node("CollisionShape", &shape.node);
+        let name = match shape.shape_type {
+            COLLISION_PLANE => "Plane",
+            COLLISION_SPHERE => "Sphere",
+            COLLISION_CYLINDER => "Cylinder",
+            _ => "Box",
+        };
+        self.line(format!("{},", name));
+        self.open(format!("Vertices {}", shape.vertices.len()));
+        for v in &shape.vertices {
+            self.line(format!("{},", vertex(v)));
+        }
+        self.close();
+        if shape.shape_type != COLLISION_BOX && shape.shape_type != COLLISION_PLANE {
+            self.line(format!("BoundsRadius {},", float(shape.radius)));
+        }
+        self.close();
+    }
+}
+
+/// 节点 id 字段：0xFFFFFFFF 写作 `unset`（骨骼的 GeosetId 为 `Multiple`，GeosetAnimId 为 `None`），
+/// mdl_parser 把这些非数字都读作 0xFFFFFFFF
+fn id_or(id: u32, unset: &str) -> String {
+    if id == u32::MAX {
+        unset.to_string()
+    } else {
+        id.to_string()
+    }
+}
+
+/// 把模型写为 MDL 文本
+pub fn write_mdl(model: &MdxModel) -> String {
+    let mut w = MdlWriter {
+        out: String::new(),
+        depth: 0,
+    };
+    w.line("// Exported by UI-Designer");
+    let geosets_with = |test: fn(&Geoset) -> bool| model.geosets.iter().filter(|g| test(g)).count();
+    let omitted = [
+        ("lights", model.lights.len()),
+        ("particle emitters", model.particle_emitters.len()),
+        ("particle emitters 2", model.particle_emitters2.len()),
+        ("cameras", model.cameras.len()),
+        ("geoset skin weights", geosets_with(|g| !g.skin.is_empty())),
+        (
+            "non-triangle primitive groups",
+            model
+                .geosets
+                .iter()
+                .flat_map(|g| &g.primitive_groups)
+                .filter(|group| group.primitive_type != PRIMITIVE_TRIANGLES)
+                .count(),
+        ),
+    ];
+    for (kind, count) in omitted.iter().filter(|(_, count)| *count > 0) {
+        w.line(format!(
+            "// Omitted {} {} (not supported by the MDL exporter yet)",
+            count, kind
+        ));
+    }
+    w.line("// Not kept when reading MDX: tangents, geoset LOD, visibility tracks, event objects, ribbon emitters");
+
+    let version = if model.version == 0 {
+        DEFAULT_VERSION
+    } else {
+        model.version
+    };
+    w.open("Version");
+    w.line(format!("FormatVersion {},", version));
+    w.close();
+
+    w.open(format!("Model {}", quoted(&model.name)));
+    for (key, count) in [
+        ("NumGeosets", model.geosets.len()),
+        ("NumGeosetAnims", model.geoset_animations.len()),
+        ("NumHelpers", model.helpers.len()),
+        ("NumBones", model.bones.len()),
+        ("NumAttachments", model.attachments.len()),
+    ] {
+        if count > 0 {
+            w.line(format!("{} {},", key, count));
+        }
+    }
+    w.line(format!("BlendTime {},", BLEND_TIME));
+    w.extent(&model.overall_bounds, model.overall_bounds_radius);
+    w.close();
+
+    if !model.sequences.is_empty() {
+        w.open(format!("Sequences {}", model.sequences.len()));
+        for sequence in &model.sequences {
+            w.sequence(sequence);
+        }
+        w.close();
+    }
+    if !model.global_sequences.is_empty() {
+        w.open(format!("GlobalSequences {}", model.global_sequences.len()));
+        for duration in &model.global_sequences {
+            w.line(format!("Duration {},", duration));
+        }
+        w.close();
+    }
+    if !model.textures.is_empty() {
+        w.open(format!("Textures {}", model.textures.len()));
+        for texture in &model.textures {
+            w.texture(texture);
+        }
+        w.close();
+    }
+    if !model.materials.is_empty() {
+        w.open(format!("Materials {}", model.materials.len()));
+        for material in &model.materials {
+            w.material(material);
+        }
+        w.close();
+    }
+    if !model.texture_animations.is_empty() {
+        w.open(format!("TextureAnims {}", model.texture_animations.len()));
+        for animation in &model.texture_animations {
+            w.texture_animation(animation);
+        }
+        w.close();
+    }
+    for geoset in &model.geosets {
+        w.geoset(geoset);
+    }
+    for animation in &model.geoset_animations {
+        w.geoset_animation(animation);
+    }
+    for bone in &model.bones {
+        w.node("Bone", &bone.node);
+        w.line(format!("GeosetId {},", id_or(bone.geoset_id, "Multiple")));
+        w.line(format!(
+            "GeosetAnimId {},",
+            id_or(bone.geoset_animation_id, "None")
+        ));
+        w.close();
+    }
+    for helper in &model.helpers {
+        w.node("Helper", helper);
+        w.close();
+    }
+    for attachment in &model.attachments {
+        w.node("Attachment", &attachment.node);
+        w.line(format!("AttachmentID {},", attachment.attachment_id));
+        if !attachment.path.is_empty() {
+            w.line(format!("Path {},", quoted(&attachment.path)));
+        }
+        w.close();
+    }
+    if !model.pivot_points.is_empty() {
+        w.open(format!("PivotPoints {}", model.pivot_points.len()));
+        for point in &model.pivot_points {
+            w.line(format!("{},", vertex(point)));
+        }
+        w.close();
+    }
+    for shape in &model.collision_shapes {
+        w.collision_shape(shape);
+    }
+    w.out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdl_parser::MdlParser;
+    use crate::mdx_parser::{
+        calculate_bounds, Bone, Face, Keyframe, NodeFlags, PrimitiveGroup, NODE_BONE, UV,
+    };
+
+    fn sample_model() -> MdxModel {
+        let vertices = vec![
+            Vertex {
+                x: 0.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            Vertex {
+                x: 1.0,
+                y: 0.0,
+                z: 0.0,
+            },
+            Vertex {
+                x: 0.0,
+                y: 1.5,
+                z: 2.0,
+            },
+        ];
+        let mut model = MdxModel {
+            version: 800,
+            name: "Box".to_string(),
+            sequences: vec![Sequence {
+                name: "Stand".to_string(),
+                interval: [0, 1000],
+                move_speed: 0.0,
+                non_looping: true,
+                rarity: 0.5,
+                sync_point: 0,
+                bounds_radius: 1.0,
+                minimum_extent: Vertex::default(),
+                maximum_extent: Vertex {
+                    x: 1.0,
+                    y: 1.0,
+                    z: 1.0,
+                },
+            }],
+            global_sequences: vec![2000],
+            textures: vec![Texture::new(1, String::new(), 0x3)],
+            materials: vec![Material {
+                priority_plane: 0,
+                flags: 0x1,
+                shader: String::new(),
+                layers: vec![Layer {
+                    filter_mode: 2,
+                    shading_flags: 0x10,
+                    texture_id: 0,
+                    texture_animation_id: u32::MAX,
+                    coord_id: 0,
+                    alpha: 1.0,
+                    alpha_track: Some(Track {
+                        interpolation: 1,
+                        global_sequence_id: -1,
+                        keys: vec![
+                            Keyframe {
+                                frame: 0,
+                                value: [1.0],
+                                in_tan: None,
+                                out_tan: None,
+                            },
+                            Keyframe {
+                                frame: 500,
+                                value: [0.25],
+                                in_tan: None,
+                                out_tan: None,
+                            },
+                        ],
+                    }),
+                    texture_track: None,
+                    emissive_gain: None,
+                    fresnel_color: None,
+                    fresnel_opacity: None,
+                    fresnel_team_color: None,
+                }],
+            }],
+            geosets: vec![Geoset {
+                bounds: calculate_bounds(&vertices),
+                vertices,
+                uvs: vec![vec![
+                    UV { u: 0.0, v: 0.0 },
+                    UV { u: 1.0, v: 0.0 },
+                    UV { u: 0.0, v: 1.0 },
+                ]],
+                faces: vec![Face { indices: [0, 1, 2] }],
+                primitive_groups: vec![PrimitiveGroup {
+                    primitive_type: PRIMITIVE_TRIANGLES,
+                    index_count: 3,
+                    first_face: 0,
+                    face_count: 1,
+                }],
+                vertex_groups: vec![0, 0, 0],
+                matrix_groups: vec![1],
+                matrix_indices: vec![0],
+                ..Default::default()
+            }],
+            bones: vec![Bone {
+                node: Node {
+                    name: "Root".to_string(),
+                    object_id: 0,
+                    parent_id: NO_PARENT,
+                    flags: NODE_BONE | 0x8 | 0x2,
+                    decoded_flags: NodeFlags::from_bits(NODE_BONE | 0x8 | 0x2),
+                    translation: None,
+                    rotation: Some(Track {
+                        interpolation: 2,
+                        global_sequence_id: 0,
+                        keys: vec![Keyframe {
+                            frame: 100,
+                            value: [0.0, 0.0, 1.0, 0.0],
+                            in_tan: Some([0.0, 0.0, 1.0, 0.0]),
+                            out_tan: Some([0.0, 0.0, 1.0, 0.0]),
+                        }],
+                    }),
+                    scaling: None,
+                },
+                geoset_id: 0,
+                geoset_animation_id: u32::MAX,
+            }],
+            pivot_points: vec![Vertex::default()],
+            ..Default::default()
+        };
+        model.update_bounds();
+        model
+    }
+
+    #[test]
+    fn test_write_mdl_round_trip() {
+        let text = write_mdl(&sample_model());
+        let model = MdlParser::new(text.clone().into_bytes())
+            .and_then(|mut parser| parser.parse())
+            .unwrap();
+
+        assert_eq!(model.name, "Box");
+        assert_eq!(model.global_sequences, vec![2000]);
+        assert_eq!(model.textures[0].replaceable_id, 1);
+        assert_eq!(model.textures[0].flags, 0x3);
+        let layer = &model.materials[0].layers[0];
+        assert_eq!(layer.filter_mode, 2);
+        assert_eq!(layer.alpha_track.as_ref().unwrap().keys[1].value, [0.25]);
+        assert_eq!(model.geosets[0].faces[0].indices, [0, 1, 2]);
+        let bone = &model.bones[0];
+        assert!(bone.node.decoded_flags.billboarded);
+        assert!(bone.node.decoded_flags.dont_inherit_rotation);
+        assert_eq!(bone.geoset_animation_id, u32::MAX);
+        assert!(bone.node.rotation.as_ref().unwrap().keys[0]
+            .out_tan
+            .is_some());
+
+        // 解析结果再次写出应得到相同文本
+        assert_eq!(write_mdl(&model), text);
+    }
+
+    #[test]
+    fn test_write_mdl_special_values() {
+        let mut model = sample_model();
+        model.bones[0].geoset_id = u32::MAX;
+        model.geosets[0].vertices[1].x = f32::NAN;
+        model.geosets[0].vertices[2].y = f32::INFINITY;
+        model.geosets[0].skin = vec![[0, 0, 0, 0, 255, 0, 0, 0]; 3];
+        model.geosets[0].primitive_groups.push(PrimitiveGroup {
+            primitive_type: 5,
+            index_count: 4,
+            first_face: 1,
+            face_count: 0,
+        });
+
+        let text = write_mdl(&model);
+        assert!(text.contains("GeosetId Multiple,"));
+        assert!(text.contains("GeosetAnimId None,"));
+        assert!(!text.contains("NaN") && !text.contains("inf"));
+        assert!(text.contains("// Omitted 1 geoset skin weights"));
+        assert!(text.contains("// Omitted 1 non-triangle primitive groups"));
+
+        let parsed = MdlParser::new(text.into_bytes())
+            .and_then(|mut parser| parser.parse())
+            .unwrap();
+        assert_eq!(parsed.bones[0].geoset_id, u32::MAX);
+        let vertices = &parsed.geosets[0].vertices;
+        assert_eq!(vertices[1].x, 0.0);
+        assert_eq!(vertices[2].y, f32::MAX);
+    }
+}
@@ -0,0 +1,510 @@
+// 模型动画求值：关键帧采样、节点层级矩阵、蒙皮后的序列包围盒
+// War3 动画以毫秒为帧单位，每个序列对应时间轴上的 [start, end] 区间
+
+use crate::mdx_parser::{calculate_bounds, BoundingBox, Geoset, MdxModel, Node, Track, Vertex, NO_PARENT};
+use std::collections::HashMap;
+
+/// 4x4 列主序矩阵
+pub type Mat4 = [f32; 16];
+
+pub const IDENTITY: Mat4 = [
+    1.0, 0.0, 0.0, 0.0, //
+    0.0, 1.0, 0.0, 0.0, //
+    0.0, 0.0, 1.0, 0.0, //
+    0.0, 0.0, 0.0, 1.0,
+];
+
+// 单个序列最多采样的帧数（关键帧过多时均匀抽样）
+const MAX_SAMPLES_PER_SEQUENCE: usize = 64;
+// 除关键帧外，每个序列额外均匀采样的帧数
+const EXTRA_SAMPLES_PER_SEQUENCE: u32 = 8;
+// 节点层级的最大深度，防止损坏文件中的循环引用
+const MAX_NODE_DEPTH: usize = 256;
+// geoset 透明度高于该值才视为可见
+const VISIBILITY_THRESHOLD: f32 = 0.01;
+
+/// 动画求值时刻
+#[derive(Debug, Clone, Copy)]
+pub struct AnimationTime<'a> {
+    pub frame: u32,
+    pub interval: [u32; 2],
+    pub global_sequences: &'a [u32],
+}
+
+impl AnimationTime<'_> {
+    /// 计算轨道实际使用的帧与区间（引用全局序列的轨道独立循环）
+    fn resolve(&self, global_sequence_id: i32) -> (u32, [u32; 2]) {
+        if global_sequence_id >= 0 {
+            if let Some(&duration) = self.global_sequences.get(global_sequence_id as usize) {
+                if duration > 0 {
+                    let elapsed = self.frame.saturating_sub(self.interval[0]);
+                    return (elapsed % duration, [0, duration]);
+                }
+            }
+        }
+        (self.frame, self.interval)
+    }
+}
+
+/// 在给定时刻采样轨道，区间内没有关键帧时返回 None（由调用方使用静态值）
+///
+/// 四元数轨道（N = 4）使用归一化线性插值；Hermite/Bezier 轨道按线性插值近似。
+pub fn sample_track<const N: usize>(track: &Track<[f32; N]>, time: &AnimationTime) -> Option<[f32; N]> {
+    let (frame, [start, end]) = time.resolve(track.global_sequence_id);
+    let lo = track.keys.partition_point(|k| k.frame < start);
+    let hi = track.keys.partition_point(|k| k.frame <= end);
+    let keys = track.keys.get(lo..hi)?;
+    let first = keys.first()?;
+    let last = keys.last()?;
+
+    if frame <= first.frame {
+        return Some(first.value);
+    }
+    if frame >= last.frame {
+        return Some(last.value);
+    }
+
+    let next = keys.partition_point(|k| k.frame <= frame);
+    let (a, b) = (&keys[next - 1], &keys[next]);
+    if track.interpolation == 0 {
+        return Some(a.value);
+    }
+
+    let t = (frame - a.frame) as f32 / (b.frame - a.frame) as f32;
+    let mut target = b.value;
+    if N == 4 {
+        // 走最短路径
+        let dot: f32 = a.value.iter().zip(&b.value).map(|(x, y)| x * y).sum();
+        if dot < 0.0 {
+            target.iter_mut().for_each(|v| *v = -*v);
+        }
+    }
+
+    let mut value = [0.0f32; N];
+    for i in 0..N {
+        value[i] = a.value[i] + (target[i] - a.value[i]) * t;
+    }
+    if N == 4 {
+        let length = value.iter().map(|v| v * v).sum::<f32>().sqrt();
+        if length > 0.0 {
+            value.iter_mut().for_each(|v| *v /= length);
+        }
+    }
+    Some(value)
+}
+
+/// 矩阵乘法 a * b
+pub fn multiply(a: &Mat4, b: &Mat4) -> Mat4 {
+    let mut out = [0.0f32; 16];
+    for col in 0..4 {
+        for row in 0..4 {
+            out[col * 4 + row] = (0..4).map(|k| a[k * 4 + row] * b[col * 4 + k]).sum();
+        }
+    }
+    out
+}
+
+/// 用矩阵变换一个点
+pub fn transform_point(m: &Mat4, v: &Vertex) -> Vertex {
+    Vertex {
+        x: m[0] * v.x + m[4] * v.y + m[8] * v.z + m[12],
+        y: m[1] * v.x + m[5] * v.y + m[9] * v.z + m[13],
+        z: m[2] * v.x + m[6] * v.y + m[10] * v.z + m[14],
+    }
+}
+
+/// 节点在给定时刻的局部矩阵: T(pivot + translation) * R * S * T(-pivot)
+pub fn local_matrix(node: &Node, pivot: &Vertex, time: &AnimationTime) -> Mat4 {
+    let [tx, ty, tz] = node
+        .translation
+        .as_ref()
+        .and_then(|track| sample_track(track, time))
+        .unwrap_or([0.0; 3]);
+    let [x, y, z, w] = node
+        .rotation
+        .as_ref()
+        .and_then(|track| sample_track(track, time))
+        .unwrap_or([0.0, 0.0, 0.0, 1.0]);
+    let [sx, sy, sz] = node
+        .scaling
+        .as_ref()
+        .and_then(|track| sample_track(track, time))
+        .unwrap_or([1.0; 3]);
+
+    // 旋转 * 缩放（列主序）
+    let mut m = [
+        (1.0 - 2.0 * (y * y + z * z)) * sx,
+        (2.0 * (x * y + z * w)) * sx,
+        (2.0 * (x * z - y * w)) * sx,
+        0.0,
+        (2.0 * (x * y - z * w)) * sy,
+        (1.0 - 2.0 * (x * x + z * z)) * sy,
+        (2.0 * (y * z + x * w)) * sy,
+        0.0,
+        (2.0 * (x * z + y * w)) * sz,
+        (2.0 * (y * z - x * w)) * sz,
+        (1.0 - 2.0 * (x * x + y * y)) * sz,
+        0.0,
+        0.0,
+        0.0,
+        0.0,
+        1.0,
+    ];
+
+    let rotated_pivot = transform_point(&m, pivot);
+    m[12] = pivot.x + tx - rotated_pivot.x;
+    m[13] = pivot.y + ty - rotated_pivot.y;
+    m[14] = pivot.z + tz - rotated_pivot.z;
+    m
+}
+
+/// 计算所有节点在给定时刻的世界矩阵（按 object_id 索引）
+pub fn node_world_matrices(model: &MdxModel, time: &AnimationTime) -> HashMap<u32, Mat4> {
+    let nodes: HashMap<u32, &Node> = model
+        .all_nodes()
+        .into_iter()
+        .map(|node| (node.object_id, node))
+        .collect();
+
+    let mut world = HashMap::with_capacity(nodes.len());
+    for &object_id in nodes.keys() {
+        resolve_world_matrix(object_id, model, &nodes, time, &mut world, 0);
+    }
+    world
+}
+
+fn resolve_world_matrix(
+    object_id: u32,
+    model: &MdxModel,
+    nodes: &HashMap<u32, &Node>,
+    time: &AnimationTime,
+    world: &mut HashMap<u32, Mat4>,
+    depth: usize,
+) -> Mat4 {
+    if let Some(matrix) = world.get(&object_id) {
+        return *matrix;
+    }
+    let node = match nodes.get(&object_id) {
+        Some(node) => node,
+        None => return IDENTITY,
+    };
+
+    let pivot = model
+        .pivot_points
+        .get(object_id as usize)
+        .copied()
+        .unwrap_or_default();
+    let local = local_matrix(node, &pivot, time);
+
+    let matrix = if node.parent_id != NO_PARENT && node.parent_id != object_id && depth < MAX_NODE_DEPTH {
+        let parent = resolve_world_matrix(node.parent_id, model, nodes, time, world, depth + 1);
+        multiply(&parent, &local)
+    } else {
+        local
+    };
+
+    world.insert(object_id, matrix);
+    matrix
+}
+
+/// 计算蒙皮后的顶点：有 SKIN（v900+）时按骨骼权重加权，
+/// 否则按经典绑定（GNDX + MTGC + MATS）取所在矩阵组骨骼变换的平均值
+pub fn skin_vertices(geoset: &Geoset, world: &HashMap<u32, Mat4>) -> Vec<Vertex> {
+    let mut groups: Vec<&[u32]> = Vec::with_capacity(geoset.matrix_groups.len());
+    let mut offset = 0;
+    for &size in &geoset.matrix_groups {
+        let end = (offset + size as usize).min(geoset.matrix_indices.len());
+        groups.push(&geoset.matrix_indices[offset..end]);
+        offset = end;
+    }
+    let bone_matrix = |bone: &u32| world.get(bone).unwrap_or(&IDENTITY);
+
+    geoset
+        .vertices
+        .iter()
+        .enumerate()
+        .map(|(i, vertex)| {
+            // SKIN: 4 个 MATS 下标 + 4 个权重（/255）
+            let influences: Vec<(&Mat4, f32)> = match geoset.skin.get(i) {
+                Some(entry) => (0..4)
+                    .filter(|&k| entry[4 + k] > 0)
+                    .filter_map(|k| {
+                        let bone = geoset.matrix_indices.get(entry[k] as usize)?;
+                        Some((bone_matrix(bone), entry[4 + k] as f32))
+                    })
+                    .collect(),
+                None => geoset
+                    .vertex_groups
+                    .get(i)
+                    .and_then(|&group| groups.get(group as usize))
+                    .map(|bones| bones.iter().map(|bone| (bone_matrix(bone), 1.0)).collect())
+                    .unwrap_or_default(),
+            };
+
+            let total: f32 = influences.iter().map(|(_, weight)| weight).sum();
+            if total <= 0.0 {
+                return *vertex;
+            }
+            let mut sum = Vertex::default();
+            for (matrix, weight) in influences {
+                let p = transform_point(matrix, vertex);
+                sum.x += p.x * weight;
+                sum.y += p.y * weight;
+                sum.z += p.z * weight;
+            }
+            Vertex {
+                x: sum.x / total,
+                y: sum.y / total,
+                z: sum.z / total,
+            }
+        })
+        .collect()
+}
+
+/// 序列区间内需要采样的帧：节点关键帧、区间端点以及均匀分布的中间帧
+fn sample_frames(model: &MdxModel, interval: [u32; 2]) -> Vec<u32> {
+    let [start, end] = interval;
+    let mut frames = vec![start, end];
+    if end > start {
+        let step = ((end - start) / EXTRA_SAMPLES_PER_SEQUENCE).max(1);
+        frames.extend((start..end).step_by(step as usize));
+    }
+
+    for node in model.all_nodes() {
+        let translation = node.translation.iter().flat_map(|t| t.keys.iter().map(|k| k.frame));
+        let rotation = node.rotation.iter().flat_map(|t| t.keys.iter().map(|k| k.frame));
+        let scaling = node.scaling.iter().flat_map(|t| t.keys.iter().map(|k| k.frame));
+        frames.extend(
+            translation
+                .chain(rotation)
+                .chain(scaling)
+                .filter(|&frame| frame >= start && frame <= end),
+        );
+    }
+
+    frames.sort_unstable();
+    frames.dedup();
+
+    if frames.len() > MAX_SAMPLES_PER_SEQUENCE {
+        let stride = frames.len().div_ceil(MAX_SAMPLES_PER_SEQUENCE);
+        let last = *frames.last().unwrap();
+        frames = frames.into_iter().step_by(stride).collect();
+        if frames.last() != Some(&last) {
+            frames.push(last);
+        }
+    }
+    frames
+}
+
+/// 计算单个序列播放过程中所有蒙皮顶点的包围盒，模型无顶点时返回 None
+pub fn compute_sequence_bounds_for(model: &MdxModel, interval: [u32; 2]) -> Option<BoundingBox> {
+    let mut bounds: Option<BoundingBox> = None;
+    for frame in sample_frames(model, interval) {
+        let time = AnimationTime {
+            frame,
+            interval,
+            global_sequences: &model.global_sequences,
+        };
+        let world = node_world_matrices(model, &time);
+
+        for geoset in &model.geosets {
+            if geoset.vertices.is_empty() {
+                continue;
+            }
+            let frame_bounds = calculate_bounds(&skin_vertices(geoset, &world));
+            bounds = Some(match bounds {
+                Some(b) => b.union(&frame_bounds),
+                None => frame_bounds,
+            });
+        }
+    }
+    bounds
+}
+
+/// 根骨骼在序列中的位移 [dx, dy, dz]（序列末帧与首帧的平移差）
+///
+/// 根骨骼取第一个没有父节点且带有平移轨道的骨骼；没有这样的骨骼时位移为 0。
+pub fn root_motion(model: &MdxModel, interval: [u32; 2]) -> [f32; 3] {
+    let root_translation = model
+        .bones
+        .iter()
+        .filter(|bone| bone.node.parent_id == NO_PARENT)
+        .find_map(|bone| bone.node.translation.as_ref());
+    let Some(track) = root_translation else {
+        return [0.0; 3];
+    };
+
+    let sample = |frame| {
+        let time = AnimationTime {
+            frame,
+            interval,
+            global_sequences: &model.global_sequences,
+        };
+        sample_track(track, &time).unwrap_or([0.0; 3])
+    };
+    let (start, end) = (sample(interval[0]), sample(interval[1]));
+    [end[0] - start[0], end[1] - start[1], end[2] - start[2]]
+}
+
+/// geoset 在序列播放过程中是否可见（GEOA 透明度曾高于阈值）
+///
+/// 没有对应 GEOA 的 geoset 始终可见；轨道在区间内没有关键帧时使用静态透明度。
+/// 线性插值下区间内的最大值必然出现在关键帧上，因此只需检查关键帧。
+pub fn is_geoset_visible(model: &MdxModel, geoset_id: u32, interval: [u32; 2]) -> bool {
+    let Some(animation) = model
+        .geoset_animations
+        .iter()
+        .find(|a| a.geoset_id == geoset_id)
+    else {
+        return true;
+    };
+    let Some(track) = &animation.alpha_track else {
+        return animation.alpha > VISIBILITY_THRESHOLD;
+    };
+
+    let time = AnimationTime {
+        frame: interval[0],
+        interval,
+        global_sequences: &model.global_sequences,
+    };
+    let (_, [start, end]) = time.resolve(track.global_sequence_id);
+    let mut keys = track
+        .keys
+        .iter()
+        .filter(|k| k.frame >= start && k.frame <= end)
+        .peekable();
+    if keys.peek().is_none() {
+        return animation.alpha > VISIBILITY_THRESHOLD;
+    }
+    keys.any(|k| k.value[0] > VISIBILITY_THRESHOLD)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdx_parser::{GeosetAnimation, Keyframe};
+
+    fn key(frame: u32, value: [f32; 3]) -> Keyframe<[f32; 3]> {
+        Keyframe {
+            frame,
+            value,
+            in_tan: None,
+            out_tan: None,
+        }
+    }
+
+    #[test]
+    fn test_sample_track_interpolates_within_sequence() {
+        let track = Track {
+            interpolation: 1,
+            global_sequence_id: -1,
+            keys: vec![key(0, [9.0; 3]), key(100, [0.0; 3]), key(200, [10.0, 20.0, 30.0]), key(500, [1.0; 3])],
+        };
+        let time = |frame| AnimationTime {
+            frame,
+            interval: [100, 300],
+            global_sequences: &[],
+        };
+
+        assert_eq!(sample_track(&track, &time(150)), Some([5.0, 10.0, 15.0]));
+        // 区间外的关键帧不参与插值
+        assert_eq!(sample_track(&track, &time(300)), Some([10.0, 20.0, 30.0]));
+
+        let empty = AnimationTime {
+            frame: 50,
+            interval: [20, 80],
+            global_sequences: &[],
+        };
+        assert_eq!(sample_track(&track, &empty), None);
+    }
+
+    #[test]
+    fn test_skin_vertices_uses_skin_weights() {
+        let mut moved = IDENTITY;
+        moved[12] = 10.0;
+        let world = HashMap::from([(5, moved), (6, IDENTITY)]);
+        let mut geoset = Geoset {
+            vertices: vec![Vertex::default(); 2],
+            vertex_groups: vec![0, 0],
+            matrix_groups: vec![2],
+            matrix_indices: vec![5, 6],
+            ..Default::default()
+        };
+
+        // 经典绑定：矩阵组内两根骨骼取平均
+        let classic = skin_vertices(&geoset, &world);
+        assert_eq!(classic[0].x, 5.0);
+
+        // SKIN: 第一个顶点 3/4 权重在骨骼 5 上，第二个顶点全部在骨骼 6 上
+        geoset.skin = vec![[0, 1, 0, 0, 192, 64, 0, 0], [1, 0, 0, 0, 255, 0, 0, 0]];
+        let skinned = skin_vertices(&geoset, &world);
+        assert_eq!(skinned[0].x, 7.5);
+        assert_eq!(skinned[1].x, 0.0);
+    }
+
+    #[test]
+    fn test_root_motion() {
+        use crate::mdx_parser::{Bone, Node, NodeFlags};
+
+        let bone = |object_id: u32, parent_id: u32, distance: f32| Bone {
+            node: Node {
+                name: format!("Bone{}", object_id),
+                object_id,
+                parent_id,
+                flags: 0x100,
+                decoded_flags: NodeFlags::default(),
+                translation: Some(Track {
+                    interpolation: 1,
+                    global_sequence_id: -1,
+                    keys: vec![key(0, [0.0; 3]), key(1000, [distance, 0.0, 0.0])],
+                }),
+                rotation: None,
+                scaling: None,
+            },
+            geoset_id: 0,
+            geoset_animation_id: u32::MAX,
+        };
+        // 子骨骼的位移不计入
+        let mut model = MdxModel {
+            bones: vec![bone(1, 0, 999.0), bone(0, NO_PARENT, 100.0)],
+            ..Default::default()
+        };
+
+        assert_eq!(root_motion(&model, [0, 1000]), [100.0, 0.0, 0.0]);
+
+        model.bones.clear();
+        assert_eq!(root_motion(&model, [0, 1000]), [0.0; 3]);
+    }
+
+    #[test]
+    fn test_geoset_visibility_per_sequence() {
+        let alpha_key = |frame, alpha| Keyframe {
+            frame,
+            value: [alpha],
+            in_tan: None,
+            out_tan: None,
+        };
+        let model = MdxModel {
+            geoset_animations: vec![GeosetAnimation {
+                alpha: 1.0,
+                flags: 0,
+                color: [1.0; 3],
+                geoset_id: 0,
+                alpha_track: Some(Track {
+                    interpolation: 0,
+                    global_sequence_id: -1,
+                    keys: vec![alpha_key(0, 1.0), alpha_key(1000, 0.0)],
+                }),
+                color_track: None,
+            }],
+            ..Default::default()
+        };
+
+        assert!(is_geoset_visible(&model, 0, [0, 500]));
+        assert!(!is_geoset_visible(&model, 0, [1000, 1500]));
+        // 区间内没有关键帧时使用静态透明度
+        assert!(is_geoset_visible(&model, 0, [2000, 2500]));
+        // 没有 GEOA 的 geoset 始终可见
+        assert!(is_geoset_visible(&model, 1, [1000, 1500]));
+    }
+}
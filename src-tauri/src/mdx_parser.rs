@@ -9,18 +9,70 @@ use std::io::{Cursor, Read, Seek, SeekFrom};
 const MDX_MAGIC: &[u8; 4] = b"MDLX";
 // const MDL_VERSION: u32 = 800; // Warcraft III uses version 800 (未使用，保留作参考)
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Default)]
 pub struct MdxModel {
     pub version: u32,
     pub name: String,
-    pub vertices: Vec<Vertex>,
-    pub normals: Vec<Normal>,
-    pub uvs: Vec<UV>,
-    pub faces: Vec<Face>,
-    pub bounds: BoundingBox,
+    pub sequences: Vec<Sequence>,
+    pub global_sequences: Vec<u32>,
+    pub textures: Vec<Texture>,
+    pub materials: Vec<Material>,
+    pub texture_animations: Vec<TextureAnimation>,
+    pub geosets: Vec<Geoset>,
+    pub geoset_animations: Vec<GeosetAnimation>,
+    pub bones: Vec<Bone>,
+    pub helpers: Vec<Node>,
+    pub attachments: Vec<Attachment>,
+    pub pivot_points: Vec<Vertex>,
+    pub particle_emitters: Vec<ParticleEmitter>,
+    pub particle_emitters2: Vec<ParticleEmitter2>,
+    pub collision_shapes: Vec<CollisionShape>,
+    pub cameras: Vec<Camera>,
+    pub lights: Vec<Light>,
+    pub warnings: Vec<String>, // 因截断或损坏而跳过的 chunk
+    pub overall_bounds: BoundingBox, // 所有 geoset 的合并包围盒，没有顶点时取 MODL 中的 extent
+    pub overall_bounds_radius: f32,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+impl MdxModel {
+    /// 合并各 geoset 的包围盒作为模型包围盒；没有任何顶点时保留已读取的 MODL extent
+    pub fn update_bounds(&mut self) {
+        if let Some(bounds) = self
+            .geosets
+            .iter()
+            .filter(|g| !g.vertices.is_empty())
+            .map(|g| g.bounds)
+            .reduce(|a, b| a.union(&b))
+        {
+            self.overall_bounds = bounds;
+        }
+        self.overall_bounds_radius = self.overall_bounds.radius();
+    }
+
+    /// 为有面但缺少 NRMS 的 geoset 生成平滑法线，已有法线的保持不变
+    pub fn compute_missing_normals(&mut self) {
+        for geoset in &mut self.geosets {
+            if geoset.normals.is_empty() && !geoset.faces.is_empty() {
+                geoset.normals = calculate_smooth_normals(&geoset.vertices, &geoset.faces);
+            }
+        }
+    }
+
+    /// 所有节点（骨骼、灯光、辅助体、附着点、发射器），按 object_id 构成层级
+    pub fn all_nodes(&self) -> Vec<&Node> {
+        let mut nodes: Vec<&Node> = Vec::new();
+        nodes.extend(self.bones.iter().map(|b| &b.node));
+        nodes.extend(self.lights.iter().map(|l| &l.node));
+        nodes.extend(self.helpers.iter());
+        nodes.extend(self.attachments.iter().map(|a| &a.node));
+        nodes.extend(self.particle_emitters.iter().map(|e| &e.node));
+        nodes.extend(self.particle_emitters2.iter().map(|e| &e.node));
+        nodes.extend(self.collision_shapes.iter().map(|c| &c.node));
+        nodes
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
 pub struct Vertex {
     pub x: f32,
     pub y: f32,
@@ -45,12 +97,393 @@ pub struct Face {
     pub indices: [u16; 3], // 三角面的三个顶点索引
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default)]
 pub struct BoundingBox {
     pub min: Vertex,
     pub max: Vertex,
 }
 
+impl BoundingBox {
+    /// 合并两个包围盒
+    pub fn union(&self, other: &BoundingBox) -> BoundingBox {
+        BoundingBox {
+            min: Vertex {
+                x: self.min.x.min(other.min.x),
+                y: self.min.y.min(other.min.y),
+                z: self.min.z.min(other.min.z),
+            },
+            max: Vertex {
+                x: self.max.x.max(other.max.x),
+                y: self.max.y.max(other.max.y),
+                z: self.max.z.max(other.max.z),
+            },
+        }
+    }
+
+    /// 外接球半径（对角线的一半）
+    pub fn radius(&self) -> f32 {
+        let dx = self.max.x - self.min.x;
+        let dy = self.max.y - self.min.y;
+        let dz = self.max.z - self.min.z;
+        (dx * dx + dy * dy + dz * dz).sqrt() / 2.0
+    }
+}
+
+/// 材质（MTLS）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Material {
+    pub priority_plane: u32,
+    pub flags: u32,
+    pub shader: String, // 仅 v900 及以上
+    pub layers: Vec<Layer>,
+}
+
+/// 材质层
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Layer {
+    pub filter_mode: u32, // 0 None, 1 Transparent, 2 Blend, 3 Additive, 4 AddAlpha, 5 Modulate, 6 Modulate2x
+    pub shading_flags: u32,
+    pub texture_id: u32,
+    pub texture_animation_id: u32, // 0xFFFFFFFF 表示无纹理动画
+    pub coord_id: u32,
+    pub alpha: f32,
+    pub alpha_track: Option<Track<[f32; 1]>>, // KMTA
+    pub texture_track: Option<Track<u32>>,     // KMTF: 翻页纹理
+    pub emissive_gain: Option<f32>,            // v900+
+    pub fresnel_color: Option<[f32; 3]>,       // v1000+，以下同
+    pub fresnel_opacity: Option<f32>,
+    pub fresnel_team_color: Option<f32>,
+}
+
+/// 纹理动画（TXAN）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct TextureAnimation {
+    pub translation: Option<Track<[f32; 3]>>,
+    pub rotation: Option<Track<[f32; 4]>>,
+    pub scaling: Option<Track<[f32; 3]>>,
+}
+
+/// 几何体（GEOS 中的单个 geoset），面索引相对于本 geoset 的顶点
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Geoset {
+    pub vertices: Vec<Vertex>,
+    pub normals: Vec<Normal>,
+    pub uvs: Vec<Vec<UV>>, // UVAS/UVBS: 每层一组 UV，与顶点一一对应
+    pub faces: Vec<Face>,
+    pub primitive_groups: Vec<PrimitiveGroup>, // PTYP/PCNT: 面数据的分组
+    pub vertex_groups: Vec<u8>,   // GNDX: 每个顶点所属的矩阵组
+    pub matrix_groups: Vec<u32>,  // MTGC: 每个矩阵组包含的骨骼数
+    pub matrix_indices: Vec<u32>, // MATS: 按组依次排列的骨骼 object_id
+    pub skin: Vec<[u8; 8]>,       // SKIN (v900+): 每顶点 4 个骨骼（MATS 下标）+ 4 个权重（/255）
+    pub material_id: u32,
+    pub selection_group: u32,
+    pub selection_flags: u32,
+    pub bounds: BoundingBox,
+}
+
+impl Geoset {
+    /// 第一层 UV（没有 UV 时为空）
+    pub fn first_uvs(&self) -> &[UV] {
+        self.uvs.first().map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+/// 图元类型为三角形列表（PTYP 中的值）
+pub const PRIMITIVE_TRIANGLES: u32 = 4;
+
+/// 图元分组：PVTX 中连续的一段索引，只有三角形列表分组会生成 `faces`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PrimitiveGroup {
+    pub primitive_type: u32, // 0 点, 1 线, 2 线环, 3 线带, 4 三角形, 5 三角形带, 6 三角形扇, 7 四边形, 8 四边形带, 9 多边形
+    pub index_count: u32,    // 本组在 PVTX 中的索引数
+    pub first_face: u32,     // 本组第一个面在 `faces` 中的下标
+    pub face_count: u32,
+}
+
+/// 几何体动画（GEOA），控制 geoset 的透明度与颜色
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct GeosetAnimation {
+    pub alpha: f32,
+    pub flags: u32, // 0x1 投射阴影, 0x2 使用颜色
+    pub color: [f32; 3],
+    pub geoset_id: u32,
+    pub alpha_track: Option<Track<[f32; 1]>>, // KGAO
+    pub color_track: Option<Track<[f32; 3]>>, // KGAC
+}
+
+/// 动画序列（SEQS）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Sequence {
+    pub name: String,
+    pub interval: [u32; 2], // [起始帧, 结束帧]
+    pub move_speed: f32,
+    pub non_looping: bool,
+    pub rarity: f32,
+    pub sync_point: u32,
+    pub bounds_radius: f32,
+    pub minimum_extent: Vertex,
+    pub maximum_extent: Vertex,
+}
+
+/// 动画轨道中的单个关键帧
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Keyframe<T> {
+    pub frame: u32,
+    pub value: T,
+    pub in_tan: Option<T>,  // 仅 Hermite/Bezier 插值
+    pub out_tan: Option<T>,
+}
+
+/// 关键帧动画轨道（KGTR/KGRT/KGSC 等）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Track<T> {
+    pub interpolation: u32,      // 0 无插值, 1 线性, 2 Hermite, 3 Bezier
+    pub global_sequence_id: i32, // -1 表示不使用全局序列
+    pub keys: Vec<Keyframe<T>>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Texture {
+    pub replaceable_id: u32,
+    pub path: String,
+    pub flags: u32,
+    #[serde(default)]
+    pub resolved_path: Option<String>, // 可替换纹理对应的标准 BLP（按红色队伍解析），普通纹理为 None
+}
+
+impl Texture {
+    pub fn new(replaceable_id: u32, path: String, flags: u32) -> Self {
+        let resolved_path = if path.is_empty() {
+            resolve_replaceable_texture(replaceable_id, 0)
+        } else {
+            None
+        };
+        Texture {
+            replaceable_id,
+            path,
+            flags,
+            resolved_path,
+        }
+    }
+}
+
+// 可替换纹理 id 与 ReplaceableTextures 下的路径（不含扩展名）
+const REPLACEABLE_TEXTURES: &[(u32, &str)] = &[
+    (11, "Cliff\\Cliff0"),
+    (31, "LordaeronTree\\LordaeronSummerTree"),
+    (32, "AshenvaleTree\\AshenTree"),
+    (33, "BarrensTree\\BarrensTree"),
+    (34, "NorthrendTree\\NorthTree"),
+    (35, "Mushroom\\MushroomTree"),
+    (36, "RuinsTree\\RuinsTree"),
+    (37, "OutlandMushroomTree\\MushroomTree"),
+];
+
+/// 把可替换纹理 id 解析为标准 BLP 路径；1、2 为 `team_color` 队伍的队伍颜色与光晕
+pub fn resolve_replaceable_texture(id: u32, team_color: u32) -> Option<String> {
+    let path = match id {
+        1 => format!("TeamColor\\TeamColor{:02}", team_color),
+        2 => format!("TeamGlow\\TeamGlow{:02}", team_color),
+        _ => REPLACEABLE_TEXTURES
+            .iter()
+            .find(|(known, _)| *known == id)?
+            .1
+            .to_string(),
+    };
+    Some(format!("ReplaceableTextures\\{}.blp", path))
+}
+
+/// 节点公共头（BONE/HELP/ATCH/PREM 等共用）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Node {
+    pub name: String,
+    pub object_id: u32,
+    pub parent_id: u32, // 0xFFFFFFFF 表示无父节点
+    pub flags: u32,
+    #[serde(default)]
+    pub decoded_flags: NodeFlags, // 由 flags 解码，只读，写出时以 flags 为准
+    pub translation: Option<Track<[f32; 3]>>,
+    pub rotation: Option<Track<[f32; 4]>>, // 四元数 (x, y, z, w)
+    pub scaling: Option<Track<[f32; 3]>>,
+}
+
+// 节点类型标志（均未设置时为辅助体）
+pub const NODE_BONE: u32 = 0x100;
+pub const NODE_LIGHT: u32 = 0x200;
+pub const NODE_EVENT_OBJECT: u32 = 0x400;
+pub const NODE_ATTACHMENT: u32 = 0x800;
+pub const NODE_PARTICLE_EMITTER: u32 = 0x1000;
+pub const NODE_COLLISION_SHAPE: u32 = 0x2000;
+pub const NODE_RIBBON_EMITTER: u32 = 0x4000;
+
+/// 节点 flags 的解码结果
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct NodeFlags {
+    pub dont_inherit_translation: bool,
+    pub dont_inherit_rotation: bool,
+    pub dont_inherit_scaling: bool,
+    pub billboarded: bool,
+    pub billboarded_lock_x: bool,
+    pub billboarded_lock_y: bool,
+    pub billboarded_lock_z: bool,
+    pub camera_anchored: bool,
+    pub bone: bool,
+    pub light: bool,
+    pub event_object: bool,
+    pub attachment: bool,
+    pub particle_emitter: bool,
+    pub collision_shape: bool,
+    pub ribbon_emitter: bool,
+}
+
+impl NodeFlags {
+    pub fn from_bits(flags: u32) -> Self {
+        let has = |bit: u32| flags & bit != 0;
+        NodeFlags {
+            dont_inherit_translation: has(0x1),
+            dont_inherit_rotation: has(0x2),
+            dont_inherit_scaling: has(0x4),
+            billboarded: has(0x8),
+            billboarded_lock_x: has(0x10),
+            billboarded_lock_y: has(0x20),
+            billboarded_lock_z: has(0x40),
+            camera_anchored: has(0x80),
+            bone: has(NODE_BONE),
+            light: has(NODE_LIGHT),
+            event_object: has(NODE_EVENT_OBJECT),
+            attachment: has(NODE_ATTACHMENT),
+            particle_emitter: has(NODE_PARTICLE_EMITTER),
+            collision_shape: has(NODE_COLLISION_SHAPE),
+            ribbon_emitter: has(NODE_RIBBON_EMITTER),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Bone {
+    pub node: Node,
+    pub geoset_id: u32,
+    pub geoset_animation_id: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Attachment {
+    pub node: Node,
+    pub path: String,
+    pub attachment_id: u32,
+}
+
+/// PREM 粒子发射器（直接引用模型文件）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParticleEmitter {
+    pub node: Node,
+    pub emission_rate: f32,
+    pub gravity: f32,
+    pub longitude: f32,
+    pub latitude: f32,
+    pub path: String,
+    pub life_span: f32,
+    pub initial_velocity: f32,
+}
+
+/// PRE2 粒子发射器（贴图粒子）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ParticleEmitter2 {
+    pub node: Node,
+    pub speed: f32,
+    pub variation: f32,
+    pub latitude: f32,
+    pub gravity: f32,
+    pub life_span: f32,
+    pub emission_rate: f32,
+    pub width: f32,
+    pub length: f32,
+    pub filter_mode: u32,
+    pub rows: u32,
+    pub columns: u32,
+    pub head_or_tail: u32, // 0 头部, 1 尾部, 2 两者
+    pub tail_length: f32,
+    pub time: f32,                    // 中段颜色/透明度/缩放所处的生命周期比例
+    pub segment_color: [[f32; 3]; 3], // 起始/中段/结束，BGR
+    pub segment_alpha: [u8; 3],
+    pub segment_scaling: [f32; 3],
+    pub head_intervals: [[u32; 3]; 2], // 生命期、衰减期: 起始帧, 结束帧, 重复次数
+    pub tail_intervals: [[u32; 3]; 2],
+    pub texture_id: u32,
+    pub squirt: u32,
+    pub priority_plane: i32,
+    pub replaceable_id: u32,
+    pub speed_track: Option<Track<[f32; 1]>>,         // KP2S
+    pub variation_track: Option<Track<[f32; 1]>>,     // KP2R
+    pub latitude_track: Option<Track<[f32; 1]>>,      // KP2L
+    pub gravity_track: Option<Track<[f32; 1]>>,       // KP2G
+    pub emission_rate_track: Option<Track<[f32; 1]>>, // KP2E
+    pub length_track: Option<Track<[f32; 1]>>,        // KP2N
+    pub width_track: Option<Track<[f32; 1]>>,         // KP2W
+    pub visibility_track: Option<Track<[f32; 1]>>,    // KP2V
+}
+
+/// 碰撞体（CLID），顶点为模型空间坐标
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CollisionShape {
+    pub node: Node,
+    pub shape_type: u32,        // 0 长方体, 1 平面, 2 球体, 3 圆柱体
+    pub vertices: Vec<Vertex>,  // 长方体/平面: 最小/最大角点; 球体: 球心; 圆柱体: 底面/顶面中心
+    pub radius: f32,            // 仅球体与圆柱体
+}
+
+/// 灯光（LITE）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Light {
+    pub node: Node,
+    pub light_type: u32, // 0 点光源, 1 平行光, 2 环境光
+    pub attenuation_start: f32,
+    pub attenuation_end: f32,
+    pub color: [f32; 3], // BGR
+    pub intensity: f32,
+    pub ambient_color: [f32; 3], // BGR
+    pub ambient_intensity: f32,
+}
+
+/// 镜头（CAMS），位置与目标点为模型空间坐标
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Camera {
+    pub name: String,
+    pub position: Vertex,
+    pub field_of_view: f32, // 弧度
+    pub far_clip: f32,
+    pub near_clip: f32,
+    pub target: Vertex,
+    pub translation: Option<Track<[f32; 3]>>,        // KCTR
+    pub rotation: Option<Track<[f32; 1]>>,           // KCRL，绕视线方向的滚转角
+    pub target_translation: Option<Track<[f32; 3]>>, // KTTR
+}
+
+pub const COLLISION_BOX: u32 = 0;
+pub const COLLISION_PLANE: u32 = 1;
+pub const COLLISION_SPHERE: u32 = 2;
+pub const COLLISION_CYLINDER: u32 = 3;
+
+/// 解析进度（每个 chunk 读完后报告一次）
+#[derive(Debug, Serialize, Clone)]
+pub struct ParseProgress {
+    pub chunk: String,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+// 各记录中定长字符串字段的长度
+pub const NAME_LEN: usize = 80;
+pub const PATH_LEN: usize = 260;
+// MODL 的标准大小: name + animation_file + bounds_radius + extent + blend_time
+pub const MODEL_INFO_SIZE: usize = NAME_LEN + PATH_LEN + 28 + 4;
+// TEXS 中每个纹理记录的大小: replaceable_id + path + flags
+pub const TEXTURE_RECORD_SIZE: usize = 4 + PATH_LEN + 4;
+// SEQS 中每个序列记录的大小: name + interval + move_speed + flags + rarity + sync_point + extent
+pub const SEQUENCE_RECORD_SIZE: usize = NAME_LEN + 8 + 4 + 4 + 4 + 4 + 28;
+// parent_id 为该值时表示根节点
+pub const NO_PARENT: u32 = u32::MAX;
+
 // Chunk 类型标识符 (4 bytes)
 #[derive(Debug, PartialEq)]
 enum ChunkType {
@@ -67,6 +500,12 @@ enum ChunkType {
     Pivt, // Pivot points
     Evts, // Events
     Clid, // Collision shapes
+    Prem, // Particle emitters
+    Glbs, // Global sequences
+    Txan, // Texture animations
+    Pre2, // Particle emitters v2
+    Cams, // Cameras
+    Lite, // Lights
     Unknown,
 }
 
@@ -86,6 +525,12 @@ impl ChunkType {
             b"PIVT" => ChunkType::Pivt,
             b"EVTS" => ChunkType::Evts,
             b"CLID" => ChunkType::Clid,
+            b"PREM" => ChunkType::Prem,
+            b"GLBS" => ChunkType::Glbs,
+            b"TXAN" => ChunkType::Txan,
+            b"PRE2" => ChunkType::Pre2,
+            b"CAMS" => ChunkType::Cams,
+            b"LITE" => ChunkType::Lite,
             _ => ChunkType::Unknown,
         }
     }
@@ -103,31 +548,18 @@ impl MdxParser {
     }
 
     pub fn parse(&mut self) -> Result<MdxModel, String> {
-        // 读取文件头
-        let mut magic = [0u8; 4];
-        self.cursor
-            .read_exact(&mut magic)
-            .map_err(|e| format!("Failed to read magic: {}", e))?;
+        self.parse_with_progress(|_| {})
+    }
 
-        if &magic != MDX_MAGIC {
-            return Err(format!(
-                "Invalid MDX magic: expected {:?}, got {:?}",
-                MDX_MAGIC, magic
-            ));
-        }
+    /// 与 `parse` 相同，每读完一个 chunk 调用一次 `on_chunk` 报告进度
+    pub fn parse_with_progress(
+        &mut self,
+        mut on_chunk: impl FnMut(&ParseProgress),
+    ) -> Result<MdxModel, String> {
+        self.read_magic()?;
 
-        let mut model = MdxModel {
-            version: 0,
-            name: String::new(),
-            vertices: Vec::new(),
-            normals: Vec::new(),
-            uvs: Vec::new(),
-            faces: Vec::new(),
-            bounds: BoundingBox {
-                min: Vertex { x: 0.0, y: 0.0, z: 0.0 },
-                max: Vertex { x: 0.0, y: 0.0, z: 0.0 },
-            },
-        };
+        let mut model = MdxModel::default();
+        let data_len = self.cursor.get_ref().len() as u64;
 
         // 读取所有 chunks
         loop {
@@ -141,53 +573,188 @@ impl MdxParser {
             let chunk_type = ChunkType::from_bytes(&chunk_id);
 
             // 读取 chunk size (4 bytes)
-            let chunk_size = self
-                .cursor
-                .read_u32::<LittleEndian>()
-                .map_err(|e| format!("Failed to read chunk size: {}", e))?;
-
-            // 根据 chunk 类型处理
-            match chunk_type {
-                ChunkType::Vers => {
-                    model.version = self
-                        .cursor
-                        .read_u32::<LittleEndian>()
-                        .map_err(|e| format!("Failed to read version: {}", e))?;
-                }
-                ChunkType::Modl => {
-                    self.parse_model_info(&mut model, chunk_size)?;
-                }
-                ChunkType::Geos => {
-                    self.parse_geosets(&mut model, chunk_size)?;
-                }
-                _ => {
-                    // 跳过未知或暂不处理的 chunk
-                    self.cursor
-                        .seek(SeekFrom::Current(chunk_size as i64))
-                        .map_err(|e| format!("Failed to skip chunk: {}", e))?;
-                }
+            let Ok(chunk_size) = self.cursor.read_u32::<LittleEndian>() else {
+                model.warnings.push(format!(
+                    "{} chunk header is truncated",
+                    String::from_utf8_lossy(&chunk_id)
+                ));
+                break;
+            };
+            let chunk_start = self.cursor.position();
+            let chunk_end = chunk_start + chunk_size as u64;
+
+            // 单个 chunk 解析失败时记录警告并跳到其结尾，尽量保留其余数据
+            if let Err(e) = self.parse_chunk(&mut model, chunk_type, chunk_size, chunk_end) {
+                model.warnings.push(format!(
+                    "Skipped {} chunk: {}",
+                    String::from_utf8_lossy(&chunk_id),
+                    e
+                ));
+            }
+            if chunk_end > data_len {
+                model.warnings.push(format!(
+                    "{} chunk is truncated ({} of {} bytes)",
+                    String::from_utf8_lossy(&chunk_id),
+                    data_len - chunk_start,
+                    chunk_size
+                ));
+                break;
             }
+
+            // 无论处理器读了多少，都从 chunk 结尾继续（跳过未知或暂不处理的 chunk）
+            self.cursor
+                .seek(SeekFrom::Start(chunk_end))
+                .map_err(|e| format!("Failed to skip chunk: {}", e))?;
+            on_chunk(&ParseProgress {
+                chunk: String::from_utf8_lossy(&chunk_id).into_owned(),
+                bytes_done: chunk_end,
+                bytes_total: data_len,
+            });
         }
 
         // 计算边界框
-        self.calculate_bounds(&mut model);
+        model.update_bounds();
 
         Ok(model)
     }
 
+    /// 读取并校验文件头
+    fn read_magic(&mut self) -> Result<(), String> {
+        let mut magic = [0u8; 4];
+        self.cursor
+            .read_exact(&mut magic)
+            .map_err(|e| format!("Failed to read magic: {}", e))?;
+
+        if &magic != MDX_MAGIC {
+            return Err(format!(
+                "Invalid MDX magic: expected {:?}, got {:?}",
+                MDX_MAGIC, magic
+            ));
+        }
+        Ok(())
+    }
+
+    /// 只读取 SEQS 中的动画序列，跳过其余 chunk（用于动画列表等轻量场景）
+    pub fn parse_sequence_list(&mut self) -> Result<Vec<Sequence>, String> {
+        self.read_magic()?;
+
+        let mut model = MdxModel::default();
+        while let Ok(chunk_id) = self.read_tag() {
+            let chunk_size = self.read_u32()?;
+            let chunk_end = self.cursor.position() + chunk_size as u64;
+            if &chunk_id == b"SEQS" {
+                self.parse_sequences(&mut model, chunk_end)?;
+                break;
+            }
+            self.cursor
+                .seek(SeekFrom::Start(chunk_end))
+                .map_err(|e| format!("Failed to skip chunk: {}", e))?;
+        }
+        Ok(model.sequences)
+    }
+
+    /// 按类型解析单个 chunk，调用方负责之后跳到 chunk 结尾
+    fn parse_chunk(
+        &mut self,
+        model: &mut MdxModel,
+        chunk_type: ChunkType,
+        chunk_size: u32,
+        chunk_end: u64,
+    ) -> Result<(), String> {
+        match chunk_type {
+            ChunkType::Vers => {
+                model.version = self
+                    .cursor
+                    .read_u32::<LittleEndian>()
+                    .map_err(|e| format!("Failed to read version: {}", e))?;
+            }
+            ChunkType::Modl => {
+                self.parse_model_info(model, chunk_size)?;
+            }
+            ChunkType::Seqs => {
+                self.parse_sequences(model, chunk_end)?;
+            }
+            ChunkType::Glbs => {
+                while self.cursor.position() + 4 <= chunk_end {
+                    let duration = self.read_u32()?;
+                    model.global_sequences.push(duration);
+                }
+            }
+            ChunkType::Geos => {
+                self.parse_geosets(model, chunk_end)?;
+            }
+            ChunkType::Geoa => {
+                self.parse_geoset_animations(model, chunk_end)?;
+            }
+            ChunkType::Bone => {
+                self.parse_bones(model, chunk_end)?;
+            }
+            ChunkType::Help => {
+                while self.cursor.position() < chunk_end {
+                    let node = self.parse_node()?;
+                    model.helpers.push(node);
+                }
+            }
+            ChunkType::Pivt => {
+                while self.cursor.position() + 12 <= chunk_end {
+                    let [x, y, z] = self.read_vec::<3>()?;
+                    model.pivot_points.push(Vertex { x, y, z });
+                }
+            }
+            ChunkType::Texs => {
+                self.parse_textures(model, chunk_end)?;
+            }
+            ChunkType::Mtls => {
+                self.parse_materials(model, chunk_end)?;
+            }
+            ChunkType::Atch => {
+                self.parse_attachments(model, chunk_end)?;
+            }
+            ChunkType::Prem => {
+                self.parse_particle_emitters(model, chunk_end)?;
+            }
+            ChunkType::Pre2 => {
+                self.parse_particle_emitters2(model, chunk_end)?;
+            }
+            ChunkType::Txan => {
+                self.parse_texture_animations(model, chunk_end)?;
+            }
+            ChunkType::Clid => {
+                self.parse_collision_shapes(model, chunk_end)?;
+            }
+            ChunkType::Cams => {
+                self.parse_cameras(model, chunk_end)?;
+            }
+            ChunkType::Lite => {
+                self.parse_lights(model, chunk_end)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     fn parse_model_info(&mut self, model: &mut MdxModel, size: u32) -> Result<(), String> {
+        let start = self.cursor.position();
         // 模型名称 (80 bytes, null-terminated string)
-        let mut name_bytes = vec![0u8; 80];
-        self.cursor
-            .read_exact(&mut name_bytes)
-            .map_err(|e| format!("Failed to read model name: {}", e))?;
+        model.name = self.read_string(NAME_LEN)?;
 
-        // 找到第一个 null 字符
-        let name_end = name_bytes.iter().position(|&b| b == 0).unwrap_or(80);
-        model.name = String::from_utf8_lossy(&name_bytes[..name_end]).to_string();
+        // 标准布局在名称后还有 260 字节的动画文件名；部分导出器省略该字段，按 chunk 大小区分
+        let size = size as usize;
+        if size >= MODEL_INFO_SIZE {
+            self.read_string(PATH_LEN)?;
+        }
 
-        // 跳过剩余的 MODL 数据 (boundary sphere, extents, etc.)
-        let remaining = size as i64 - 80;
+        // bounds_radius + min + max，之后是 blend_time
+        if size >= NAME_LEN + 28 {
+            self.read_f32()?;
+            let [x, y, z] = self.read_vec::<3>()?;
+            model.overall_bounds.min = Vertex { x, y, z };
+            let [x, y, z] = self.read_vec::<3>()?;
+            model.overall_bounds.max = Vertex { x, y, z };
+        }
+
+        // 跳过剩余的 MODL 数据
+        let remaining = size as i64 - (self.cursor.position() - start) as i64;
         if remaining > 0 {
             self.cursor
                 .seek(SeekFrom::Current(remaining))
@@ -197,45 +764,597 @@ impl MdxParser {
         Ok(())
     }
 
-    fn parse_geosets(&mut self, model: &mut MdxModel, _size: u32) -> Result<(), String> {
-        // GEOS chunk 包含多个 geoset
-        // 每个 geoset 有自己的几何数据
+    /// 读取定长、以 null 结尾的字符串字段
+    fn read_string(&mut self, len: usize) -> Result<String, String> {
+        let mut bytes = vec![0u8; len];
+        self.cursor
+            .read_exact(&mut bytes)
+            .map_err(|e| format!("Failed to read string field: {}", e))?;
 
-        loop {
-            // 尝试读取下一个 geoset
-            let start_pos = self.cursor.position();
-
-            // 读取可能的 chunk ID
-            let mut next_chunk = [0u8; 4];
-            match self.cursor.read_exact(&mut next_chunk) {
-                Ok(_) => {
-                    // 检查是否是新的顶层 chunk（非 geoset 数据）
-                    if ChunkType::from_bytes(&next_chunk) != ChunkType::Unknown {
-                        // 回退，让主循环处理
-                        self.cursor.seek(SeekFrom::Start(start_pos)).ok();
-                        break;
+        // 找到第一个 null 字符
+        let end = bytes.iter().position(|&b| b == 0).unwrap_or(len);
+        Ok(String::from_utf8_lossy(&bytes[..end]).to_string())
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        self.cursor
+            .read_u32::<LittleEndian>()
+            .map_err(|e| format!("Failed to read u32: {}", e))
+    }
+
+    fn read_f32(&mut self) -> Result<f32, String> {
+        self.cursor
+            .read_f32::<LittleEndian>()
+            .map_err(|e| format!("Failed to read f32: {}", e))
+    }
+
+    fn read_vec<const N: usize>(&mut self) -> Result<[f32; N], String> {
+        let mut values = [0.0f32; N];
+        for value in values.iter_mut() {
+            *value = self.read_f32()?;
+        }
+        Ok(values)
+    }
+
+    /// 读取三维向量数组的数量，数组超出 geoset 结尾时视为损坏
+    fn read_vec3_count(&mut self, geoset_end: u64, what: &str) -> Result<usize, String> {
+        let count = self.read_u32()? as u64;
+        let remaining = geoset_end.saturating_sub(self.cursor.position());
+        if count > remaining / 12 {
+            return Err(format!("Corrupt {} chunk: {} entries exceed geoset", what, count));
+        }
+        Ok(count as usize)
+    }
+
+    fn read_tag(&mut self) -> Result<[u8; 4], String> {
+        let mut tag = [0u8; 4];
+        self.cursor
+            .read_exact(&mut tag)
+            .map_err(|e| format!("Failed to read tag: {}", e))?;
+        Ok(tag)
+    }
+
+    /// 读取一条浮点关键帧轨道（不含 4 字节标识）
+    fn read_track<const N: usize>(&mut self) -> Result<Track<[f32; N]>, String> {
+        self.read_track_with(Self::read_vec::<N>)
+    }
+
+    /// 读取一条关键帧轨道，`read_value` 读取单个值（关键帧值与切线）
+    fn read_track_with<T>(
+        &mut self,
+        read_value: fn(&mut Self) -> Result<T, String>,
+    ) -> Result<Track<T>, String> {
+        let count = self.read_u32()?;
+        let interpolation = self.read_u32()?;
+        let global_sequence_id = self.read_u32()? as i32;
+        let has_tangents = interpolation > 1;
+
+        let mut keys = Vec::with_capacity(count.min(4096) as usize);
+        for _ in 0..count {
+            let frame = self.read_u32()?;
+            let value = read_value(self)?;
+            let (in_tan, out_tan) = if has_tangents {
+                (Some(read_value(self)?), Some(read_value(self)?))
+            } else {
+                (None, None)
+            };
+            keys.push(Keyframe {
+                frame,
+                value,
+                in_tan,
+                out_tan,
+            });
+        }
+
+        Ok(Track {
+            interpolation,
+            global_sequence_id,
+            keys,
+        })
+    }
+
+    fn parse_sequences(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() + SEQUENCE_RECORD_SIZE as u64 <= chunk_end {
+            let name = self.read_string(NAME_LEN)?;
+            let interval = [self.read_u32()?, self.read_u32()?];
+            let move_speed = self.read_f32()?;
+            let non_looping = self.read_u32()? & 1 != 0;
+            let rarity = self.read_f32()?;
+            let sync_point = self.read_u32()?;
+            let bounds_radius = self.read_f32()?;
+            let [x, y, z] = self.read_vec::<3>()?;
+            let minimum_extent = Vertex { x, y, z };
+            let [x, y, z] = self.read_vec::<3>()?;
+            let maximum_extent = Vertex { x, y, z };
+            model.sequences.push(Sequence {
+                name,
+                interval,
+                move_speed,
+                non_looping,
+                rarity,
+                sync_point,
+                bounds_radius,
+                minimum_extent,
+                maximum_extent,
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_bones(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() < chunk_end {
+            let node = self.parse_node()?;
+            let geoset_id = self.read_u32()?;
+            let geoset_animation_id = self.read_u32()?;
+            model.bones.push(Bone {
+                node,
+                geoset_id,
+                geoset_animation_id,
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_textures(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() + TEXTURE_RECORD_SIZE as u64 <= chunk_end {
+            let replaceable_id = self.read_u32()?;
+            let path = self.read_string(PATH_LEN)?;
+            let flags = self.read_u32()?;
+            model.textures.push(Texture::new(replaceable_id, path, flags));
+        }
+        Ok(())
+    }
+
+    fn parse_materials(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() + 4 <= chunk_end {
+            let material_start = self.cursor.position();
+            let material_end = material_start + self.read_u32()? as u64;
+            if material_end <= material_start + 4 || material_end > chunk_end {
+                return Err("Corrupt material record".to_string());
+            }
+
+            let mut material = Material {
+                priority_plane: self.read_u32()?,
+                flags: self.read_u32()?,
+                shader: String::new(),
+                layers: Vec::new(),
+            };
+            if model.version > 800 {
+                material.shader = self.read_string(NAME_LEN)?;
+            }
+
+            if &self.read_tag()? == b"LAYS" {
+                let layer_count = self.read_u32()?;
+                for _ in 0..layer_count {
+                    let layer_start = self.cursor.position();
+                    let layer_end = layer_start + self.read_u32()? as u64;
+                    let mut layer = Layer {
+                        filter_mode: self.read_u32()?,
+                        shading_flags: self.read_u32()?,
+                        texture_id: self.read_u32()?,
+                        texture_animation_id: self.read_u32()?,
+                        coord_id: self.read_u32()?,
+                        alpha: self.read_f32()?,
+                        alpha_track: None,
+                        texture_track: None,
+                        emissive_gain: None,
+                        fresnel_color: None,
+                        fresnel_opacity: None,
+                        fresnel_team_color: None,
+                    };
+                    if model.version > 800 {
+                        layer.emissive_gain = Some(self.read_f32()?);
                     }
-                    // 回退，继续读取 geoset
-                    self.cursor.seek(SeekFrom::Start(start_pos)).ok();
+                    if model.version > 900 {
+                        layer.fresnel_color = Some(self.read_vec::<3>()?);
+                        layer.fresnel_opacity = Some(self.read_f32()?);
+                        layer.fresnel_team_color = Some(self.read_f32()?);
+                    }
+                    while self.cursor.position() + 4 <= layer_end {
+                        match &self.read_tag()? {
+                            b"KMTA" => layer.alpha_track = Some(self.read_track::<1>()?),
+                            b"KMTF" => layer.texture_track = Some(self.read_track_with(Self::read_u32)?),
+                            // 自发光与菲涅尔动画暂不保留，只跳过
+                            b"KMTE" | b"KFCA" | b"KFTC" => {
+                                self.read_track::<1>()?;
+                            }
+                            b"KFC3" => {
+                                self.read_track::<3>()?;
+                            }
+                            _ => break,
+                        }
+                    }
+                    material.layers.push(layer);
+                    // 跳过未识别的字段与轨道
+                    self.cursor
+                        .seek(SeekFrom::Start(layer_end))
+                        .map_err(|e| format!("Failed to skip layer: {}", e))?;
+                }
+            }
+
+            model.materials.push(material);
+            self.cursor
+                .seek(SeekFrom::Start(material_end))
+                .map_err(|e| format!("Failed to skip material: {}", e))?;
+        }
+        Ok(())
+    }
+
+    /// 读取节点公共头及其变换轨道（KGTR/KGRT/KGSC）
+    fn parse_node(&mut self) -> Result<Node, String> {
+        let node_start = self.cursor.position();
+        let inclusive_size = self.read_u32()?;
+        let node_end = node_start + inclusive_size as u64;
+
+        let name = self.read_string(NAME_LEN)?;
+        let object_id = self.read_u32()?;
+        let parent_id = self.read_u32()?;
+        let flags = self.read_u32()?;
+        let mut node = Node {
+            name,
+            object_id,
+            parent_id,
+            flags,
+            decoded_flags: NodeFlags::from_bits(flags),
+            translation: None,
+            rotation: None,
+            scaling: None,
+        };
+
+        while self.cursor.position() + 4 <= node_end {
+            match &self.read_tag()? {
+                b"KGTR" => node.translation = Some(self.read_track::<3>()?),
+                b"KGRT" => node.rotation = Some(self.read_track::<4>()?),
+                b"KGSC" => node.scaling = Some(self.read_track::<3>()?),
+                _ => break,
+            }
+        }
+
+        self.cursor
+            .seek(SeekFrom::Start(node_end))
+            .map_err(|e| format!("Failed to skip node: {}", e))?;
+
+        Ok(node)
+    }
+
+    fn parse_attachments(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() + 4 <= chunk_end {
+            let start = self.cursor.position();
+            let end = start + self.read_u32()? as u64;
+            if end <= start + 4 || end > chunk_end {
+                return Err("Corrupt attachment record".to_string());
+            }
+            let node = self.parse_node()?;
+            let path = self.read_string(PATH_LEN)?;
+            let attachment_id = self.read_u32()?;
+            model.attachments.push(Attachment {
+                node,
+                path,
+                attachment_id,
+            });
+
+            // 跳过 KATV 可见性轨道
+            self.cursor
+                .seek(SeekFrom::Start(end))
+                .map_err(|e| format!("Failed to skip attachment: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn parse_geoset_animations(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() + 4 <= chunk_end {
+            let start = self.cursor.position();
+            let end = start + self.read_u32()? as u64;
+            if end <= start + 4 || end > chunk_end {
+                return Err("Corrupt geoset animation record".to_string());
+            }
+
+            let mut animation = GeosetAnimation {
+                alpha: self.read_f32()?,
+                flags: self.read_u32()?,
+                color: self.read_vec::<3>()?,
+                geoset_id: self.read_u32()?,
+                alpha_track: None,
+                color_track: None,
+            };
+            while self.cursor.position() + 4 <= end {
+                match &self.read_tag()? {
+                    b"KGAO" => animation.alpha_track = Some(self.read_track::<1>()?),
+                    b"KGAC" => animation.color_track = Some(self.read_track::<3>()?),
+                    _ => break,
+                }
+            }
+            model.geoset_animations.push(animation);
+
+            self.cursor
+                .seek(SeekFrom::Start(end))
+                .map_err(|e| format!("Failed to skip geoset animation: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn parse_texture_animations(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() + 4 <= chunk_end {
+            let start = self.cursor.position();
+            let end = start + self.read_u32()? as u64;
+            if end <= start + 4 || end > chunk_end {
+                return Err("Corrupt texture animation record".to_string());
+            }
+
+            let mut animation = TextureAnimation::default();
+            while self.cursor.position() + 4 <= end {
+                match &self.read_tag()? {
+                    b"KTAT" => animation.translation = Some(self.read_track::<3>()?),
+                    b"KTAR" => animation.rotation = Some(self.read_track::<4>()?),
+                    b"KTAS" => animation.scaling = Some(self.read_track::<3>()?),
+                    _ => break,
+                }
+            }
+            model.texture_animations.push(animation);
+
+            self.cursor
+                .seek(SeekFrom::Start(end))
+                .map_err(|e| format!("Failed to skip texture animation: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn parse_collision_shapes(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() < chunk_end {
+            let node = self.parse_node()?;
+            let shape_type = self.read_u32()?;
+            let vertex_count = match shape_type {
+                COLLISION_SPHERE => 1,
+                COLLISION_BOX | COLLISION_PLANE | COLLISION_CYLINDER => 2,
+                _ => return Err(format!("Unknown collision shape type {}", shape_type)),
+            };
+
+            let mut vertices = Vec::with_capacity(vertex_count);
+            for _ in 0..vertex_count {
+                let [x, y, z] = self.read_vec::<3>()?;
+                vertices.push(Vertex { x, y, z });
+            }
+            let radius = match shape_type {
+                COLLISION_SPHERE | COLLISION_CYLINDER => self.read_f32()?,
+                _ => 0.0,
+            };
+
+            model.collision_shapes.push(CollisionShape {
+                node,
+                shape_type,
+                vertices,
+                radius,
+            });
+        }
+        Ok(())
+    }
+
+    fn parse_lights(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() + 4 <= chunk_end {
+            let start = self.cursor.position();
+            let end = start + self.read_u32()? as u64;
+            if end <= start + 4 || end > chunk_end {
+                return Err("Corrupt light record".to_string());
+            }
+            let node = self.parse_node()?;
+            model.lights.push(Light {
+                node,
+                light_type: self.read_u32()?,
+                attenuation_start: self.read_f32()?,
+                attenuation_end: self.read_f32()?,
+                color: self.read_vec::<3>()?,
+                intensity: self.read_f32()?,
+                ambient_color: self.read_vec::<3>()?,
+                ambient_intensity: self.read_f32()?,
+            });
+
+            // 跳过 KLA*/KLB* 动画轨道
+            self.cursor
+                .seek(SeekFrom::Start(end))
+                .map_err(|e| format!("Failed to skip light: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn parse_cameras(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() + 4 <= chunk_end {
+            let start = self.cursor.position();
+            let camera_end = start + self.read_u32()? as u64;
+            if camera_end <= start + 4 || camera_end > chunk_end {
+                return Err("Corrupt camera record".to_string());
+            }
+            let name = self.read_string(NAME_LEN)?;
+            let [x, y, z] = self.read_vec::<3>()?;
+            let position = Vertex { x, y, z };
+            let field_of_view = self.read_f32()?;
+            let far_clip = self.read_f32()?;
+            let near_clip = self.read_f32()?;
+            let [x, y, z] = self.read_vec::<3>()?;
+            let mut camera = Camera {
+                name,
+                position,
+                field_of_view,
+                far_clip,
+                near_clip,
+                target: Vertex { x, y, z },
+                translation: None,
+                rotation: None,
+                target_translation: None,
+            };
+
+            while self.cursor.position() + 4 <= camera_end {
+                match &self.read_tag()? {
+                    b"KCTR" => camera.translation = Some(self.read_track::<3>()?),
+                    b"KCRL" => camera.rotation = Some(self.read_track::<1>()?),
+                    b"KTTR" => camera.target_translation = Some(self.read_track::<3>()?),
+                    _ => break,
                 }
-                Err(_) => break,
             }
+            model.cameras.push(camera);
+
+            self.cursor
+                .seek(SeekFrom::Start(camera_end))
+                .map_err(|e| format!("Failed to skip camera: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn parse_particle_emitters(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() + 4 <= chunk_end {
+            let start = self.cursor.position();
+            let end = start + self.read_u32()? as u64;
+            if end <= start + 4 || end > chunk_end {
+                return Err("Corrupt particle emitter record".to_string());
+            }
+            let node = self.parse_node()?;
+            let emission_rate = self.read_f32()?;
+            let gravity = self.read_f32()?;
+            let longitude = self.read_f32()?;
+            let latitude = self.read_f32()?;
+            let path = self.read_string(PATH_LEN)?;
+            let life_span = self.read_f32()?;
+            let initial_velocity = self.read_f32()?;
+            model.particle_emitters.push(ParticleEmitter {
+                node,
+                emission_rate,
+                gravity,
+                longitude,
+                latitude,
+                path,
+                life_span,
+                initial_velocity,
+            });
+
+            // 跳过 KPE* 动画轨道
+            self.cursor
+                .seek(SeekFrom::Start(end))
+                .map_err(|e| format!("Failed to skip particle emitter: {}", e))?;
+        }
+        Ok(())
+    }
 
-            // 读取 geoset size
-            let geoset_size = match self.cursor.read_u32::<LittleEndian>() {
-                Ok(size) => size,
-                Err(_) => break,
+    fn parse_particle_emitters2(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        while self.cursor.position() + 4 <= chunk_end {
+            let start = self.cursor.position();
+            let emitter_end = start + self.read_u32()? as u64;
+            if emitter_end <= start + 4 || emitter_end > chunk_end {
+                return Err("Corrupt particle emitter 2 record".to_string());
+            }
+            let node = self.parse_node()?;
+            let speed = self.read_f32()?;
+            let variation = self.read_f32()?;
+            let latitude = self.read_f32()?;
+            let gravity = self.read_f32()?;
+            let life_span = self.read_f32()?;
+            let emission_rate = self.read_f32()?;
+            let width = self.read_f32()?;
+            let length = self.read_f32()?;
+            let filter_mode = self.read_u32()?;
+            let rows = self.read_u32()?;
+            let columns = self.read_u32()?;
+            let head_or_tail = self.read_u32()?;
+            let tail_length = self.read_f32()?;
+            let time = self.read_f32()?;
+            let segment_color = [self.read_vec::<3>()?, self.read_vec::<3>()?, self.read_vec::<3>()?];
+            let mut segment_alpha = [0u8; 3];
+            self.cursor
+                .read_exact(&mut segment_alpha)
+                .map_err(|e| format!("Failed to read segment alpha: {}", e))?;
+            let segment_scaling = self.read_vec::<3>()?;
+            let mut intervals = [[0u32; 3]; 4];
+            for interval in intervals.iter_mut().flatten() {
+                *interval = self.read_u32()?;
+            }
+            let [head_life, head_decay, tail_life, tail_decay] = intervals;
+            let texture_id = self.read_u32()?;
+            let squirt = self.read_u32()?;
+            let priority_plane = self.read_u32()? as i32;
+            let replaceable_id = self.read_u32()?;
+            let mut emitter = ParticleEmitter2 {
+                node,
+                speed,
+                variation,
+                latitude,
+                gravity,
+                life_span,
+                emission_rate,
+                width,
+                length,
+                filter_mode,
+                rows,
+                columns,
+                head_or_tail,
+                tail_length,
+                time,
+                segment_color,
+                segment_alpha,
+                segment_scaling,
+                head_intervals: [head_life, head_decay],
+                tail_intervals: [tail_life, tail_decay],
+                texture_id,
+                squirt,
+                priority_plane,
+                replaceable_id,
+                speed_track: None,
+                variation_track: None,
+                latitude_track: None,
+                gravity_track: None,
+                emission_rate_track: None,
+                length_track: None,
+                width_track: None,
+                visibility_track: None,
             };
 
-            self.parse_single_geoset(model, geoset_size)?;
+            while self.cursor.position() + 4 <= emitter_end {
+                let tag = self.read_tag()?;
+                if tag[0] != b'K' {
+                    break;
+                }
+                // PRE2 的轨道都是单个浮点数，未识别的轨道按同样格式读过
+                let track = Some(self.read_track::<1>()?);
+                match &tag {
+                    b"KP2S" => emitter.speed_track = track,
+                    b"KP2R" => emitter.variation_track = track,
+                    b"KP2L" => emitter.latitude_track = track,
+                    b"KP2G" => emitter.gravity_track = track,
+                    b"KP2E" => emitter.emission_rate_track = track,
+                    b"KP2N" => emitter.length_track = track,
+                    b"KP2W" => emitter.width_track = track,
+                    b"KP2V" => emitter.visibility_track = track,
+                    _ => {}
+                }
+            }
+            model.particle_emitters2.push(emitter);
+
+            self.cursor
+                .seek(SeekFrom::Start(emitter_end))
+                .map_err(|e| format!("Failed to skip particle emitter: {}", e))?;
+        }
+        Ok(())
+    }
+
+    fn parse_geosets(&mut self, model: &mut MdxModel, chunk_end: u64) -> Result<(), String> {
+        // GEOS chunk 包含多个 geoset
+        // 每个 geoset 以包含自身的 size 开头，后跟自己的几何数据
+        while self.cursor.position() + 4 <= chunk_end {
+            let geoset_start = self.cursor.position();
+            let geoset_size = self.read_u32()?;
+            if geoset_size == 0 {
+                break;
+            }
+
+            let geoset = self.parse_single_geoset(geoset_start + geoset_size as u64, model.version)?;
+            model.geosets.push(geoset);
         }
 
         Ok(())
     }
 
-    fn parse_single_geoset(&mut self, model: &mut MdxModel, size: u32) -> Result<(), String> {
-        let geoset_start = self.cursor.position();
-        let geoset_end = geoset_start + size as u64;
+    fn parse_single_geoset(&mut self, geoset_end: u64, version: u32) -> Result<Geoset, String> {
+        let mut geoset = Geoset::default();
+        // PTYP、PCNT 位于 PVTX 之前，先暂存
+        let mut primitive_types = Vec::new();
+        let mut primitive_counts = Vec::new();
 
         // 读取 geoset 内的 sub-chunks
         while self.cursor.position() < geoset_end {
@@ -247,77 +1366,118 @@ impl MdxParser {
             match &chunk_id {
                 b"VRTX" => {
                     // Vertices
-                    let count = self
-                        .cursor
-                        .read_u32::<LittleEndian>()
-                        .map_err(|e| format!("Failed to read vertex count: {}", e))?;
-
+                    let count = self.read_vec3_count(geoset_end, "vertex")?;
+                    geoset.vertices.reserve(count);
                     for _ in 0..count {
-                        let x = self.cursor.read_f32::<LittleEndian>().unwrap_or(0.0);
-                        let y = self.cursor.read_f32::<LittleEndian>().unwrap_or(0.0);
-                        let z = self.cursor.read_f32::<LittleEndian>().unwrap_or(0.0);
-                        model.vertices.push(Vertex { x, y, z });
+                        let [x, y, z] = self.read_vec::<3>()?;
+                        geoset.vertices.push(Vertex { x, y, z });
                     }
                 }
                 b"NRMS" => {
                     // Normals
-                    let count = self.cursor.read_u32::<LittleEndian>().unwrap_or(0);
+                    let count = self.read_vec3_count(geoset_end, "normal")?;
+                    geoset.normals.reserve(count);
                     for _ in 0..count {
-                        let x = self.cursor.read_f32::<LittleEndian>().unwrap_or(0.0);
-                        let y = self.cursor.read_f32::<LittleEndian>().unwrap_or(0.0);
-                        let z = self.cursor.read_f32::<LittleEndian>().unwrap_or(0.0);
-                        model.normals.push(Normal { x, y, z });
+                        let [x, y, z] = self.read_vec::<3>()?;
+                        geoset.normals.push(Normal { x, y, z });
                     }
                 }
-                b"UVBS" => {
-                    // UV coordinates (texture coordinates)
-                    let count = self.cursor.read_u32::<LittleEndian>().unwrap_or(0);
+                b"PTYP" => {
+                    // Primitive types (每组一个类型)
+                    let count = self.read_u32()?;
                     for _ in 0..count {
-                        let u = self.cursor.read_f32::<LittleEndian>().unwrap_or(0.0);
-                        let v = self.cursor.read_f32::<LittleEndian>().unwrap_or(0.0);
-                        model.uvs.push(UV { u, v });
+                        primitive_types.push(self.read_u32()?);
                     }
                 }
-                b"PTYP" => {
-                    // Primitive types (face types)
-                    let count = self.cursor.read_u32::<LittleEndian>().unwrap_or(0);
-                    // 跳过面类型数据（通常是 4 = 三角形）
-                    self.cursor.seek(SeekFrom::Current((count * 4) as i64)).ok();
-                }
                 b"PCNT" => {
-                    // Primitive counts (indices per primitive)
-                    let count = self.cursor.read_u32::<LittleEndian>().unwrap_or(0);
-                    // 跳过原始计数数据
-                    self.cursor.seek(SeekFrom::Current((count * 4) as i64)).ok();
+                    // Primitive counts (每组的索引数)
+                    let count = self.read_u32()?;
+                    for _ in 0..count {
+                        primitive_counts.push(self.read_u32()?);
+                    }
                 }
                 b"PVTX" => {
-                    // Primitive vertex indices (faces)
-                    let count = self.cursor.read_u32::<LittleEndian>().unwrap_or(0);
-                    
-                    // PVTX 包含三角形索引，每3个u16组成一个面
-                    for _ in 0..(count / 3) {
-                        let i0 = self.cursor.read_u16::<LittleEndian>().unwrap_or(0);
-                        let i1 = self.cursor.read_u16::<LittleEndian>().unwrap_or(0);
-                        let i2 = self.cursor.read_u16::<LittleEndian>().unwrap_or(0);
-                        model.faces.push(Face {
-                            indices: [i0, i1, i2],
-                        });
+                    // Primitive vertex indices，按 PTYP/PCNT 分组
+                    let count = self.read_u32()?;
+                    let mut indices = Vec::with_capacity(count.min(4096) as usize);
+                    for _ in 0..count {
+                        indices.push(
+                            self.cursor
+                                .read_u16::<LittleEndian>()
+                                .map_err(|e| format!("Failed to read face index: {}", e))?,
+                        );
                     }
+                    geoset.primitive_groups = split_primitive_groups(
+                        &indices,
+                        &primitive_types,
+                        &primitive_counts,
+                        &mut geoset.faces,
+                    );
                 }
-                b"GNDX" | b"MTGC" | b"MATS" => {
-                    // 其他 geoset 数据，暂时跳过
-                    let count = self.cursor.read_u32::<LittleEndian>().unwrap_or(0);
-                    let skip_size = match &chunk_id {
-                        b"GNDX" => count * 1,  // u8 group indices
-                        b"MTGC" => count * 4,  // u32 matrix counts
-                        b"MATS" => count * 4,  // u32 matrix indices
-                        _ => 0,
-                    };
-                    self.cursor.seek(SeekFrom::Current(skip_size as i64)).ok();
+                b"GNDX" => {
+                    // Vertex groups (u8 per vertex)
+                    let count = self.read_u32()?;
+                    for _ in 0..count {
+                        let group = self
+                            .cursor
+                            .read_u8()
+                            .map_err(|e| format!("Failed to read vertex group: {}", e))?;
+                        geoset.vertex_groups.push(group);
+                    }
+                }
+                b"MTGC" => {
+                    // Matrix group counts
+                    let count = self.read_u32()?;
+                    for _ in 0..count {
+                        let size = self.read_u32()?;
+                        geoset.matrix_groups.push(size);
+                    }
+                }
+                b"MATS" => {
+                    // Matrix indices (bone object ids)
+                    let count = self.read_u32()?;
+                    for _ in 0..count {
+                        let index = self.read_u32()?;
+                        geoset.matrix_indices.push(index);
+                    }
+                    // MATS 之后是不带标识的定长字段
+                    self.parse_geoset_properties(&mut geoset, version)?;
+                }
+                b"TANG" => {
+                    // Tangents (v900+, 4 floats per vertex)
+                    let count = self.read_u32()?;
+                    self.cursor.seek(SeekFrom::Current(count as i64 * 16)).ok();
+                }
+                b"SKIN" => {
+                    // Skin weights (v900+, count 为字节数，每顶点 8 字节)
+                    let count = self.read_u32()?;
+                    for _ in 0..count / 8 {
+                        let mut entry = [0u8; 8];
+                        self.cursor
+                            .read_exact(&mut entry)
+                            .map_err(|e| format!("Failed to read skin weights: {}", e))?;
+                        geoset.skin.push(entry);
+                    }
+                    self.cursor.seek(SeekFrom::Current(count as i64 % 8)).ok();
+                }
+                b"UVAS" => {
+                    // UV 层数，每层是一个 UVBS 块
+                    let set_count = self.read_u32()?;
+                    for _ in 0..set_count {
+                        if &self.read_tag()? != b"UVBS" {
+                            return Err("Expected UVBS after UVAS".to_string());
+                        }
+                        let count = self.read_u32()?;
+                        let mut uvs = Vec::with_capacity(count.min(4096) as usize);
+                        for _ in 0..count {
+                            let [u, v] = self.read_vec::<2>()?;
+                            uvs.push(UV { u, v });
+                        }
+                        geoset.uvs.push(uvs);
+                    }
                 }
                 _ => {
                     // 未知 chunk，跳到 geoset 结尾
-                    self.cursor.seek(SeekFrom::Start(geoset_end)).ok();
                     break;
                 }
             }
@@ -326,29 +1486,137 @@ impl MdxParser {
         // 确保指针在 geoset 结尾
         self.cursor.seek(SeekFrom::Start(geoset_end)).ok();
 
-        Ok(())
+        // SKIN 必须与 VRTX 逐顶点对应，否则权重会错位；丢弃后回退到 GNDX/MTGC 矩阵组
+        if !geoset.skin.is_empty() && geoset.skin.len() != geoset.vertices.len() {
+            geoset.skin.clear();
+        }
+
+        geoset.bounds = calculate_bounds(&geoset.vertices);
+        Ok(geoset)
     }
 
-    fn calculate_bounds(&self, model: &mut MdxModel) {
-        if model.vertices.is_empty() {
-            return;
+    /// 读取 MATS 之后的材质、选择组、LOD 与 extent 字段
+    fn parse_geoset_properties(&mut self, geoset: &mut Geoset, version: u32) -> Result<(), String> {
+        geoset.material_id = self.read_u32()?;
+        geoset.selection_group = self.read_u32()?;
+        geoset.selection_flags = self.read_u32()?;
+
+        if version > 800 {
+            self.read_u32()?; // LOD
+            self.read_string(NAME_LEN)?; // LOD 名称
         }
 
-        let mut min = model.vertices[0];
-        let mut max = model.vertices[0];
+        // 整体 extent (bounds_radius + min + max)，以及每个序列的 extent
+        self.cursor.seek(SeekFrom::Current(28)).ok();
+        let extent_count = self.read_u32()?;
+        self.cursor
+            .seek(SeekFrom::Current(extent_count as i64 * 28))
+            .map_err(|e| format!("Failed to skip geoset extents: {}", e))?;
+        Ok(())
+    }
+}
+
+/// 按 PTYP/PCNT 把 PVTX 索引拆成图元分组，只把三角形列表分组展开为面
+/// 缺少 PTYP/PCNT 时整段视为一个三角形列表
+pub(crate) fn split_primitive_groups(
+    indices: &[u16],
+    types: &[u32],
+    counts: &[u32],
+    faces: &mut Vec<Face>,
+) -> Vec<PrimitiveGroup> {
+    let groups: Vec<(u32, u32)> = if types.is_empty() || counts.is_empty() {
+        vec![(PRIMITIVE_TRIANGLES, indices.len() as u32)]
+    } else {
+        types.iter().copied().zip(counts.iter().copied()).collect()
+    };
 
-        for vertex in &model.vertices {
-            min.x = min.x.min(vertex.x);
-            min.y = min.y.min(vertex.y);
-            min.z = min.z.min(vertex.z);
+    let mut result = Vec::with_capacity(groups.len());
+    let mut offset = 0usize;
+    for (primitive_type, index_count) in groups {
+        let end = (offset + index_count as usize).min(indices.len());
+        let first_face = faces.len() as u32;
+        if primitive_type == PRIMITIVE_TRIANGLES {
+            for tri in indices[offset..end].chunks_exact(3) {
+                faces.push(Face {
+                    indices: [tri[0], tri[1], tri[2]],
+                });
+            }
+        }
+        result.push(PrimitiveGroup {
+            primitive_type,
+            index_count,
+            first_face,
+            face_count: faces.len() as u32 - first_face,
+        });
+        offset = end;
+    }
+    result
+}
 
-            max.x = max.x.max(vertex.x);
-            max.y = max.y.max(vertex.y);
-            max.z = max.z.max(vertex.z);
+/// 按面法线（未归一化，面积加权）累加到顶点并归一化；不属于任何面的顶点法线朝 +Z
+pub fn calculate_smooth_normals(vertices: &[Vertex], faces: &[Face]) -> Vec<Normal> {
+    let mut sums = vec![[0.0f32; 3]; vertices.len()];
+    for face in faces {
+        let [a, b, c] = face.indices.map(|i| i as usize);
+        let (Some(va), Some(vb), Some(vc)) = (vertices.get(a), vertices.get(b), vertices.get(c))
+        else {
+            continue;
+        };
+        let e1 = [vb.x - va.x, vb.y - va.y, vb.z - va.z];
+        let e2 = [vc.x - va.x, vc.y - va.y, vc.z - va.z];
+        let n = [
+            e1[1] * e2[2] - e1[2] * e2[1],
+            e1[2] * e2[0] - e1[0] * e2[2],
+            e1[0] * e2[1] - e1[1] * e2[0],
+        ];
+        for index in [a, b, c] {
+            for k in 0..3 {
+                sums[index][k] += n[k];
+            }
         }
+    }
+
+    sums.into_iter()
+        .map(|[x, y, z]| {
+            let length = (x * x + y * y + z * z).sqrt();
+            if length > f32::EPSILON {
+                Normal {
+                    x: x / length,
+                    y: y / length,
+                    z: z / length,
+                }
+            } else {
+                Normal { x: 0.0, y: 0.0, z: 1.0 }
+            }
+        })
+        .collect()
+}
+
+/// 计算一组顶点的轴对齐包围盒
+pub fn calculate_bounds<'a, I>(vertices: I) -> BoundingBox
+where
+    I: IntoIterator<Item = &'a Vertex>,
+{
+    let mut vertices = vertices.into_iter();
+    let first = match vertices.next() {
+        Some(vertex) => *vertex,
+        None => return BoundingBox::default(),
+    };
+
+    let mut min = first;
+    let mut max = first;
 
-        model.bounds = BoundingBox { min, max };
+    for vertex in vertices {
+        min.x = min.x.min(vertex.x);
+        min.y = min.y.min(vertex.y);
+        min.z = min.z.min(vertex.z);
+
+        max.x = max.x.max(vertex.x);
+        max.y = max.y.max(vertex.y);
+        max.z = max.z.max(vertex.z);
     }
+
+    BoundingBox { min, max }
 }
 
 #[cfg(test)]
@@ -359,6 +1627,7 @@ mod tests {
     fn test_chunk_type_parsing() {
         assert_eq!(ChunkType::from_bytes(b"VERS"), ChunkType::Vers);
         assert_eq!(ChunkType::from_bytes(b"GEOS"), ChunkType::Geos);
+        assert_eq!(ChunkType::from_bytes(b"CAMS"), ChunkType::Cams);
         assert_eq!(ChunkType::from_bytes(b"XXXX"), ChunkType::Unknown);
     }
 
@@ -366,4 +1635,355 @@ mod tests {
     fn test_mdx_magic() {
         assert_eq!(MDX_MAGIC, b"MDLX");
     }
+
+    fn geoset_bytes(vertex_count: u32, skin: &[u8]) -> Vec<u8> {
+        let mut data = b"VRTX".to_vec();
+        data.extend_from_slice(&vertex_count.to_le_bytes());
+        data.extend(std::iter::repeat_n(0u8, vertex_count as usize * 12));
+        if !skin.is_empty() {
+            data.extend_from_slice(b"SKIN");
+            data.extend_from_slice(&(skin.len() as u32).to_le_bytes());
+            data.extend_from_slice(skin);
+        }
+        data
+    }
+
+    #[test]
+    fn test_skip_corrupt_chunk() {
+        let mut data = MDX_MAGIC.to_vec();
+        let mut push_chunk = |tag: &[u8; 4], payload: &[u8]| {
+            data.extend_from_slice(tag);
+            data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            data.extend_from_slice(payload);
+        };
+        push_chunk(b"VERS", &800u32.to_le_bytes());
+        // geoset 中 MTGC 声明的数量超出文件
+        let mut geoset = 12u32.to_le_bytes().to_vec();
+        geoset.extend_from_slice(b"MTGC");
+        geoset.extend_from_slice(&1000u32.to_le_bytes());
+        push_chunk(b"GEOS", &geoset);
+        push_chunk(b"GLBS", &1000u32.to_le_bytes());
+        data.extend_from_slice(b"TEXS");
+        data.extend_from_slice(&100u32.to_le_bytes()); // 声明长度超出文件
+
+        let model = MdxParser::new(data).unwrap().parse().unwrap();
+        assert_eq!(model.version, 800);
+        assert_eq!(model.global_sequences, vec![1000]);
+        assert_eq!(model.warnings.len(), 2);
+        assert!(model.warnings[0].starts_with("Skipped GEOS chunk"));
+        assert!(model.warnings[1].starts_with("TEXS chunk is truncated"));
+    }
+
+    /// 构造只含一个 chunk 的模型，后接 GLBS 以确认之后的 chunk 仍能解析
+    fn model_with_chunk(tag: &[u8; 4], payload: &[u8]) -> MdxModel {
+        let mut data = MDX_MAGIC.to_vec();
+        data.extend_from_slice(tag);
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(payload);
+        data.extend_from_slice(b"GLBS");
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&1000u32.to_le_bytes());
+        let model = MdxParser::new(data).unwrap().parse().unwrap();
+        assert_eq!(model.global_sequences, vec![1000]);
+        model
+    }
+
+    #[test]
+    fn test_zero_size_record() {
+        // 记录长度为 0 时不能原地循环，整个 chunk 作为损坏跳过
+        for tag in [b"LITE", b"ATCH", b"PREM", b"PRE2", b"CAMS"] {
+            let model = model_with_chunk(tag, &0u32.to_le_bytes());
+            assert_eq!(model.warnings.len(), 1);
+            let expected = format!("Skipped {} chunk", String::from_utf8_lossy(tag));
+            assert!(model.warnings[0].starts_with(&expected), "{:?}", model.warnings);
+        }
+    }
+
+    #[test]
+    fn test_huge_index_count() {
+        // 声明的数量远超数据时应返回错误，而不是按该数量预分配
+        for prefix in [&b"VRTX"[..], b"NRMS", b"PVTX", b"UVAS\x01\0\0\0UVBS"] {
+            let mut data = prefix.to_vec();
+            data.extend_from_slice(&u32::MAX.to_le_bytes());
+            let end = data.len() as u64;
+            let mut parser = MdxParser::new(data).unwrap();
+            assert!(parser.parse_single_geoset(end, 800).is_err());
+        }
+    }
+
+    #[test]
+    fn test_collision_shape_layouts() {
+        // 长方体/平面: 两个角点; 球体: 球心 + 半径; 圆柱体: 两端中心 + 半径
+        let shapes: [(u32, &[f32]); 4] = [
+            (COLLISION_BOX, &[0.0, 0.0, 0.0, 1.0, 2.0, 3.0]),
+            (COLLISION_PLANE, &[-1.0, -1.0, 0.0, 1.0, 1.0, 0.0]),
+            (COLLISION_SPHERE, &[4.0, 5.0, 6.0, 7.0]),
+            (COLLISION_CYLINDER, &[0.0, 0.0, 0.0, 0.0, 0.0, 10.0, 2.5]),
+        ];
+        let mut clid = Vec::new();
+        for (index, (shape_type, values)) in shapes.iter().enumerate() {
+            clid.extend_from_slice(&96u32.to_le_bytes());
+            let mut name = format!("Collision{}", index).into_bytes();
+            name.resize(NAME_LEN, 0);
+            clid.extend_from_slice(&name);
+            for field in [index as u32, NO_PARENT, 0x2000] {
+                clid.extend_from_slice(&field.to_le_bytes());
+            }
+            clid.extend_from_slice(&shape_type.to_le_bytes());
+            for value in *values {
+                clid.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+        let mut data = MDX_MAGIC.to_vec();
+        for (tag, payload) in [(b"CLID", clid), (b"GLBS", 1000u32.to_le_bytes().to_vec())] {
+            data.extend_from_slice(tag);
+            data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            data.extend_from_slice(&payload);
+        }
+
+        let model = MdxParser::new(data).unwrap().parse().unwrap();
+        assert!(model.warnings.is_empty(), "{:?}", model.warnings);
+        assert_eq!(model.global_sequences, vec![1000]);
+        let shapes = &model.collision_shapes;
+        assert_eq!(shapes.len(), 4);
+        for (index, shape) in shapes.iter().enumerate() {
+            assert_eq!(shape.node.name, format!("Collision{}", index));
+            assert!(shape.node.decoded_flags.collision_shape);
+            assert!(!shape.node.decoded_flags.bone);
+        }
+        assert_eq!(shapes[0].vertices.len(), 2);
+        assert_eq!(shapes[0].vertices[1].z, 3.0);
+        assert_eq!(shapes[1].vertices.len(), 2);
+        assert_eq!(shapes[1].radius, 0.0);
+        assert_eq!(shapes[2].vertices.len(), 1);
+        assert_eq!(shapes[2].vertices[0].x, 4.0);
+        assert_eq!(shapes[2].radius, 7.0);
+        assert_eq!(shapes[3].vertices.len(), 2);
+        assert_eq!(shapes[3].vertices[1].z, 10.0);
+        assert_eq!(shapes[3].radius, 2.5);
+    }
+
+    fn model_with_modl(with_animation_file: bool) -> MdxModel {
+        let mut modl = b"Footman".to_vec();
+        modl.resize(NAME_LEN, 0);
+        if with_animation_file {
+            modl.extend(std::iter::repeat_n(0u8, PATH_LEN));
+        }
+        for value in [5.0f32, -1.0, -2.0, 0.0, 1.0, 2.0, 4.0] {
+            modl.extend_from_slice(&value.to_le_bytes());
+        }
+        modl.extend_from_slice(&150u32.to_le_bytes()); // blend_time
+        let mut data = MDX_MAGIC.to_vec();
+        data.extend_from_slice(b"MODL");
+        data.extend_from_slice(&(modl.len() as u32).to_le_bytes());
+        data.extend_from_slice(&modl);
+        data.extend_from_slice(b"GLBS");
+        data.extend_from_slice(&4u32.to_le_bytes());
+        data.extend_from_slice(&1000u32.to_le_bytes());
+        MdxParser::new(data).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    fn test_model_info_layouts() {
+        for with_animation_file in [true, false] {
+            let model = model_with_modl(with_animation_file);
+            assert_eq!(model.name, "Footman");
+            assert_eq!(model.global_sequences, vec![1000]);
+            // 没有 geoset 时包围盒取 MODL 中的 extent
+            assert_eq!(model.overall_bounds.min.y, -2.0);
+            assert_eq!(model.overall_bounds.max.z, 4.0);
+            assert_eq!(model.overall_bounds_radius, 3.0);
+        }
+    }
+
+    #[test]
+    fn test_smooth_normals_of_cube() {
+        // 单位立方体，8 个共享顶点、12 个逆时针（朝外）三角面
+        let vertices: Vec<Vertex> = (0..8)
+            .map(|i| Vertex {
+                x: (i & 1) as f32,
+                y: ((i >> 1) & 1) as f32,
+                z: ((i >> 2) & 1) as f32,
+            })
+            .collect();
+        let quads = [
+            [0, 2, 3, 1], // -Z
+            [4, 5, 7, 6], // +Z
+            [0, 1, 5, 4], // -Y
+            [2, 6, 7, 3], // +Y
+            [0, 4, 6, 2], // -X
+            [1, 3, 7, 5], // +X
+        ];
+        let faces: Vec<Face> = quads
+            .iter()
+            .flat_map(|&[a, b, c, d]| [[a, b, c], [a, c, d]])
+            .map(|indices| Face { indices })
+            .collect();
+
+        let mut model = MdxModel::default();
+        model.geosets.push(Geoset {
+            vertices: vertices.clone(),
+            faces,
+            ..Default::default()
+        });
+        model.compute_missing_normals();
+
+        // 每个角的法线为单位向量，且大致沿中心指向该角的方向（三角化使各面权重略有差异）
+        let normals = &model.geosets[0].normals;
+        assert_eq!(normals.len(), 8);
+        for (vertex, normal) in vertices.iter().zip(normals) {
+            let length = (normal.x * normal.x + normal.y * normal.y + normal.z * normal.z).sqrt();
+            assert!((length - 1.0).abs() < 1e-5);
+            let outward = [vertex.x * 2.0 - 1.0, vertex.y * 2.0 - 1.0, vertex.z * 2.0 - 1.0];
+            let dot = (normal.x * outward[0] + normal.y * outward[1] + normal.z * outward[2]) / 3f32.sqrt();
+            assert!(dot > 0.9, "{:?} -> {:?}", vertex, normal);
+        }
+    }
+
+    #[test]
+    fn test_particle_emitter2_tracks() {
+        let mut body = 96u32.to_le_bytes().to_vec(); // 节点头
+        body.extend(std::iter::repeat_n(0u8, NAME_LEN));
+        body.extend_from_slice(&[0; 8]); // object_id, parent_id
+        body.extend_from_slice(&0x1000u32.to_le_bytes());
+        body.extend(std::iter::repeat_n(0u8, 171)); // 定长字段
+        for (tag, value) in [(b"KP2E", 30.0f32), (b"KP2X", 1.0), (b"KP2V", 0.0)] {
+            body.extend_from_slice(tag);
+            for field in [1u32, 1, u32::MAX, 100] {
+                body.extend_from_slice(&field.to_le_bytes()); // 键数, 线性插值, 全局序列, 帧
+            }
+            body.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut payload = (body.len() as u32 + 4).to_le_bytes().to_vec();
+        payload.extend_from_slice(&body);
+
+        let mut data = MDX_MAGIC.to_vec();
+        data.extend_from_slice(b"PRE2");
+        data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        data.extend_from_slice(&payload);
+
+        let model = MdxParser::new(data).unwrap().parse().unwrap();
+        assert!(model.warnings.is_empty(), "{:?}", model.warnings);
+        let emitter = &model.particle_emitters2[0];
+        assert_eq!(emitter.emission_rate_track.as_ref().unwrap().keys[0].value, [30.0]);
+        assert_eq!(emitter.visibility_track.as_ref().unwrap().keys[0].frame, 100);
+        assert!(emitter.speed_track.is_none());
+    }
+
+    #[test]
+    fn test_v1000_layer() {
+        let mut layer = 52u32.to_le_bytes().to_vec();
+        for value in [2u32, 0x10, 0, u32::MAX, 0] {
+            layer.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [0.5f32, 2.0, 1.0, 0.5, 0.25, 0.75, 0.1] {
+            layer.extend_from_slice(&value.to_le_bytes());
+        }
+        let mut material = (4 + 8 + NAME_LEN as u32 + 8 + 52).to_le_bytes().to_vec();
+        material.extend_from_slice(&[0; 8]); // priority_plane, flags
+        material.extend(std::iter::repeat_n(0u8, NAME_LEN)); // shader
+        material.extend_from_slice(b"LAYS");
+        material.extend_from_slice(&1u32.to_le_bytes());
+        material.extend_from_slice(&layer);
+
+        let mut data = MDX_MAGIC.to_vec();
+        for (tag, payload) in [(b"VERS", 1000u32.to_le_bytes().to_vec()), (b"MTLS", material)] {
+            data.extend_from_slice(tag);
+            data.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+            data.extend_from_slice(&payload);
+        }
+
+        let model = MdxParser::new(data).unwrap().parse().unwrap();
+        assert!(model.warnings.is_empty());
+        let layer = &model.materials[0].layers[0];
+        assert_eq!(layer.alpha, 0.5);
+        assert_eq!(layer.emissive_gain, Some(2.0));
+        assert_eq!(layer.fresnel_color, Some([1.0, 0.5, 0.25]));
+        assert_eq!(layer.fresnel_opacity, Some(0.75));
+        assert_eq!(layer.fresnel_team_color, Some(0.1));
+    }
+
+    #[test]
+    fn test_geoset_uv_layers() {
+        let mut data = geoset_bytes(2, &[]);
+        data.extend_from_slice(b"UVAS");
+        data.extend_from_slice(&2u32.to_le_bytes());
+        for layer in [[0.0f32, 0.25, 0.5, 0.75], [1.0, 0.5, 0.0, 0.5]] {
+            data.extend_from_slice(b"UVBS");
+            data.extend_from_slice(&2u32.to_le_bytes());
+            for value in layer {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        let end = data.len() as u64;
+        let mut parser = MdxParser::new(data).unwrap();
+        let geoset = parser.parse_single_geoset(end, 800).unwrap();
+        assert_eq!(geoset.uvs.len(), 2);
+        assert_eq!(geoset.first_uvs().len(), 2);
+        assert_eq!(geoset.uvs[0][1].v, 0.75);
+        assert_eq!(geoset.uvs[1][0].u, 1.0);
+    }
+
+    #[test]
+    fn test_geoset_skin() {
+        let skin = [0, 1, 0, 0, 200, 55, 0, 0, 1, 0, 0, 0, 255, 0, 0, 0];
+        let data = geoset_bytes(2, &skin);
+        let end = data.len() as u64;
+        let mut parser = MdxParser::new(data).unwrap();
+        let geoset = parser.parse_single_geoset(end, 900).unwrap();
+        assert_eq!(geoset.skin, vec![[0, 1, 0, 0, 200, 55, 0, 0], [1, 0, 0, 0, 255, 0, 0, 0]]);
+
+        // 条目数与顶点数不一致时丢弃
+        let data = geoset_bytes(3, &skin);
+        let end = data.len() as u64;
+        let mut parser = MdxParser::new(data).unwrap();
+        assert!(parser.parse_single_geoset(end, 900).unwrap().skin.is_empty());
+    }
+
+    #[test]
+    fn test_geoset_matrix_groups() {
+        // v800 geoset: 3 个顶点分属两个矩阵组 [0] 与 [0, 1]，MATS 之后跟材质等定长字段
+        let mut data = geoset_bytes(3, &[]);
+        data.extend_from_slice(b"GNDX");
+        data.extend_from_slice(&3u32.to_le_bytes());
+        data.extend_from_slice(&[0, 1, 1]);
+        let mut push_u32s = |tag: &[u8; 4], values: &[u32]| {
+            data.extend_from_slice(tag);
+            data.extend_from_slice(&(values.len() as u32).to_le_bytes());
+            for value in values {
+                data.extend_from_slice(&value.to_le_bytes());
+            }
+        };
+        push_u32s(b"MTGC", &[1, 2]);
+        push_u32s(b"MATS", &[4, 4, 7]);
+        for value in [2u32, 0, 0] {
+            data.extend_from_slice(&value.to_le_bytes()); // material, selection group, flags
+        }
+        data.extend(std::iter::repeat_n(0u8, 28)); // extent
+        data.extend_from_slice(&0u32.to_le_bytes()); // 序列 extent 数
+
+        let end = data.len() as u64;
+        let mut parser = MdxParser::new(data).unwrap();
+        let geoset = parser.parse_single_geoset(end, 800).unwrap();
+        assert_eq!(geoset.vertex_groups, vec![0, 1, 1]);
+        assert_eq!(geoset.matrix_groups, vec![1, 2]);
+        assert_eq!(geoset.matrix_indices, vec![4, 4, 7]);
+        assert_eq!(geoset.material_id, 2);
+        assert!(geoset.skin.is_empty());
+    }
+
+    #[test]
+    fn test_split_primitive_groups() {
+        // 一组三角形（6 个索引）+ 一组三角形带（4 个索引）+ 一组三角形（3 个索引）
+        let indices = [0, 1, 2, 2, 1, 3, 4, 5, 6, 7, 8, 9, 10];
+        let mut faces = Vec::new();
+        let groups = split_primitive_groups(&indices, &[4, 5, 4], &[6, 4, 3], &mut faces);
+
+        assert_eq!(faces.len(), 3);
+        assert_eq!(faces[2].indices, [8, 9, 10]);
+        assert_eq!(groups.len(), 3);
+        assert_eq!((groups[1].first_face, groups[1].face_count), (2, 0));
+        assert_eq!((groups[2].first_face, groups[2].face_count), (2, 1));
+    }
 }
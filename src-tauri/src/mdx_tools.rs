@@ -0,0 +1,1631 @@
+// MDX 模型工具：基于解析结果的分析与修改
+// 修改类操作走 "解析 -> 变换 -> 写出" 流程，写出由 mdx_writer 负责
+
+use crate::mdx_animation;
+use crate::mdx_parser::{
+    Camera, MdxModel, MdxParser, Sequence, Vertex, COLLISION_BOX, COLLISION_CYLINDER, COLLISION_PLANE,
+    COLLISION_SPHERE, NO_PARENT,
+};
+use crate::mdx_writer::{self, MdxChunk};
+use image::{Rgba, RgbaImage};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+
+// 三角面面积低于该值视为退化
+const DEGENERATE_AREA_EPSILON: f32 = 1e-6;
+// 静态模型的伪序列长度（毫秒）
+const STATIC_SEQUENCE_LENGTH: u32 = 1000;
+// 预览渲染器尚未支持的 chunk 及其说明
+const UNRENDERED_CHUNKS: &[(&[u8; 4], &str)] = &[
+    (b"PREM", "Particle emitters"),
+    (b"PRE2", "Particle emitters (v2)"),
+    (b"RIBB", "Ribbon emitters"),
+    (b"TXAN", "Texture animations"),
+    (b"LITE", "Lights"),
+    (b"CAMS", "Cameras"),
+];
+// UV 线框的颜色
+const UV_LINE_COLOR: Rgba<u8> = Rgba([255, 255, 255, 255]);
+// PREM 记录中 node 之后、path 之前的字段: emission_rate, gravity, longitude, latitude
+const PREM_PATH_OFFSET: usize = 16;
+
+// 游戏按单词匹配挂点名称（如 "hand,left" 匹配 "Hand Left Ref"），以下为可识别的主名称
+const ATTACHMENT_POINTS: &[&str] = &[
+    "origin", "overhead", "head", "chest", "hand", "foot", "weapon", "sprite", "mount", "turret",
+    "rallypoint",
+];
+// 可识别的修饰词
+const ATTACHMENT_MODIFIERS: &[&str] = &[
+    "left", "right", "first", "second", "third", "fourth", "fifth", "sixth", "rear", "large",
+    "medium", "small", "alternate", "ref",
+];
+// 多数技能特效依赖的挂点，缺失时给出提示
+const EXPECTED_ATTACHMENT_POINTS: &[&str] = &["origin", "overhead", "head", "chest"];
+
+/// War3 路径比较时忽略大小写，且 `/` 与 `\` 等价
+fn normalize_path(path: &str) -> String {
+    path.to_ascii_lowercase().replace('/', "\\")
+}
+
+/// 按替换表计算新路径
+///
+/// 精确模式下整条路径匹配；前缀模式下替换最长的匹配前缀（如 `Textures\` -> `war3mapImported\`）。
+fn remap_path(path: &str, replacements: &HashMap<String, String>, prefix_mode: bool) -> Option<String> {
+    if path.is_empty() {
+        return None;
+    }
+    let normalized = normalize_path(path);
+
+    if prefix_mode {
+        replacements
+            .iter()
+            .filter(|(from, _)| !from.is_empty() && normalized.starts_with(&normalize_path(from)))
+            .max_by_key(|(from, _)| from.len())
+            .map(|(from, to)| format!("{}{}", to, &path[from.len()..]))
+    } else {
+        replacements
+            .iter()
+            .find(|(from, _)| normalize_path(from) == normalized)
+            .map(|(_, to)| to.clone())
+    }
+}
+
+/// 重写模型中的所有纹理路径（TEXS 纹理、PREM 发射器模型、ATCH 附着点模型）
+pub fn remap_textures(
+    mdx_data: &[u8],
+    replacements: &HashMap<String, String>,
+    prefix_mode: bool,
+) -> Result<Vec<u8>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let mut model = parser.parse()?;
+
+    for texture in &mut model.textures {
+        if let Some(new_path) = remap_path(&texture.path, replacements, prefix_mode) {
+            texture.path = new_path;
+        }
+    }
+
+    let mut chunks: Vec<MdxChunk> = mdx_writer::read_chunks(mdx_data)?;
+    for chunk in &mut chunks {
+        match &chunk.tag {
+            b"TEXS" => chunk.data = mdx_writer::write_textures(&model.textures)?,
+            b"ATCH" => {
+                mdx_writer::patch_node_record_paths(&mut chunk.data, 0, |path| {
+                    remap_path(path, replacements, prefix_mode)
+                })?;
+            }
+            b"PREM" => {
+                mdx_writer::patch_node_record_paths(&mut chunk.data, PREM_PATH_OFFSET, |path| {
+                    remap_path(path, replacements, prefix_mode)
+                })?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(mdx_writer::write_chunks(&chunks))
+}
+
+/// 序列是否缺少 extent（包围盒与半径全为 0）
+fn has_empty_extent(sequence: &Sequence) -> bool {
+    let (min, max) = (&sequence.minimum_extent, &sequence.maximum_extent);
+    sequence.bounds_radius == 0.0
+        && [min.x, min.y, min.z, max.x, max.y, max.z].iter().all(|&v| v == 0.0)
+}
+
+/// 为 extent 为空的序列计算播放过程中的真实包围盒并写回 SEQS
+///
+/// 缺少 extent 的序列在游戏中会被错误裁剪（某些视角下模型消失）。
+pub fn repair_sequence_extents(mdx_data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let mut model = parser.parse()?;
+
+    let mut repaired = Vec::new();
+    for (index, sequence) in model.sequences.iter().enumerate() {
+        if !has_empty_extent(sequence) {
+            continue;
+        }
+        if let Some(bounds) = mdx_animation::compute_sequence_bounds_for(&model, sequence.interval) {
+            repaired.push((index, bounds));
+        }
+    }
+
+    for (index, bounds) in repaired {
+        let sequence = &mut model.sequences[index];
+        sequence.minimum_extent = bounds.min;
+        sequence.maximum_extent = bounds.max;
+        sequence.bounds_radius = bounds.radius();
+    }
+
+    let mut chunks = mdx_writer::read_chunks(mdx_data)?;
+    for chunk in &mut chunks {
+        if &chunk.tag == b"SEQS" {
+            chunk.data = mdx_writer::write_sequences(&model.sequences)?;
+        }
+    }
+
+    Ok(mdx_writer::write_chunks(&chunks))
+}
+
+/// 编辑距离（用于拼写建议）
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, &cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == cb { prev } else { 1 + prev.min(row[j]).min(row[j + 1]) };
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
+
+/// 为无法识别的单词找出最接近的挂点单词
+fn suggest_attachment_word(word: &str) -> Option<&'static str> {
+    let known = ATTACHMENT_POINTS.iter().chain(ATTACHMENT_MODIFIERS);
+    // 漏写空格的情况，如 "handleft"
+    if let Some(point) = ATTACHMENT_POINTS.iter().find(|p| word.starts_with(*p) && word.len() > p.len()) {
+        return Some(point);
+    }
+    known
+        .map(|k| (edit_distance(word, k), *k))
+        .filter(|&(distance, _)| distance <= 2)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, k)| k)
+}
+
+/// 根据挂点名称列表生成警告
+fn attachment_name_warnings(names: &[&str]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    let mut found_points = Vec::new();
+
+    for name in names {
+        let words: Vec<String> = name.split_whitespace().map(|w| w.to_ascii_lowercase()).collect();
+        let mut has_point = false;
+
+        for word in &words {
+            if ATTACHMENT_POINTS.contains(&word.as_str()) {
+                has_point = true;
+                found_points.push(word.clone());
+            } else if !ATTACHMENT_MODIFIERS.contains(&word.as_str()) {
+                match suggest_attachment_word(word) {
+                    Some(suggestion) => warnings.push(format!(
+                        "Attachment \"{}\": unrecognized word \"{}\" (did you mean \"{}\"?)",
+                        name, word, suggestion
+                    )),
+                    None => warnings.push(format!("Attachment \"{}\": unrecognized word \"{}\"", name, word)),
+                }
+            }
+        }
+
+        if !has_point {
+            warnings.push(format!("Attachment \"{}\" has no recognized attachment point name", name));
+        }
+        if words.iter().any(|w| w == "hand" || w == "foot")
+            && !words.iter().any(|w| w == "left" || w == "right")
+        {
+            warnings.push(format!("Attachment \"{}\" is missing \"Left\" or \"Right\"", name));
+        }
+    }
+
+    for expected in EXPECTED_ATTACHMENT_POINTS {
+        if !found_points.iter().any(|p| p == expected) {
+            warnings.push(format!("Missing standard attachment point \"{}\"", expected));
+        }
+    }
+    warnings
+}
+
+/// 检查模型挂点名称是否符合游戏的命名约定，返回警告列表
+///
+/// 挂点名称拼写错误时技能特效会静默地不显示，例如 "HandLeft Ref" 无法被 "hand,left" 匹配。
+pub fn check_attachment_names(mdx_data: &[u8]) -> Result<Vec<String>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    let names: Vec<&str> = model.attachments.iter().map(|a| a.node.name.as_str()).collect();
+    Ok(attachment_name_warnings(&names))
+}
+
+/// 查找退化三角面（顶点索引重复或面积接近 0），返回 (geoset 索引, 面索引)
+///
+/// 索引越界的面不在此处理。
+pub fn find_degenerate_faces(mdx_data: &[u8]) -> Result<Vec<(usize, usize)>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    Ok(degenerate_faces(&model))
+}
+
+fn degenerate_faces(model: &MdxModel) -> Vec<(usize, usize)> {
+    let mut degenerate = Vec::new();
+    for (geoset_index, geoset) in model.geosets.iter().enumerate() {
+        for (face_index, face) in geoset.faces.iter().enumerate() {
+            let [a, b, c] = face.indices;
+            if a == b || b == c || a == c {
+                degenerate.push((geoset_index, face_index));
+                continue;
+            }
+
+            let vertex = |i: u16| geoset.vertices.get(i as usize);
+            let (Some(a), Some(b), Some(c)) = (vertex(a), vertex(b), vertex(c)) else {
+                continue;
+            };
+            let (ux, uy, uz) = (b.x - a.x, b.y - a.y, b.z - a.z);
+            let (vx, vy, vz) = (c.x - a.x, c.y - a.y, c.z - a.z);
+            let (cx, cy, cz) = (uy * vz - uz * vy, uz * vx - ux * vz, ux * vy - uy * vx);
+            let area = (cx * cx + cy * cy + cz * cz).sqrt() / 2.0;
+            if area < DEGENERATE_AREA_EPSILON {
+                degenerate.push((geoset_index, face_index));
+            }
+        }
+    }
+    degenerate
+}
+
+/// 查找顶点索引超出所在 geoset 顶点数量的面（游戏加载时会崩溃），返回 (geoset 索引, 面索引)
+pub fn check_face_indices(mdx_data: &[u8]) -> Result<Vec<(usize, usize)>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    Ok(out_of_range_faces(&model))
+}
+
+fn out_of_range_faces(model: &MdxModel) -> Vec<(usize, usize)> {
+    let mut invalid = Vec::new();
+    for (geoset_index, geoset) in model.geosets.iter().enumerate() {
+        let vertex_count = geoset.vertices.len();
+        for (face_index, face) in geoset.faces.iter().enumerate() {
+            if face.indices.iter().any(|&i| i as usize >= vertex_count) {
+                invalid.push((geoset_index, face_index));
+            }
+        }
+    }
+    invalid
+}
+
+/// 检查问题的严重程度
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum IssueSeverity {
+    Error,   // 会导致游戏崩溃或显示错误
+    Warning, // 可以使用但建议修复
+}
+
+/// 模型检查发现的问题
+#[derive(Debug, Serialize, Clone)]
+pub struct ValidationIssue {
+    pub severity: IssueSeverity,
+    pub message: String,
+}
+
+impl ValidationIssue {
+    pub fn error(message: String) -> Self {
+        ValidationIssue {
+            severity: IssueSeverity::Error,
+            message,
+        }
+    }
+
+    pub fn warning(message: String) -> Self {
+        ValidationIssue {
+            severity: IssueSeverity::Warning,
+            message,
+        }
+    }
+}
+
+/// 对已解析的模型执行结构检查
+pub fn validate_model(model: &MdxModel) -> Vec<ValidationIssue> {
+    // 解析时跳过的损坏 chunk
+    let mut issues: Vec<ValidationIssue> = model
+        .warnings
+        .iter()
+        .map(|warning| ValidationIssue::warning(warning.clone()))
+        .collect();
+
+    for (geoset_index, face_index) in out_of_range_faces(model) {
+        issues.push(ValidationIssue::error(format!(
+            "Geoset {} face {} references a vertex out of range",
+            geoset_index, face_index
+        )));
+    }
+    for (geoset_index, geoset) in model.geosets.iter().enumerate() {
+        if geoset.faces.is_empty() {
+            issues.push(ValidationIssue::warning(format!(
+                "Geoset {} has no faces",
+                geoset_index
+            )));
+        }
+        if geoset.material_id as usize >= model.materials.len() {
+            issues.push(ValidationIssue::error(format!(
+                "Geoset {} references material {} but the model has {} materials",
+                geoset_index,
+                geoset.material_id,
+                model.materials.len()
+            )));
+        }
+    }
+    let degenerate = degenerate_faces(model);
+    if !degenerate.is_empty() {
+        issues.push(ValidationIssue::warning(format!(
+            "{} degenerate faces (duplicate indices or zero area)",
+            degenerate.len()
+        )));
+    }
+
+    for (material_index, material) in model.materials.iter().enumerate() {
+        for (layer_index, layer) in material.layers.iter().enumerate() {
+            if layer.texture_id as usize >= model.textures.len() {
+                issues.push(ValidationIssue::error(format!(
+                    "Material {} layer {} references texture {} but the model has {} textures",
+                    material_index,
+                    layer_index,
+                    layer.texture_id,
+                    model.textures.len()
+                )));
+            }
+        }
+    }
+
+    let nodes = model.all_nodes();
+    for node in &nodes {
+        if node.parent_id != NO_PARENT && !nodes.iter().any(|n| n.object_id == node.parent_id) {
+            issues.push(ValidationIssue::error(format!(
+                "Node \"{}\" references missing parent {}",
+                node.name, node.parent_id
+            )));
+        }
+    }
+
+    for sequence in &model.sequences {
+        if has_empty_extent(sequence) {
+            issues.push(ValidationIssue::warning(format!(
+                "Sequence \"{}\" has no extents",
+                sequence.name
+            )));
+        }
+    }
+
+    issues
+}
+
+/// 列出模型依赖的游戏自带资源路径（不在 `war3mapImported\` 下的纹理与发射器模型）
+///
+/// 结果去重（忽略大小写）并排序。
+pub fn list_stock_dependencies(mdx_data: &[u8]) -> Result<Vec<String>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    Ok(dependency_paths(&model, false))
+}
+
+/// 列出模型依赖的导入资源路径（`war3mapImported\` 下的纹理与发射器模型），去重并排序
+pub fn imported_dependencies(model: &MdxModel) -> Vec<String> {
+    dependency_paths(model, true)
+}
+
+fn dependency_paths(model: &MdxModel, imported: bool) -> Vec<String> {
+    let texture_paths = model.textures.iter().map(|t| &t.path);
+    let emitter_paths = model.particle_emitters.iter().map(|e| &e.path);
+
+    let mut seen = std::collections::HashSet::new();
+    let mut dependencies: Vec<String> = texture_paths
+        .chain(emitter_paths)
+        .filter(|path| !path.is_empty())
+        .filter(|path| normalize_path(path).starts_with("war3mapimported\\") == imported)
+        .filter(|path| seen.insert(normalize_path(path)))
+        .cloned()
+        .collect();
+
+    dependencies.sort_by_key(|path| normalize_path(path));
+    dependencies
+}
+
+/// 统计被两个及以上模型共用的纹理，返回 纹理路径 -> 使用它的模型名
+///
+/// 路径比较忽略大小写与分隔符差异，键使用首次出现时的写法。
+pub fn shared_textures(models: &[(String, MdxModel)]) -> HashMap<String, Vec<String>> {
+    let mut users: HashMap<String, (String, Vec<String>)> = HashMap::new();
+    for (model_name, model) in models {
+        for texture in model.textures.iter().filter(|t| !t.path.is_empty()) {
+            let (_, names) = users
+                .entry(normalize_path(&texture.path))
+                .or_insert_with(|| (texture.path.clone(), Vec::new()));
+            if !names.contains(model_name) {
+                names.push(model_name.clone());
+            }
+        }
+    }
+
+    users
+        .into_values()
+        .filter(|(_, names)| names.len() > 1)
+        .collect()
+}
+
+/// 去掉模型的动画（SEQS 以及骨骼/辅助体的变换轨道），
+/// 保留绑定姿势并写入单个 "Stand" 伪序列，用于把单位模型转为装饰物
+///
+/// GLBS 原样保留：材质、GEOA、TXAN、附着点、光源与粒子中的轨道仍可能引用全局序列，
+/// 删除后这些引用会失效。全局序列与普通序列无关，不影响静态的 "Stand"。
+pub fn make_static(mdx_data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+
+    let stand = Sequence {
+        name: "Stand".to_string(),
+        interval: [0, STATIC_SEQUENCE_LENGTH],
+        move_speed: 0.0,
+        non_looping: false,
+        rarity: 0.0,
+        sync_point: 0,
+        bounds_radius: model.overall_bounds.radius(),
+        minimum_extent: model.overall_bounds.min,
+        maximum_extent: model.overall_bounds.max,
+    };
+    let sequences = MdxChunk {
+        tag: *b"SEQS",
+        data: mdx_writer::write_sequences(&[stand])?,
+    };
+
+    let mut chunks = Vec::new();
+    let mut sequences = Some(sequences);
+    for chunk in mdx_writer::read_chunks(mdx_data)? {
+        match &chunk.tag {
+            b"SEQS" => continue,
+            b"BONE" => chunks.push(MdxChunk {
+                tag: chunk.tag,
+                data: mdx_writer::strip_node_tracks(&chunk.data, 8)?,
+            }),
+            b"HELP" => chunks.push(MdxChunk {
+                tag: chunk.tag,
+                data: mdx_writer::strip_node_tracks(&chunk.data, 0)?,
+            }),
+            b"VERS" | b"MODL" => chunks.push(chunk),
+            _ => {
+                // SEQS 紧跟在 VERS/MODL 之后
+                if let Some(sequences) = sequences.take() {
+                    chunks.push(sequences);
+                }
+                chunks.push(chunk);
+            }
+        }
+    }
+    chunks.extend(sequences);
+
+    Ok(mdx_writer::write_chunks(&chunks))
+}
+
+/// 把模型平移到几何中心（顶点包围盒中心）位于原点，返回新的 MDX 数据与实际平移量
+///
+/// 顶点、枢轴点、碰撞体以及模型/序列/geoset 的 extent 一起平移；
+/// 节点平移轨道相对于枢轴点，无需修改。
+pub fn recenter_model(mdx_data: &[u8]) -> Result<(Vec<u8>, [f32; 3]), String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    if model.geosets.iter().all(|g| g.vertices.is_empty()) {
+        return Ok((mdx_data.to_vec(), [0.0; 3]));
+    }
+
+    let (min, max) = (model.overall_bounds.min, model.overall_bounds.max);
+    let offset = [
+        -(min.x + max.x) / 2.0,
+        -(min.y + max.y) / 2.0,
+        -(min.z + max.z) / 2.0,
+    ];
+
+    let mut chunks = mdx_writer::read_chunks(mdx_data)?;
+    for chunk in &mut chunks {
+        match &chunk.tag {
+            b"MODL" => mdx_writer::translate_model_extent(&mut chunk.data, offset)?,
+            b"SEQS" => mdx_writer::translate_sequence_extents(&mut chunk.data, offset)?,
+            b"GEOS" => mdx_writer::translate_geosets(&mut chunk.data, model.version, offset)?,
+            b"PIVT" => mdx_writer::translate_pivots(&mut chunk.data, offset)?,
+            b"CLID" => mdx_writer::translate_collision_shapes(&mut chunk.data, offset)?,
+            _ => {}
+        }
+    }
+
+    Ok((mdx_writer::write_chunks(&chunks), offset))
+}
+
+/// 计算骨骼（按 object_id）对 geoset 每个顶点的影响权重 (0..1)
+///
+/// 有 SKIN 数据（HD 模型）时使用实际权重；经典模型按 GNDX 矩阵组判断，
+/// 所属矩阵组包含该骨骼即为 1，否则为 0。
+pub fn get_bone_weight_heatmap(
+    mdx_data: &[u8],
+    geoset_index: usize,
+    bone_id: u32,
+) -> Result<Vec<f32>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    let geoset = model
+        .geosets
+        .get(geoset_index)
+        .ok_or_else(|| format!("Geoset index {} out of range", geoset_index))?;
+
+    let matrix_is_bone = |index: u8| geoset.matrix_indices.get(index as usize) == Some(&bone_id);
+    if !geoset.skin.is_empty() {
+        return Ok(geoset
+            .skin
+            .iter()
+            .map(|entry| {
+                (0..4)
+                    .filter(|&i| matrix_is_bone(entry[i]))
+                    .map(|i| entry[4 + i] as f32 / 255.0)
+                    .sum::<f32>()
+                    .min(1.0)
+            })
+            .collect());
+    }
+
+    // 每个矩阵组在 MATS 中的起始位置
+    let mut group_ranges = Vec::with_capacity(geoset.matrix_groups.len());
+    let mut start = 0usize;
+    for &size in &geoset.matrix_groups {
+        group_ranges.push(start..start + size as usize);
+        start += size as usize;
+    }
+    Ok(geoset
+        .vertex_groups
+        .iter()
+        .map(|&group| {
+            let members = group_ranges
+                .get(group as usize)
+                .and_then(|range| geoset.matrix_indices.get(range.clone()))
+                .unwrap_or(&[]);
+            if members.contains(&bone_id) {
+                1.0
+            } else {
+                0.0
+            }
+        })
+        .collect())
+}
+
+/// 把 geoset 的 UV 展开（第一组 UV）画成透明背景上的线框，用于叠加在纹理上查看
+///
+/// UV (0, 0) 对应图像左上角；超出 [0, 1] 的部分被裁剪。
+pub fn render_uv_layout(mdx_data: &[u8], geoset_index: usize, size: u32) -> Result<RgbaImage, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    let geoset = model
+        .geosets
+        .get(geoset_index)
+        .ok_or_else(|| format!("Geoset index {} out of range", geoset_index))?;
+
+    let mut img = RgbaImage::new(size, size);
+    let to_pixel = |index: u16| {
+        geoset
+            .first_uvs()
+            .get(index as usize)
+            // 限制坐标范围，避免异常 UV 导致画线循环过长
+            .map(|uv| [uv.u, uv.v].map(|t| (t.clamp(-1.0, 2.0) * size as f32) as i64))
+            .map(|[x, y]| (x, y))
+    };
+
+    // 相邻三角面共用的边只画一次
+    let mut edges = HashSet::new();
+    for face in &geoset.faces {
+        let [a, b, c] = face.indices;
+        for (from, to) in [(a, b), (b, c), (c, a)] {
+            if !edges.insert((from.min(to), from.max(to))) {
+                continue;
+            }
+            if let (Some(p0), Some(p1)) = (to_pixel(from), to_pixel(to)) {
+                draw_line(&mut img, p0, p1);
+            }
+        }
+    }
+    Ok(img)
+}
+
+/// Bresenham 直线，超出图像的像素忽略
+fn draw_line(img: &mut RgbaImage, (mut x0, mut y0): (i64, i64), (x1, y1): (i64, i64)) {
+    let (width, height) = (img.width() as i64, img.height() as i64);
+    let (dx, dy) = ((x1 - x0).abs(), -(y1 - y0).abs());
+    let (sx, sy) = (if x0 < x1 { 1 } else { -1 }, if y0 < y1 { 1 } else { -1 });
+    let mut err = dx + dy;
+    loop {
+        if (0..width).contains(&x0) && (0..height).contains(&y0) {
+            img.put_pixel(x0 as u32, y0 as u32, UV_LINE_COLOR);
+        }
+        if x0 == x1 && y0 == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x0 += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y0 += sy;
+        }
+    }
+}
+
+/// 列出模型中存在、但预览渲染器会忽略的特性（按非空 chunk 判断）
+pub fn get_preview_limitations(mdx_data: &[u8]) -> Result<Vec<String>, String> {
+    // 先完整解析一次，保证模型本身可用
+    MdxParser::new(mdx_data.to_vec())?.parse()?;
+    let chunks = mdx_writer::read_chunks(mdx_data)?;
+
+    Ok(UNRENDERED_CHUNKS
+        .iter()
+        .filter(|(tag, _)| chunks.iter().any(|c| &c.tag == *tag && !c.data.is_empty()))
+        .map(|(_, feature)| feature.to_string())
+        .collect())
+}
+
+/// 估算绘制调用次数：geoset 实际引用的不同材质数量（而非声明的材质总数）
+pub fn get_draw_call_estimate(mdx_data: &[u8]) -> Result<usize, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    let materials: std::collections::HashSet<u32> =
+        model.geosets.iter().map(|g| g.material_id).collect();
+    Ok(materials.len())
+}
+
+/// 可直接绘制的碰撞体几何（模型空间）
+#[derive(Debug, Serialize, Clone)]
+#[serde(tag = "shape", rename_all = "lowercase")]
+pub enum CollisionGeom {
+    Box {
+        name: String,
+        corners: Vec<Vertex>,
+    },
+    Plane {
+        name: String,
+        corners: Vec<Vertex>,
+    },
+    Sphere {
+        name: String,
+        center: Vertex,
+        radius: f32,
+    },
+    Cylinder {
+        name: String,
+        start: Vertex,
+        end: Vertex,
+        radius: f32,
+    },
+}
+
+/// 将模型的碰撞体转换为线框预览用的几何数据
+///
+/// 碰撞体顶点在绑定姿势下已经是模型空间坐标（相对节点 pivot 的位置已包含在内）。
+pub fn get_collision_geometry(mdx_data: &[u8]) -> Result<Vec<CollisionGeom>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+
+    let geometry = model
+        .collision_shapes
+        .iter()
+        .filter_map(|shape| {
+            let name = shape.node.name.clone();
+            let v = &shape.vertices;
+            match shape.shape_type {
+                COLLISION_BOX => {
+                    let (min, max) = (v[0], v[1]);
+                    let corners = (0..8)
+                        .map(|i| Vertex {
+                            x: if i & 1 == 0 { min.x } else { max.x },
+                            y: if i & 2 == 0 { min.y } else { max.y },
+                            z: if i & 4 == 0 { min.z } else { max.z },
+                        })
+                        .collect();
+                    Some(CollisionGeom::Box { name, corners })
+                }
+                COLLISION_PLANE => {
+                    let (min, max) = (v[0], v[1]);
+                    let corners = vec![
+                        min,
+                        Vertex {
+                            x: max.x,
+                            y: min.y,
+                            z: min.z,
+                        },
+                        max,
+                        Vertex {
+                            x: min.x,
+                            y: max.y,
+                            z: max.z,
+                        },
+                    ];
+                    Some(CollisionGeom::Plane { name, corners })
+                }
+                COLLISION_SPHERE => Some(CollisionGeom::Sphere {
+                    name,
+                    center: v[0],
+                    radius: shape.radius,
+                }),
+                COLLISION_CYLINDER => Some(CollisionGeom::Cylinder {
+                    name,
+                    start: v[0],
+                    end: v[1],
+                    radius: shape.radius,
+                }),
+                _ => None,
+            }
+        })
+        .collect();
+    Ok(geometry)
+}
+
+/// 选取头像（portrait）镜头：优先名称中含 "Portrait" 的镜头（不区分大小写），
+/// 否则取第一个镜头（头像模型通常只有一个镜头）
+pub fn find_portrait_camera(model: &MdxModel) -> Option<&Camera> {
+    model
+        .cameras
+        .iter()
+        .find(|camera| camera.name.to_ascii_lowercase().contains("portrait"))
+        .or_else(|| model.cameras.first())
+}
+
+/// 单个全局序列的使用情况
+#[derive(Debug, Serialize, Clone)]
+pub struct GlobalSeqUsage {
+    pub id: usize,
+    pub duration: u32,
+    pub users: Vec<String>, // 引用该全局序列的轨道描述，如 `Node "Bone_Root" rotation`
+}
+
+/// 计算每个 geoset 在每个序列中是否可见，返回 geoset × 序列 的矩阵
+pub fn get_geoset_visibility_matrix(mdx_data: &[u8]) -> Result<Vec<Vec<bool>>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+
+    Ok((0..model.geosets.len() as u32)
+        .map(|geoset_id| {
+            model
+                .sequences
+                .iter()
+                .map(|sequence| mdx_animation::is_geoset_visible(&model, geoset_id, sequence.interval))
+                .collect()
+        })
+        .collect())
+}
+
+/// 统计每个全局序列被哪些节点、材质层、纹理动画轨道引用
+pub fn get_global_sequence_usage(mdx_data: &[u8]) -> Result<Vec<GlobalSeqUsage>, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+
+    let mut usage: Vec<GlobalSeqUsage> = model
+        .global_sequences
+        .iter()
+        .enumerate()
+        .map(|(id, &duration)| GlobalSeqUsage {
+            id,
+            duration,
+            users: Vec::new(),
+        })
+        .collect();
+    let mut add_user = |global_sequence_id: i32, user: String| {
+        if let Some(entry) = usize::try_from(global_sequence_id).ok().and_then(|id| usage.get_mut(id)) {
+            entry.users.push(user);
+        }
+    };
+
+    for node in model.all_nodes() {
+        if let Some(track) = &node.translation {
+            add_user(track.global_sequence_id, format!("Node \"{}\" translation", node.name));
+        }
+        if let Some(track) = &node.rotation {
+            add_user(track.global_sequence_id, format!("Node \"{}\" rotation", node.name));
+        }
+        if let Some(track) = &node.scaling {
+            add_user(track.global_sequence_id, format!("Node \"{}\" scaling", node.name));
+        }
+    }
+
+    for (material_index, material) in model.materials.iter().enumerate() {
+        for (layer_index, layer) in material.layers.iter().enumerate() {
+            if let Some(track) = &layer.alpha_track {
+                add_user(
+                    track.global_sequence_id,
+                    format!("Material {} layer {} alpha", material_index, layer_index),
+                );
+            }
+            if let Some(track) = &layer.texture_track {
+                add_user(
+                    track.global_sequence_id,
+                    format!("Material {} layer {} texture", material_index, layer_index),
+                );
+            }
+        }
+    }
+
+    for (index, animation) in model.texture_animations.iter().enumerate() {
+        if let Some(track) = &animation.translation {
+            add_user(track.global_sequence_id, format!("Texture animation {} translation", index));
+        }
+        if let Some(track) = &animation.rotation {
+            add_user(track.global_sequence_id, format!("Texture animation {} rotation", index));
+        }
+        if let Some(track) = &animation.scaling {
+            add_user(track.global_sequence_id, format!("Texture animation {} scaling", index));
+        }
+    }
+
+    Ok(usage)
+}
+
+/// 材质层混合模式名称（与 MDL 中的写法一致）
+pub fn filter_mode_name(filter_mode: u32) -> &'static str {
+    match filter_mode {
+        0 => "None",
+        1 => "Transparent",
+        2 => "Blend",
+        3 => "Additive",
+        4 => "AddAlpha",
+        5 => "Modulate",
+        6 => "Modulate2x",
+        _ => "Unknown",
+    }
+}
+
+/// 模型概要，用于在加载完整几何前展示
+#[derive(Debug, Serialize, Clone)]
+pub struct MdxSummary {
+    pub name: String,
+    pub geoset_count: usize,
+    pub vertex_count: usize,
+    pub triangle_count: usize,
+    pub bone_count: usize,
+    pub texture_count: usize,
+    pub sequence_count: usize,
+}
+
+/// 统计模型的顶点、三角面、骨骼等数量，不返回几何数据
+pub fn get_mdx_summary(mdx_data: &[u8]) -> Result<MdxSummary, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    Ok(MdxSummary {
+        name: model.name.clone(),
+        geoset_count: model.geosets.len(),
+        vertex_count: model.geosets.iter().map(|g| g.vertices.len()).sum(),
+        triangle_count: model.geosets.iter().map(|g| g.faces.len()).sum(),
+        bone_count: model.bones.len(),
+        texture_count: model.textures.len(),
+        sequence_count: model.sequences.len(),
+    })
+}
+
+/// 时间轴上的一个动画序列
+#[derive(Debug, Serialize, Clone)]
+pub struct TimelineSequence {
+    pub name: String,
+    pub interval: [u32; 2], // [起始帧, 结束帧]
+    pub duration_ms: u32,   // War3 每秒 1000 帧，帧数即毫秒数
+    pub non_looping: bool,
+}
+
+/// 动画播放器使用的时间轴：各序列区间与独立循环的全局序列时长
+#[derive(Debug, Serialize, Clone)]
+pub struct Timeline {
+    pub sequences: Vec<TimelineSequence>,
+    pub global_sequences: Vec<u32>, // 毫秒
+}
+
+/// 生成模型的动画时间轴
+pub fn get_mdx_timeline(mdx_data: &[u8]) -> Result<Timeline, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    Ok(timeline(&model))
+}
+
+fn timeline(model: &MdxModel) -> Timeline {
+    Timeline {
+        sequences: model
+            .sequences
+            .iter()
+            .map(|sequence| TimelineSequence {
+                name: sequence.name.clone(),
+                interval: sequence.interval,
+                duration_ms: sequence.interval[1].saturating_sub(sequence.interval[0]),
+                non_looping: sequence.non_looping,
+            })
+            .collect(),
+        global_sequences: model.global_sequences.clone(),
+    }
+}
+
+/// 生成适合 `git diff` 的模型文本摘要
+///
+/// 输出按行组织且顺序固定；只包含计数、名称、路径、帧区间等离散信息，
+/// 浮点数按整数（alpha 按两位小数）输出，避免重新导出造成的微小误差产生噪声。
+pub fn mdx_to_text_summary(mdx_data: &[u8]) -> Result<String, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    let mut out = String::new();
+
+    writeln!(out, "version {}", model.version).unwrap();
+    writeln!(out, "name \"{}\"", model.name).unwrap();
+    writeln!(
+        out,
+        "bounds [{:.0}, {:.0}, {:.0}] [{:.0}, {:.0}, {:.0}]",
+        model.overall_bounds.min.x,
+        model.overall_bounds.min.y,
+        model.overall_bounds.min.z,
+        model.overall_bounds.max.x,
+        model.overall_bounds.max.y,
+        model.overall_bounds.max.z
+    )
+    .unwrap();
+
+    writeln!(out, "sequences {}", model.sequences.len()).unwrap();
+    for sequence in &model.sequences {
+        writeln!(
+            out,
+            "  \"{}\" {}-{}{}",
+            sequence.name,
+            sequence.interval[0],
+            sequence.interval[1],
+            if sequence.non_looping {
+                " nonlooping"
+            } else {
+                ""
+            }
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "global_sequences {}", model.global_sequences.len()).unwrap();
+    for duration in &model.global_sequences {
+        writeln!(out, "  {}", duration).unwrap();
+    }
+
+    writeln!(out, "textures {}", model.textures.len()).unwrap();
+    for (index, texture) in model.textures.iter().enumerate() {
+        writeln!(
+            out,
+            "  {} \"{}\" replaceable={} flags={}",
+            index, texture.path, texture.replaceable_id, texture.flags
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "materials {}", model.materials.len()).unwrap();
+    for (index, material) in model.materials.iter().enumerate() {
+        writeln!(
+            out,
+            "  {} priority={} flags={} layers={}",
+            index,
+            material.priority_plane,
+            material.flags,
+            material.layers.len()
+        )
+        .unwrap();
+        for layer in &material.layers {
+            writeln!(
+                out,
+                "    {} texture={} shading={} alpha={:.2}",
+                filter_mode_name(layer.filter_mode),
+                layer.texture_id,
+                layer.shading_flags,
+                layer.alpha
+            )
+            .unwrap();
+        }
+    }
+
+    writeln!(out, "geosets {}", model.geosets.len()).unwrap();
+    for (index, geoset) in model.geosets.iter().enumerate() {
+        writeln!(
+            out,
+            "  {} vertices={} faces={} groups={}",
+            index,
+            geoset.vertices.len(),
+            geoset.faces.len(),
+            geoset.matrix_groups.len()
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "bones {}", model.bones.len()).unwrap();
+    for bone in &model.bones {
+        writeln!(
+            out,
+            "  {} \"{}\" parent={}",
+            bone.node.object_id, bone.node.name, bone.node.parent_id as i32
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "helpers {}", model.helpers.len()).unwrap();
+    for helper in &model.helpers {
+        writeln!(
+            out,
+            "  {} \"{}\" parent={}",
+            helper.object_id, helper.name, helper.parent_id as i32
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "attachments {}", model.attachments.len()).unwrap();
+    for attachment in &model.attachments {
+        writeln!(
+            out,
+            "  \"{}\" path=\"{}\"",
+            attachment.node.name, attachment.path
+        )
+        .unwrap();
+    }
+
+    writeln!(out, "particle_emitters {}", model.particle_emitters.len()).unwrap();
+    for emitter in &model.particle_emitters {
+        writeln!(out, "  \"{}\" path=\"{}\"", emitter.node.name, emitter.path).unwrap();
+    }
+
+    Ok(out)
+}
+
+/// 导出为 Wavefront OBJ 文本：每个 geoset 一个 `o`/`g` 分组并引用 `material_<id>`，
+/// 索引按 geoset 依次偏移；只导出第一层 UV（V 轴翻转为 OBJ 的左下角原点）
+pub fn export_obj(mdx_data: &[u8]) -> Result<String, String> {
+    let mut parser = MdxParser::new(mdx_data.to_vec())?;
+    let model = parser.parse()?;
+    let mut out = String::new();
+
+    if !model.name.is_empty() {
+        writeln!(out, "# {}", model.name).unwrap();
+    }
+    let (mut vertex_base, mut uv_base, mut normal_base) = (1usize, 1usize, 1usize);
+    for (index, geoset) in model.geosets.iter().enumerate() {
+        writeln!(out, "o geoset_{}", index).unwrap();
+        writeln!(out, "g geoset_{}", index).unwrap();
+        writeln!(out, "usemtl material_{}", geoset.material_id).unwrap();
+
+        for v in &geoset.vertices {
+            writeln!(out, "v {} {} {}", v.x, v.y, v.z).unwrap();
+        }
+        for uv in geoset.first_uvs() {
+            writeln!(out, "vt {} {}", uv.u, 1.0 - uv.v).unwrap();
+        }
+        for n in &geoset.normals {
+            writeln!(out, "vn {} {} {}", n.x, n.y, n.z).unwrap();
+        }
+
+        // UV/法线数量与顶点数不一致时不引用，避免越界
+        let has_uvs = geoset.first_uvs().len() == geoset.vertices.len();
+        let has_normals = geoset.normals.len() == geoset.vertices.len();
+        for face in &geoset.faces {
+            out.push('f');
+            for &i in &face.indices {
+                let i = i as usize;
+                match (has_uvs, has_normals) {
+                    (true, true) => {
+                        write!(out, " {}/{}/{}", vertex_base + i, uv_base + i, normal_base + i)
+                    }
+                    (true, false) => write!(out, " {}/{}", vertex_base + i, uv_base + i),
+                    (false, true) => write!(out, " {}//{}", vertex_base + i, normal_base + i),
+                    (false, false) => write!(out, " {}", vertex_base + i),
+                }
+                .unwrap();
+            }
+            out.push('\n');
+        }
+
+        vertex_base += geoset.vertices.len();
+        uv_base += geoset.first_uvs().len();
+        normal_base += geoset.normals.len();
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdx_parser::{Texture, NAME_LEN, NODE_ATTACHMENT, NODE_PARTICLE_EMITTER, PATH_LEN};
+
+    fn model_with_textures(paths: &[&str]) -> Vec<u8> {
+        let textures: Vec<Texture> = paths
+            .iter()
+            .map(|p| Texture::new(0, p.to_string(), 0))
+            .collect();
+        mdx_writer::write_chunks(&[
+            MdxChunk {
+                tag: *b"VERS",
+                data: 800u32.to_le_bytes().to_vec(),
+            },
+            MdxChunk {
+                tag: *b"TEXS",
+                data: mdx_writer::write_textures(&textures).unwrap(),
+            },
+        ])
+    }
+
+    fn texture_paths(data: Vec<u8>) -> Vec<String> {
+        let model = MdxParser::new(data).unwrap().parse().unwrap();
+        model.textures.into_iter().map(|t| t.path).collect()
+    }
+
+    #[test]
+    fn test_remap_textures_exact_and_prefix() {
+        let data = model_with_textures(&["Textures\\Gutz.blp", "textures/Footman.blp"]);
+
+        let mut exact = HashMap::new();
+        exact.insert("TEXTURES\\GUTZ.BLP".to_string(), "war3mapImported\\Gutz.blp".to_string());
+        let remapped = remap_textures(&data, &exact, false).unwrap();
+        assert_eq!(
+            texture_paths(remapped),
+            vec!["war3mapImported\\Gutz.blp", "textures/Footman.blp"]
+        );
+
+        let mut prefix = HashMap::new();
+        prefix.insert("Textures\\".to_string(), "war3mapImported\\".to_string());
+        let remapped = remap_textures(&data, &prefix, true).unwrap();
+        assert_eq!(
+            texture_paths(remapped),
+            vec!["war3mapImported\\Gutz.blp", "war3mapImported\\Footman.blp"]
+        );
+    }
+
+    /// 节点类记录: inclusive_size + 节点头 + fields + path + trailing
+    fn node_record(flags: u32, fields: &[u8], path: &str, trailing: &[u8]) -> Vec<u8> {
+        let mut body = 96u32.to_le_bytes().to_vec();
+        body.extend(std::iter::repeat_n(0u8, NAME_LEN));
+        for field in [0, NO_PARENT, flags] {
+            body.extend_from_slice(&field.to_le_bytes());
+        }
+        body.extend_from_slice(fields);
+        mdx_writer::write_fixed_string(&mut body, path, PATH_LEN).unwrap();
+        body.extend_from_slice(trailing);
+        let mut record = (body.len() as u32 + 4).to_le_bytes().to_vec();
+        record.extend_from_slice(&body);
+        record
+    }
+
+    #[test]
+    fn test_remap_attachment_and_emitter_paths() {
+        let data = mdx_writer::write_chunks(&[
+            MdxChunk {
+                tag: *b"VERS",
+                data: 800u32.to_le_bytes().to_vec(),
+            },
+            MdxChunk {
+                tag: *b"ATCH",
+                data: node_record(NODE_ATTACHMENT, &[], "Abilities\\Weapons\\Sword.mdl", &3u32.to_le_bytes()),
+            },
+            MdxChunk {
+                tag: *b"PREM",
+                data: node_record(NODE_PARTICLE_EMITTER, &[0; PREM_PATH_OFFSET], "abilities/Spells/Fire.mdl", &[0; 8]),
+            },
+        ]);
+
+        let mut prefix = HashMap::new();
+        prefix.insert("Abilities\\".to_string(), "war3mapImported\\".to_string());
+        let model = MdxParser::new(remap_textures(&data, &prefix, true).unwrap())
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(model.warnings.is_empty(), "{:?}", model.warnings);
+        assert_eq!(model.attachments[0].path, "war3mapImported\\Weapons\\Sword.mdl");
+        assert_eq!(model.attachments[0].attachment_id, 3);
+        assert_eq!(model.particle_emitters[0].path, "war3mapImported\\Spells/Fire.mdl");
+
+        // 新路径恰好占满 260 字节时不带结尾 NUL 也能写入
+        let long_path = "x".repeat(PATH_LEN);
+        let mut exact = HashMap::new();
+        exact.insert("abilities\\spells\\fire.mdl".to_string(), long_path.clone());
+        let model = MdxParser::new(remap_textures(&data, &exact, false).unwrap())
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(model.particle_emitters[0].path, long_path);
+        assert_eq!(model.attachments[0].path, "Abilities\\Weapons\\Sword.mdl");
+    }
+
+    #[test]
+    fn test_repair_sequence_extents() {
+        use crate::mdx_parser::{Bone, Geoset, Keyframe, Node, NodeFlags, Track};
+
+        let sequence = |name: &str, interval: [u32; 2], max_x: f32| Sequence {
+            name: name.to_string(),
+            interval,
+            move_speed: 0.0,
+            non_looping: false,
+            rarity: 0.0,
+            sync_point: 0,
+            bounds_radius: max_x,
+            minimum_extent: Vertex::default(),
+            maximum_extent: Vertex { x: max_x, y: 0.0, z: 0.0 },
+        };
+        let key = |frame: u32, x: f32| Keyframe {
+            frame,
+            value: [x, 0.0, 0.0],
+            in_tan: None,
+            out_tan: None,
+        };
+        let model = MdxModel {
+            version: 800,
+            // Stand 缺少 extent，Walk 已有 extent 不应被改写
+            sequences: vec![sequence("Stand", [0, 1000], 0.0), sequence("Walk", [2000, 3000], 50.0)],
+            geosets: vec![Geoset {
+                vertices: vec![
+                    Vertex { x: -1.0, y: -2.0, z: -3.0 },
+                    Vertex { x: 4.0, y: 5.0, z: 6.0 },
+                ],
+                vertex_groups: vec![0, 0],
+                matrix_groups: vec![1],
+                matrix_indices: vec![0],
+                ..Default::default()
+            }],
+            // 骨骼在 Stand 中沿 X 平移 10
+            bones: vec![Bone {
+                node: Node {
+                    name: "Root".to_string(),
+                    object_id: 0,
+                    parent_id: NO_PARENT,
+                    flags: 0x100,
+                    decoded_flags: NodeFlags::default(),
+                    translation: Some(Track {
+                        interpolation: 1,
+                        global_sequence_id: -1,
+                        keys: vec![key(0, 0.0), key(1000, 10.0)],
+                    }),
+                    rotation: None,
+                    scaling: None,
+                },
+                geoset_id: 0,
+                geoset_animation_id: u32::MAX,
+            }],
+            pivot_points: vec![Vertex::default()],
+            ..Default::default()
+        };
+        let data = mdx_writer::write_model(&model).unwrap();
+
+        let model = MdxParser::new(repair_sequence_extents(&data).unwrap())
+            .unwrap()
+            .parse()
+            .unwrap();
+        let stand = &model.sequences[0];
+        let (min, max) = (&stand.minimum_extent, &stand.maximum_extent);
+        assert_eq!([min.x, min.y, min.z], [-1.0, -2.0, -3.0]);
+        assert_eq!([max.x, max.y, max.z], [14.0, 5.0, 6.0]);
+        assert!(stand.bounds_radius > 0.0);
+        let walk = &model.sequences[1];
+        assert_eq!(walk.maximum_extent.x, 50.0);
+        assert_eq!(walk.bounds_radius, 50.0);
+    }
+
+    #[test]
+    fn test_make_static_keeps_global_sequences() {
+        use crate::mdx_parser::{Bone, GeosetAnimation, Keyframe, Node, NodeFlags, TextureAnimation, Track};
+
+        let track = |global_sequence_id: i32| Track {
+            interpolation: 1,
+            global_sequence_id,
+            keys: vec![Keyframe {
+                frame: 0,
+                value: [1.0f32; 3],
+                in_tan: None,
+                out_tan: None,
+            }],
+        };
+        let model = MdxModel {
+            version: 800,
+            global_sequences: vec![1000, 500],
+            texture_animations: vec![TextureAnimation {
+                translation: Some(track(1)),
+                ..Default::default()
+            }],
+            geoset_animations: vec![GeosetAnimation {
+                alpha: 1.0,
+                flags: 0,
+                color: [1.0; 3],
+                geoset_id: 0,
+                alpha_track: None,
+                color_track: Some(track(0)),
+            }],
+            bones: vec![Bone {
+                node: Node {
+                    name: "Root".to_string(),
+                    object_id: 0,
+                    parent_id: NO_PARENT,
+                    flags: 0x100,
+                    decoded_flags: NodeFlags::default(),
+                    translation: Some(track(0)),
+                    rotation: None,
+                    scaling: None,
+                },
+                geoset_id: 0,
+                geoset_animation_id: u32::MAX,
+            }],
+            ..Default::default()
+        };
+        let data = mdx_writer::write_model(&model).unwrap();
+
+        let model = MdxParser::new(make_static(&data).unwrap()).unwrap().parse().unwrap();
+        assert!(model.warnings.is_empty(), "{:?}", model.warnings);
+        assert_eq!(model.sequences.len(), 1);
+        assert!(model.bones[0].node.translation.is_none());
+        // 保留下来的轨道引用的全局序列必须仍然存在
+        let global_sequence_ids = [
+            model.texture_animations[0].translation.as_ref().unwrap().global_sequence_id,
+            model.geoset_animations[0].color_track.as_ref().unwrap().global_sequence_id,
+        ];
+        for id in global_sequence_ids {
+            assert!((id as usize) < model.global_sequences.len(), "dangling global sequence {}", id);
+        }
+        assert_eq!(model.global_sequences, vec![1000, 500]);
+    }
+
+    #[test]
+    fn test_validate_model() {
+        use crate::mdx_parser::{Bone, Face, Geoset, Node, NodeFlags};
+
+        let mut model = MdxModel::default();
+        model.geosets.push(Geoset {
+            vertices: vec![Vertex::default(); 3],
+            faces: vec![Face { indices: [0, 1, 3] }],
+            material_id: 1,
+            ..Default::default()
+        });
+        model.bones.push(Bone {
+            node: Node {
+                name: "Root".to_string(),
+                object_id: 0,
+                parent_id: 5,
+                flags: 0,
+                decoded_flags: NodeFlags::default(),
+                translation: None,
+                rotation: None,
+                scaling: None,
+            },
+            geoset_id: 0,
+            geoset_animation_id: u32::MAX,
+        });
+
+        let messages: Vec<String> = validate_model(&model)
+            .into_iter()
+            .map(|issue| issue.message)
+            .collect();
+        assert!(messages.contains(&"Geoset 0 face 0 references a vertex out of range".to_string()));
+        assert!(messages
+            .contains(&"Geoset 0 references material 1 but the model has 0 materials".to_string()));
+        assert!(messages.contains(&"Node \"Root\" references missing parent 5".to_string()));
+    }
+
+    #[test]
+    fn test_mdx_summary() {
+        let data = model_with_textures(&["Textures\\Gutz.blp", "Textures\\Footman.blp"]);
+        let summary = get_mdx_summary(&data).unwrap();
+        assert_eq!(summary.texture_count, 2);
+        assert_eq!(summary.geoset_count, 0);
+        assert_eq!(summary.triangle_count, 0);
+    }
+
+    #[test]
+    fn test_mdx_to_text_summary() {
+        use crate::mdx_parser::{Face, Geoset};
+
+        let sequence = |name: &str, interval: [u32; 2]| Sequence {
+            name: name.to_string(),
+            interval,
+            move_speed: 0.0,
+            non_looping: name == "Death",
+            rarity: 0.0,
+            sync_point: 0,
+            bounds_radius: 0.0,
+            minimum_extent: Vertex::default(),
+            maximum_extent: Vertex::default(),
+        };
+        let model = MdxModel {
+            version: 800,
+            name: "Box".to_string(),
+            sequences: vec![sequence("Stand", [0, 1000]), sequence("Death", [1000, 2000])],
+            global_sequences: vec![500],
+            textures: vec![Texture::new(0, "Textures\\Box.blp".to_string(), 0)],
+            geosets: vec![Geoset {
+                vertices: vec![
+                    Vertex { x: 0.0, y: 0.0, z: 0.0 },
+                    Vertex { x: 2.0, y: 0.0, z: 0.0 },
+                    Vertex { x: 0.0, y: 1.0, z: 0.0 },
+                ],
+                faces: vec![Face { indices: [0, 1, 2] }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let data = mdx_writer::write_model(&model).unwrap();
+
+        let expected = "\
+version 800
+name \"Box\"
+bounds [0, 0, 0] [2, 1, 0]
+sequences 2
+  \"Stand\" 0-1000
+  \"Death\" 1000-2000 nonlooping
+global_sequences 1
+  500
+textures 1
+  0 \"Textures\\Box.blp\" replaceable=0 flags=0
+materials 0
+geosets 1
+  0 vertices=3 faces=1 groups=0
+bones 0
+helpers 0
+attachments 0
+particle_emitters 0
+";
+        assert_eq!(mdx_to_text_summary(&data).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_export_obj_triangle() {
+        use crate::mdx_parser::{Face, Geoset, Normal, UV};
+
+        let model = MdxModel {
+            version: 800,
+            name: "Tri".to_string(),
+            geosets: vec![Geoset {
+                vertices: vec![
+                    Vertex { x: 0.0, y: 0.0, z: 0.0 },
+                    Vertex { x: 1.0, y: 0.0, z: 0.0 },
+                    Vertex { x: 0.0, y: 1.0, z: 0.0 },
+                ],
+                normals: vec![Normal { x: 0.0, y: 0.0, z: 1.0 }; 3],
+                uvs: vec![vec![UV { u: 0.0, v: 0.0 }, UV { u: 1.0, v: 0.0 }, UV { u: 0.0, v: 1.0 }]],
+                faces: vec![Face { indices: [0, 1, 2] }],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let data = mdx_writer::write_model(&model).unwrap();
+
+        // 索引从 1 开始，V 坐标翻转
+        let expected = "\
+# Tri
+o geoset_0
+g geoset_0
+usemtl material_0
+v 0 0 0
+v 1 0 0
+v 0 1 0
+vt 0 1
+vt 1 1
+vt 0 0
+vn 0 0 1
+vn 0 0 1
+vn 0 0 1
+f 1/1/1 2/2/2 3/3/3
+";
+        assert_eq!(export_obj(&data).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_degenerate_faces() {
+        use crate::mdx_parser::{Face, Geoset};
+
+        let mut model = MdxModel::default();
+        model.geosets.push(Geoset {
+            vertices: vec![
+                Vertex { x: 0.0, y: 0.0, z: 0.0 },
+                Vertex { x: 1.0, y: 0.0, z: 0.0 },
+                Vertex { x: 0.0, y: 1.0, z: 0.0 },
+                Vertex { x: 2.0, y: 0.0, z: 0.0 },
+            ],
+            faces: vec![
+                Face { indices: [0, 1, 2] }, // 正常
+                Face { indices: [0, 1, 1] }, // 重复索引
+                Face { indices: [0, 1, 3] }, // 共线，面积为 0
+                Face { indices: [0, 1, 9] }, // 越界，不在此处报告
+            ],
+            ..Default::default()
+        });
+        assert_eq!(degenerate_faces(&model), vec![(0, 1), (0, 2)]);
+    }
+
+    #[test]
+    fn test_dependency_paths() {
+        use crate::mdx_parser::{Node, NodeFlags, ParticleEmitter};
+
+        let textures = ["Textures\\Gutz.blp", "", "war3mapImported\\Skin.blp", "textures/gutz.BLP"]
+            .iter()
+            .map(|p| Texture::new(0, p.to_string(), 0))
+            .collect();
+        let emitter = ParticleEmitter {
+            node: Node {
+                name: "Emitter".to_string(),
+                object_id: 0,
+                parent_id: NO_PARENT,
+                flags: 0x1000,
+                decoded_flags: NodeFlags::default(),
+                translation: None,
+                rotation: None,
+                scaling: None,
+            },
+            emission_rate: 0.0,
+            gravity: 0.0,
+            longitude: 0.0,
+            latitude: 0.0,
+            path: "Abilities\\Spells\\Fire.mdl".to_string(),
+            life_span: 0.0,
+            initial_velocity: 0.0,
+        };
+        let model = MdxModel {
+            textures,
+            particle_emitters: vec![emitter],
+            ..Default::default()
+        };
+
+        // 空路径（可替换纹理）被忽略，同一路径的不同写法只保留一次
+        assert_eq!(
+            dependency_paths(&model, false),
+            vec!["Abilities\\Spells\\Fire.mdl", "Textures\\Gutz.blp"]
+        );
+        assert_eq!(imported_dependencies(&model), vec!["war3mapImported\\Skin.blp"]);
+    }
+
+    #[test]
+    fn test_attachment_name_warnings() {
+        let warnings = attachment_name_warnings(&[
+            "Origin Ref",
+            "Ovrhead Ref",
+            "HandLeft Ref",
+            "Chest Ref",
+            "Head Ref",
+        ]);
+        assert!(warnings.iter().any(|w| w.contains("\"ovrhead\"") && w.contains("\"overhead\"")));
+        assert!(warnings.iter().any(|w| w.contains("\"handleft\"") && w.contains("\"hand\"")));
+        assert!(warnings.iter().any(|w| w == "Missing standard attachment point \"overhead\""));
+
+        let clean = attachment_name_warnings(&["Origin Ref", "Overhead Ref", "Head Ref", "Chest Ref", "Hand Left Ref"]);
+        assert!(clean.is_empty(), "{:?}", clean);
+    }
+
+    #[test]
+    fn test_timeline() {
+        let sequence = |name: &str, interval: [u32; 2]| Sequence {
+            name: name.to_string(),
+            interval,
+            move_speed: 0.0,
+            non_looping: name == "Death",
+            rarity: 0.0,
+            sync_point: 0,
+            bounds_radius: 0.0,
+            minimum_extent: Vertex::default(),
+            maximum_extent: Vertex::default(),
+        };
+        let model = MdxModel {
+            sequences: vec![sequence("Stand", [0, 2000]), sequence("Death", [3000, 4500])],
+            global_sequences: vec![1000, 250],
+            ..Default::default()
+        };
+
+        let timeline = timeline(&model);
+        assert_eq!(timeline.sequences[0].duration_ms, 2000);
+        assert_eq!(timeline.sequences[1].interval, [3000, 4500]);
+        assert_eq!(timeline.sequences[1].duration_ms, 1500);
+        assert!(timeline.sequences[1].non_looping);
+        assert_eq!(timeline.global_sequences, vec![1000, 250]);
+    }
+
+    #[test]
+    fn test_find_portrait_camera() {
+        let camera = |name: &str| Camera {
+            name: name.to_string(),
+            position: Vertex::default(),
+            field_of_view: 0.785,
+            far_clip: 1000.0,
+            near_clip: 8.0,
+            target: Vertex::default(),
+            translation: None,
+            rotation: None,
+            target_translation: None,
+        };
+        let mut model = MdxModel::default();
+        assert!(find_portrait_camera(&model).is_none());
+
+        model.cameras = vec![camera("Camera01"), camera("Footman_Portrait")];
+        assert_eq!(find_portrait_camera(&model).unwrap().name, "Footman_Portrait");
+
+        model.cameras.pop();
+        assert_eq!(find_portrait_camera(&model).unwrap().name, "Camera01");
+    }
+}
@@ -0,0 +1,913 @@
+// MDX 二进制写出工具
+// 以 chunk 为单位读写模型：只重写被修改的 chunk，其余 chunk 按原样保留，保证不丢数据
+// `write_model` 则从 MdxModel 完整生成 MDX，只包含解析器能读取的数据
+
+use crate::mdx_parser::{
+    Camera, CollisionShape, Geoset, GeosetAnimation, Layer, Light, Material, MdxModel, Node,
+    ParticleEmitter, ParticleEmitter2, Sequence, Texture, TextureAnimation, Track, Vertex,
+    COLLISION_CYLINDER, COLLISION_SPHERE, NAME_LEN, PATH_LEN, PRIMITIVE_TRIANGLES,
+    SEQUENCE_RECORD_SIZE,
+};
+use byteorder::{LittleEndian, WriteBytesExt};
+
+const MDX_MAGIC: &[u8; 4] = b"MDLX";
+// extent 结构: bounds_radius + min[3] + max[3]
+const EXTENT_SIZE: usize = 28;
+// MODL 中 extent 的位置: name + animation_file
+const MODEL_EXTENT_OFFSET: usize = NAME_LEN + PATH_LEN;
+// SEQS 记录中 extent 的位置: name + interval + move_speed + flags + rarity + sync_point
+const SEQUENCE_EXTENT_OFFSET: usize = NAME_LEN + 8 + 4 + 4 + 4 + 4;
+
+/// 顶层 chunk（标识 + 原始数据，不含 8 字节头）
+#[derive(Debug, Clone)]
+pub struct MdxChunk {
+    pub tag: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+/// 将 MDX 文件拆分为顶层 chunk 列表
+pub fn read_chunks(data: &[u8]) -> Result<Vec<MdxChunk>, String> {
+    if data.len() < 4 || &data[..4] != MDX_MAGIC {
+        return Err("Invalid MDX magic".to_string());
+    }
+
+    let mut chunks = Vec::new();
+    let mut pos = 4;
+    while pos + 8 <= data.len() {
+        let mut tag = [0u8; 4];
+        tag.copy_from_slice(&data[pos..pos + 4]);
+        let size = read_u32_at(data, pos + 4)? as usize;
+        let start = pos + 8;
+        let end = start
+            .checked_add(size)
+            .filter(|&end| end <= data.len())
+            .ok_or_else(|| {
+                format!(
+                    "Chunk {} exceeds file size",
+                    String::from_utf8_lossy(&tag)
+                )
+            })?;
+
+        chunks.push(MdxChunk {
+            tag,
+            data: data[start..end].to_vec(),
+        });
+        pos = end;
+    }
+
+    Ok(chunks)
+}
+
+/// 将 chunk 列表重新组装为 MDX 文件
+pub fn write_chunks(chunks: &[MdxChunk]) -> Vec<u8> {
+    let total = 4 + chunks.iter().map(|c| 8 + c.data.len()).sum::<usize>();
+    let mut out = Vec::with_capacity(total);
+    out.extend_from_slice(MDX_MAGIC);
+    for chunk in chunks {
+        out.extend_from_slice(&chunk.tag);
+        out.write_u32::<LittleEndian>(chunk.data.len() as u32).unwrap();
+        out.extend_from_slice(&chunk.data);
+    }
+    out
+}
+
+/// 写入定长、以 null 结尾的字符串字段
+pub fn write_fixed_string(out: &mut Vec<u8>, value: &str, len: usize) -> Result<(), String> {
+    let bytes = value.as_bytes();
+    if bytes.len() > len {
+        return Err(format!(
+            "String \"{}\" is too long for a {}-byte field",
+            value, len
+        ));
+    }
+    out.extend_from_slice(bytes);
+    out.resize(out.len() + len - bytes.len(), 0);
+    Ok(())
+}
+
+/// 序列化 TEXS chunk 数据
+pub fn write_textures(textures: &[Texture]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for texture in textures {
+        out.write_u32::<LittleEndian>(texture.replaceable_id).unwrap();
+        write_fixed_string(&mut out, &texture.path, PATH_LEN)?;
+        out.write_u32::<LittleEndian>(texture.flags).unwrap();
+    }
+    Ok(out)
+}
+
+fn write_vertex(out: &mut Vec<u8>, v: &Vertex) {
+    out.write_f32::<LittleEndian>(v.x).unwrap();
+    out.write_f32::<LittleEndian>(v.y).unwrap();
+    out.write_f32::<LittleEndian>(v.z).unwrap();
+}
+
+/// 序列化 SEQS chunk 数据
+pub fn write_sequences(sequences: &[Sequence]) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for sequence in sequences {
+        write_fixed_string(&mut out, &sequence.name, NAME_LEN)?;
+        out.write_u32::<LittleEndian>(sequence.interval[0]).unwrap();
+        out.write_u32::<LittleEndian>(sequence.interval[1]).unwrap();
+        out.write_f32::<LittleEndian>(sequence.move_speed).unwrap();
+        out.write_u32::<LittleEndian>(sequence.non_looping as u32).unwrap();
+        out.write_f32::<LittleEndian>(sequence.rarity).unwrap();
+        out.write_u32::<LittleEndian>(sequence.sync_point).unwrap();
+        out.write_f32::<LittleEndian>(sequence.bounds_radius).unwrap();
+        write_vertex(&mut out, &sequence.minimum_extent);
+        write_vertex(&mut out, &sequence.maximum_extent);
+    }
+    Ok(out)
+}
+
+/// 从 MdxModel 生成完整的 MDX 文件
+///
+/// 按标准顺序写出解析器支持的 chunk，所有 inclusive size 重新计算。
+/// 解析时未保留的数据（如 TANG、LOD、可见性轨道、非三角形图元）不会写出；
+/// 面统一写为一个三角形列表分组。
+pub fn write_model(model: &MdxModel) -> Result<Vec<u8>, String> {
+    let mut chunks = vec![
+        MdxChunk {
+            tag: *b"VERS",
+            data: model.version.to_le_bytes().to_vec(),
+        },
+        MdxChunk {
+            tag: *b"MODL",
+            data: write_model_info(model)?,
+        },
+    ];
+    let mut push = |tag: &[u8; 4], data: Vec<u8>| {
+        if !data.is_empty() {
+            chunks.push(MdxChunk { tag: *tag, data });
+        }
+    };
+
+    let version = model.version;
+    push(b"SEQS", write_sequences(&model.sequences)?);
+    push(
+        b"GLBS",
+        model.global_sequences.iter().flat_map(|d| d.to_le_bytes()).collect(),
+    );
+    push(b"MTLS", write_records(&model.materials, |out, m| write_material(out, m, version))?);
+    push(b"TEXS", write_textures(&model.textures)?);
+    push(b"TXAN", write_records(&model.texture_animations, write_texture_animation)?);
+    let sequence_count = model.sequences.len();
+    push(
+        b"GEOS",
+        write_records(&model.geosets, |out, g| write_geoset(out, g, version, sequence_count))?,
+    );
+    push(b"GEOA", write_records(&model.geoset_animations, write_geoset_animation)?);
+    push(
+        b"BONE",
+        write_records(&model.bones, |out, bone| {
+            write_node(out, &bone.node)?;
+            out.write_u32::<LittleEndian>(bone.geoset_id).unwrap();
+            out.write_u32::<LittleEndian>(bone.geoset_animation_id).unwrap();
+            Ok(())
+        })?,
+    );
+    push(b"LITE", write_records(&model.lights, write_light)?);
+    push(b"HELP", write_records(&model.helpers, write_node)?);
+    push(
+        b"ATCH",
+        write_records(&model.attachments, |out, attachment| {
+            write_sized(out, |out| {
+                write_node(out, &attachment.node)?;
+                write_fixed_string(out, &attachment.path, PATH_LEN)?;
+                out.write_u32::<LittleEndian>(attachment.attachment_id).unwrap();
+                Ok(())
+            })
+        })?,
+    );
+    let mut pivots = Vec::new();
+    for pivot in &model.pivot_points {
+        write_vertex(&mut pivots, pivot);
+    }
+    push(b"PIVT", pivots);
+    push(b"PREM", write_records(&model.particle_emitters, write_particle_emitter)?);
+    push(b"PRE2", write_records(&model.particle_emitters2, write_particle_emitter2)?);
+    push(b"CAMS", write_records(&model.cameras, write_camera)?);
+    push(b"CLID", write_records(&model.collision_shapes, write_collision_shape)?);
+
+    Ok(write_chunks(&chunks))
+}
+
+/// MODL：名称、动画文件、extent 与 blend_time
+fn write_model_info(model: &MdxModel) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    write_fixed_string(&mut out, &model.name, NAME_LEN)?;
+    write_fixed_string(&mut out, "", PATH_LEN)?;
+    out.write_f32::<LittleEndian>(model.overall_bounds_radius).unwrap();
+    write_vertex(&mut out, &model.overall_bounds.min);
+    write_vertex(&mut out, &model.overall_bounds.max);
+    out.write_u32::<LittleEndian>(150).unwrap(); // blend_time，模型中未保留，取常用值
+    Ok(out)
+}
+
+/// 依次写出一组记录
+fn write_records<T>(
+    items: &[T],
+    mut write: impl FnMut(&mut Vec<u8>, &T) -> Result<(), String>,
+) -> Result<Vec<u8>, String> {
+    let mut out = Vec::new();
+    for item in items {
+        write(&mut out, item)?;
+    }
+    Ok(out)
+}
+
+/// 写出以 inclusive size（含自身 4 字节）开头的记录
+fn write_sized(
+    out: &mut Vec<u8>,
+    write: impl FnOnce(&mut Vec<u8>) -> Result<(), String>,
+) -> Result<(), String> {
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    write(out)?;
+    let size = (out.len() - start) as u32;
+    out[start..start + 4].copy_from_slice(&size.to_le_bytes());
+    Ok(())
+}
+
+/// 写出带标识的关键帧轨道，Hermite/Bezier 插值缺少切线时以关键帧值代替
+fn write_track<T>(
+    out: &mut Vec<u8>,
+    tag: &[u8; 4],
+    track: &Option<Track<T>>,
+    write_value: impl Fn(&mut Vec<u8>, &T),
+) {
+    let Some(track) = track else {
+        return;
+    };
+    out.extend_from_slice(tag);
+    out.write_u32::<LittleEndian>(track.keys.len() as u32).unwrap();
+    out.write_u32::<LittleEndian>(track.interpolation).unwrap();
+    out.write_i32::<LittleEndian>(track.global_sequence_id).unwrap();
+    for key in &track.keys {
+        out.write_u32::<LittleEndian>(key.frame).unwrap();
+        write_value(out, &key.value);
+        if track.interpolation > 1 {
+            write_value(out, key.in_tan.as_ref().unwrap_or(&key.value));
+            write_value(out, key.out_tan.as_ref().unwrap_or(&key.value));
+        }
+    }
+}
+
+fn write_floats<const N: usize>(out: &mut Vec<u8>, values: &[f32; N]) {
+    for value in values {
+        out.write_f32::<LittleEndian>(*value).unwrap();
+    }
+}
+
+fn write_u32_value(out: &mut Vec<u8>, value: &u32) {
+    out.write_u32::<LittleEndian>(*value).unwrap();
+}
+
+/// 节点公共头及 KGTR/KGRT/KGSC 轨道
+fn write_node(out: &mut Vec<u8>, node: &Node) -> Result<(), String> {
+    write_sized(out, |out| {
+        write_fixed_string(out, &node.name, NAME_LEN)?;
+        out.write_u32::<LittleEndian>(node.object_id).unwrap();
+        out.write_u32::<LittleEndian>(node.parent_id).unwrap();
+        out.write_u32::<LittleEndian>(node.flags).unwrap();
+        write_track(out, b"KGTR", &node.translation, write_floats);
+        write_track(out, b"KGRT", &node.rotation, write_floats);
+        write_track(out, b"KGSC", &node.scaling, write_floats);
+        Ok(())
+    })
+}
+
+fn write_material(out: &mut Vec<u8>, material: &Material, version: u32) -> Result<(), String> {
+    write_sized(out, |out| {
+        out.write_u32::<LittleEndian>(material.priority_plane).unwrap();
+        out.write_u32::<LittleEndian>(material.flags).unwrap();
+        if version > 800 {
+            write_fixed_string(out, &material.shader, NAME_LEN)?;
+        }
+        out.extend_from_slice(b"LAYS");
+        out.write_u32::<LittleEndian>(material.layers.len() as u32).unwrap();
+        for layer in &material.layers {
+            write_layer(out, layer, version)?;
+        }
+        Ok(())
+    })
+}
+
+fn write_layer(out: &mut Vec<u8>, layer: &Layer, version: u32) -> Result<(), String> {
+    write_sized(out, |out| {
+        out.write_u32::<LittleEndian>(layer.filter_mode).unwrap();
+        out.write_u32::<LittleEndian>(layer.shading_flags).unwrap();
+        out.write_u32::<LittleEndian>(layer.texture_id).unwrap();
+        out.write_u32::<LittleEndian>(layer.texture_animation_id).unwrap();
+        out.write_u32::<LittleEndian>(layer.coord_id).unwrap();
+        out.write_f32::<LittleEndian>(layer.alpha).unwrap();
+        if version > 800 {
+            out.write_f32::<LittleEndian>(layer.emissive_gain.unwrap_or(1.0)).unwrap();
+        }
+        if version > 900 {
+            write_floats(out, &layer.fresnel_color.unwrap_or([1.0; 3]));
+            out.write_f32::<LittleEndian>(layer.fresnel_opacity.unwrap_or(0.0)).unwrap();
+            out.write_f32::<LittleEndian>(layer.fresnel_team_color.unwrap_or(0.0)).unwrap();
+        }
+        write_track(out, b"KMTA", &layer.alpha_track, write_floats);
+        write_track(out, b"KMTF", &layer.texture_track, write_u32_value);
+        Ok(())
+    })
+}
+
+fn write_texture_animation(out: &mut Vec<u8>, animation: &TextureAnimation) -> Result<(), String> {
+    write_sized(out, |out| {
+        write_track(out, b"KTAT", &animation.translation, write_floats);
+        write_track(out, b"KTAR", &animation.rotation, write_floats);
+        write_track(out, b"KTAS", &animation.scaling, write_floats);
+        Ok(())
+    })
+}
+
+/// 单个 geoset；整体 extent 取 `bounds`，并为每个序列写入同样的 extent
+fn write_geoset(
+    out: &mut Vec<u8>,
+    geoset: &Geoset,
+    version: u32,
+    sequence_count: usize,
+) -> Result<(), String> {
+    write_sized(out, |out| {
+        out.extend_from_slice(b"VRTX");
+        out.write_u32::<LittleEndian>(geoset.vertices.len() as u32).unwrap();
+        for vertex in &geoset.vertices {
+            write_vertex(out, vertex);
+        }
+        out.extend_from_slice(b"NRMS");
+        out.write_u32::<LittleEndian>(geoset.normals.len() as u32).unwrap();
+        for normal in &geoset.normals {
+            write_floats(out, &[normal.x, normal.y, normal.z]);
+        }
+
+        let index_count = geoset.faces.len() as u32 * 3;
+        out.extend_from_slice(b"PTYP");
+        out.write_u32::<LittleEndian>(1).unwrap();
+        out.write_u32::<LittleEndian>(PRIMITIVE_TRIANGLES).unwrap();
+        out.extend_from_slice(b"PCNT");
+        out.write_u32::<LittleEndian>(1).unwrap();
+        out.write_u32::<LittleEndian>(index_count).unwrap();
+        out.extend_from_slice(b"PVTX");
+        out.write_u32::<LittleEndian>(index_count).unwrap();
+        for index in geoset.faces.iter().flat_map(|face| face.indices) {
+            out.write_u16::<LittleEndian>(index).unwrap();
+        }
+
+        out.extend_from_slice(b"GNDX");
+        out.write_u32::<LittleEndian>(geoset.vertex_groups.len() as u32).unwrap();
+        out.extend_from_slice(&geoset.vertex_groups);
+        out.extend_from_slice(b"MTGC");
+        out.write_u32::<LittleEndian>(geoset.matrix_groups.len() as u32).unwrap();
+        for size in &geoset.matrix_groups {
+            out.write_u32::<LittleEndian>(*size).unwrap();
+        }
+        out.extend_from_slice(b"MATS");
+        out.write_u32::<LittleEndian>(geoset.matrix_indices.len() as u32).unwrap();
+        for index in &geoset.matrix_indices {
+            out.write_u32::<LittleEndian>(*index).unwrap();
+        }
+
+        out.write_u32::<LittleEndian>(geoset.material_id).unwrap();
+        out.write_u32::<LittleEndian>(geoset.selection_group).unwrap();
+        out.write_u32::<LittleEndian>(geoset.selection_flags).unwrap();
+        if version > 800 {
+            out.write_u32::<LittleEndian>(0).unwrap(); // LOD
+            write_fixed_string(out, "", NAME_LEN)?;
+        }
+        let mut extent = Vec::with_capacity(EXTENT_SIZE);
+        extent.write_f32::<LittleEndian>(geoset.bounds.radius()).unwrap();
+        write_vertex(&mut extent, &geoset.bounds.min);
+        write_vertex(&mut extent, &geoset.bounds.max);
+        out.extend_from_slice(&extent);
+        out.write_u32::<LittleEndian>(sequence_count as u32).unwrap();
+        for _ in 0..sequence_count {
+            out.extend_from_slice(&extent);
+        }
+
+        if version > 800 && !geoset.skin.is_empty() {
+            out.extend_from_slice(b"SKIN");
+            out.write_u32::<LittleEndian>(geoset.skin.len() as u32 * 8).unwrap();
+            for entry in &geoset.skin {
+                out.extend_from_slice(entry);
+            }
+        }
+
+        out.extend_from_slice(b"UVAS");
+        out.write_u32::<LittleEndian>(geoset.uvs.len() as u32).unwrap();
+        for uvs in &geoset.uvs {
+            out.extend_from_slice(b"UVBS");
+            out.write_u32::<LittleEndian>(uvs.len() as u32).unwrap();
+            for uv in uvs {
+                write_floats(out, &[uv.u, uv.v]);
+            }
+        }
+        Ok(())
+    })
+}
+
+fn write_geoset_animation(out: &mut Vec<u8>, animation: &GeosetAnimation) -> Result<(), String> {
+    write_sized(out, |out| {
+        out.write_f32::<LittleEndian>(animation.alpha).unwrap();
+        out.write_u32::<LittleEndian>(animation.flags).unwrap();
+        write_floats(out, &animation.color);
+        out.write_u32::<LittleEndian>(animation.geoset_id).unwrap();
+        write_track(out, b"KGAO", &animation.alpha_track, write_floats);
+        write_track(out, b"KGAC", &animation.color_track, write_floats);
+        Ok(())
+    })
+}
+
+fn write_light(out: &mut Vec<u8>, light: &Light) -> Result<(), String> {
+    write_sized(out, |out| {
+        write_node(out, &light.node)?;
+        out.write_u32::<LittleEndian>(light.light_type).unwrap();
+        out.write_f32::<LittleEndian>(light.attenuation_start).unwrap();
+        out.write_f32::<LittleEndian>(light.attenuation_end).unwrap();
+        write_floats(out, &light.color);
+        out.write_f32::<LittleEndian>(light.intensity).unwrap();
+        write_floats(out, &light.ambient_color);
+        out.write_f32::<LittleEndian>(light.ambient_intensity).unwrap();
+        Ok(())
+    })
+}
+
+fn write_particle_emitter(out: &mut Vec<u8>, emitter: &ParticleEmitter) -> Result<(), String> {
+    write_sized(out, |out| {
+        write_node(out, &emitter.node)?;
+        write_floats(
+            out,
+            &[emitter.emission_rate, emitter.gravity, emitter.longitude, emitter.latitude],
+        );
+        write_fixed_string(out, &emitter.path, PATH_LEN)?;
+        write_floats(out, &[emitter.life_span, emitter.initial_velocity]);
+        Ok(())
+    })
+}
+
+fn write_particle_emitter2(out: &mut Vec<u8>, emitter: &ParticleEmitter2) -> Result<(), String> {
+    write_sized(out, |out| {
+        write_node(out, &emitter.node)?;
+        write_floats(
+            out,
+            &[
+                emitter.speed,
+                emitter.variation,
+                emitter.latitude,
+                emitter.gravity,
+                emitter.life_span,
+                emitter.emission_rate,
+                emitter.width,
+                emitter.length,
+            ],
+        );
+        for value in [emitter.filter_mode, emitter.rows, emitter.columns, emitter.head_or_tail] {
+            out.write_u32::<LittleEndian>(value).unwrap();
+        }
+        write_floats(out, &[emitter.tail_length, emitter.time]);
+        for color in &emitter.segment_color {
+            write_floats(out, color);
+        }
+        out.extend_from_slice(&emitter.segment_alpha);
+        write_floats(out, &emitter.segment_scaling);
+        for value in emitter
+            .head_intervals
+            .iter()
+            .chain(&emitter.tail_intervals)
+            .flatten()
+        {
+            out.write_u32::<LittleEndian>(*value).unwrap();
+        }
+        out.write_u32::<LittleEndian>(emitter.texture_id).unwrap();
+        out.write_u32::<LittleEndian>(emitter.squirt).unwrap();
+        out.write_i32::<LittleEndian>(emitter.priority_plane).unwrap();
+        out.write_u32::<LittleEndian>(emitter.replaceable_id).unwrap();
+        write_track(out, b"KP2S", &emitter.speed_track, write_floats);
+        write_track(out, b"KP2R", &emitter.variation_track, write_floats);
+        write_track(out, b"KP2L", &emitter.latitude_track, write_floats);
+        write_track(out, b"KP2G", &emitter.gravity_track, write_floats);
+        write_track(out, b"KP2E", &emitter.emission_rate_track, write_floats);
+        write_track(out, b"KP2N", &emitter.length_track, write_floats);
+        write_track(out, b"KP2W", &emitter.width_track, write_floats);
+        write_track(out, b"KP2V", &emitter.visibility_track, write_floats);
+        Ok(())
+    })
+}
+
+fn write_camera(out: &mut Vec<u8>, camera: &Camera) -> Result<(), String> {
+    write_sized(out, |out| {
+        write_fixed_string(out, &camera.name, NAME_LEN)?;
+        write_vertex(out, &camera.position);
+        write_floats(out, &[camera.field_of_view, camera.far_clip, camera.near_clip]);
+        write_vertex(out, &camera.target);
+        write_track(out, b"KCTR", &camera.translation, write_floats);
+        write_track(out, b"KCRL", &camera.rotation, write_floats);
+        write_track(out, b"KTTR", &camera.target_translation, write_floats);
+        Ok(())
+    })
+}
+
+fn write_collision_shape(out: &mut Vec<u8>, shape: &CollisionShape) -> Result<(), String> {
+    write_node(out, &shape.node)?;
+    out.write_u32::<LittleEndian>(shape.shape_type).unwrap();
+    let vertex_count = if shape.shape_type == COLLISION_SPHERE { 1 } else { 2 };
+    if shape.vertices.len() != vertex_count {
+        return Err(format!(
+            "Collision shape {} needs {} vertices, got {}",
+            shape.node.name,
+            vertex_count,
+            shape.vertices.len()
+        ));
+    }
+    for vertex in &shape.vertices {
+        write_vertex(out, vertex);
+    }
+    if shape.shape_type == COLLISION_SPHERE || shape.shape_type == COLLISION_CYLINDER {
+        out.write_f32::<LittleEndian>(shape.radius).unwrap();
+    }
+    Ok(())
+}
+
+/// 原地修改节点类记录（ATCH/PREM）中的路径字段
+///
+/// 每条记录布局为 `inclusive_size, node, [field_offset 字节], path[260], ...`，
+/// `remap` 返回 `Some` 时替换路径。返回被修改的记录数。
+pub fn patch_node_record_paths<F>(
+    data: &mut [u8],
+    field_offset: usize,
+    mut remap: F,
+) -> Result<usize, String>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut patched = 0;
+    let mut pos = 0;
+    while pos + 8 <= data.len() {
+        let record_size = read_u32_at(data, pos)? as usize;
+        let node_size = read_u32_at(data, pos + 4)? as usize;
+        let path_start = pos + 4 + node_size + field_offset;
+        let record_end = pos + record_size;
+        if record_size == 0 || record_end > data.len() || path_start + PATH_LEN > record_end {
+            return Err("Corrupt node record".to_string());
+        }
+
+        let field = &mut data[path_start..path_start + PATH_LEN];
+        let end = field.iter().position(|&b| b == 0).unwrap_or(PATH_LEN);
+        let current = String::from_utf8_lossy(&field[..end]).to_string();
+        if let Some(new_path) = remap(&current) {
+            let mut encoded = Vec::with_capacity(PATH_LEN);
+            write_fixed_string(&mut encoded, &new_path, PATH_LEN)?;
+            field.copy_from_slice(&encoded);
+            patched += 1;
+        }
+
+        pos = record_end;
+    }
+    Ok(patched)
+}
+
+/// 去掉节点类记录（BONE/HELP 等）中的动画轨道，只保留节点头部（绑定姿势）
+///
+/// `trailing_size` 为每条记录中节点之后的定长字段大小（BONE 为 8，HELP 为 0）。
+pub fn strip_node_tracks(data: &[u8], trailing_size: usize) -> Result<Vec<u8>, String> {
+    // inclusive_size + name + object_id + parent_id + flags
+    const NODE_HEADER_SIZE: usize = 4 + NAME_LEN + 12;
+
+    let mut out = Vec::with_capacity(data.len());
+    let mut pos = 0;
+    while pos < data.len() {
+        let node_size = read_u32_at(data, pos)? as usize;
+        let record_end = pos + node_size + trailing_size;
+        if node_size < NODE_HEADER_SIZE || record_end > data.len() {
+            return Err("Corrupt node record".to_string());
+        }
+
+        out.write_u32::<LittleEndian>(NODE_HEADER_SIZE as u32).unwrap();
+        out.extend_from_slice(&data[pos + 4..pos + NODE_HEADER_SIZE]);
+        out.extend_from_slice(&data[pos + node_size..record_end]);
+        pos = record_end;
+    }
+    Ok(out)
+}
+
+/// 平移 MODL 中的模型 extent
+pub fn translate_model_extent(data: &mut [u8], delta: [f32; 3]) -> Result<(), String> {
+    translate_extent(data, MODEL_EXTENT_OFFSET, delta)
+}
+
+/// 平移 SEQS 中每个序列的 extent
+pub fn translate_sequence_extents(data: &mut [u8], delta: [f32; 3]) -> Result<(), String> {
+    for record in (0..data.len() / SEQUENCE_RECORD_SIZE).map(|i| i * SEQUENCE_RECORD_SIZE) {
+        translate_extent(data, record + SEQUENCE_EXTENT_OFFSET, delta)?;
+    }
+    Ok(())
+}
+
+/// 平移 PIVT 中的所有枢轴点
+pub fn translate_pivots(data: &mut [u8], delta: [f32; 3]) -> Result<(), String> {
+    for pos in (0..data.len() / 12).map(|i| i * 12) {
+        translate_vec3(data, pos, delta)?;
+    }
+    Ok(())
+}
+
+/// 平移 GEOS 中所有 geoset 的顶点及其 extent，其余数据原样保留
+pub fn translate_geosets(data: &mut [u8], version: u32, delta: [f32; 3]) -> Result<(), String> {
+    let mut pos = 0;
+    while pos + 4 <= data.len() {
+        let end = pos + read_u32_at(data, pos)? as usize;
+        if end <= pos + 4 || end > data.len() {
+            return Err("Corrupt geoset record".to_string());
+        }
+
+        let mut p = pos + 4;
+        while p + 8 <= end {
+            let tag = &data[p..p + 4];
+            let count = read_u32_at(data, p + 4)? as usize;
+            p += 8;
+            match tag {
+                b"VRTX" => {
+                    for i in 0..count {
+                        translate_vec3(data, p + i * 12, delta)?;
+                    }
+                    p += count * 12;
+                }
+                b"NRMS" => p += count * 12,
+                b"PTYP" | b"PCNT" | b"MTGC" => p += count * 4,
+                b"PVTX" => p += count * 2,
+                b"GNDX" | b"SKIN" => p += count,
+                b"TANG" => p += count * 16,
+                b"MATS" => {
+                    // material_id, selection_group, selection_flags（v900+ 另有 LOD 与 LOD 名称）
+                    p += count * 4 + 12;
+                    if version > 800 {
+                        p += 4 + NAME_LEN;
+                    }
+                    translate_extent(data, p, delta)?;
+                    let extent_count = read_u32_at(data, p + EXTENT_SIZE)? as usize;
+                    p += EXTENT_SIZE + 4;
+                    for i in 0..extent_count {
+                        translate_extent(data, p + i * EXTENT_SIZE, delta)?;
+                    }
+                    p += extent_count * EXTENT_SIZE;
+                }
+                b"UVAS" => {
+                    for _ in 0..count {
+                        if data.get(p..p + 4) != Some(b"UVBS".as_slice()) {
+                            return Err("Expected UVBS after UVAS".to_string());
+                        }
+                        p += 8 + read_u32_at(data, p + 4)? as usize * 8;
+                    }
+                }
+                _ => {
+                    return Err(format!(
+                        "Unknown geoset chunk {}",
+                        String::from_utf8_lossy(tag)
+                    ))
+                }
+            }
+        }
+        pos = end;
+    }
+    Ok(())
+}
+
+/// 平移 CLID 中所有碰撞体的顶点
+pub fn translate_collision_shapes(data: &mut [u8], delta: [f32; 3]) -> Result<(), String> {
+    let mut pos = 0;
+    while pos < data.len() {
+        let node_size = read_u32_at(data, pos)? as usize;
+        if node_size == 0 {
+            return Err("Corrupt collision shape record".to_string());
+        }
+        let shape_type = read_u32_at(data, pos + node_size)?;
+        let vertex_count = if shape_type == COLLISION_SPHERE { 1 } else { 2 };
+        let vertices = pos + node_size + 4;
+        for i in 0..vertex_count {
+            translate_vec3(data, vertices + i * 12, delta)?;
+        }
+        pos = vertices + vertex_count * 12;
+        if shape_type == COLLISION_SPHERE || shape_type == COLLISION_CYLINDER {
+            pos += 4; // radius
+        }
+    }
+    Ok(())
+}
+
+/// 平移 extent 的 min/max（bounds_radius 不变）
+fn translate_extent(data: &mut [u8], pos: usize, delta: [f32; 3]) -> Result<(), String> {
+    translate_vec3(data, pos + 4, delta)?;
+    translate_vec3(data, pos + 16, delta)
+}
+
+fn translate_vec3(data: &mut [u8], pos: usize, delta: [f32; 3]) -> Result<(), String> {
+    let bytes = data
+        .get_mut(pos..pos + 12)
+        .ok_or_else(|| "Unexpected end of data".to_string())?;
+    for (chunk, d) in bytes.chunks_exact_mut(4).zip(delta) {
+        let value = f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]) + d;
+        chunk.copy_from_slice(&value.to_le_bytes());
+    }
+    Ok(())
+}
+
+fn read_u32_at(data: &[u8], pos: usize) -> Result<u32, String> {
+    let bytes = data
+        .get(pos..pos + 4)
+        .ok_or_else(|| "Unexpected end of data".to_string())?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mdx_parser::{
+        calculate_bounds, Attachment, Bone, Face, Keyframe, MdxParser, NodeFlags, Normal,
+        NO_PARENT, UV,
+    };
+
+    fn node(name: &str, object_id: u32) -> Node {
+        Node {
+            name: name.to_string(),
+            object_id,
+            parent_id: NO_PARENT,
+            flags: 0,
+            decoded_flags: NodeFlags::default(),
+            translation: None,
+            rotation: None,
+            scaling: None,
+        }
+    }
+
+    fn sample_model() -> MdxModel {
+        let vertices = vec![
+            Vertex { x: 0.0, y: 0.0, z: 0.0 },
+            Vertex { x: 1.0, y: 0.0, z: 0.0 },
+            Vertex { x: 0.0, y: 1.0, z: 2.0 },
+        ];
+        let mut bone = Bone {
+            node: node("Bone_Root", 0),
+            geoset_id: 0,
+            geoset_animation_id: u32::MAX,
+        };
+        bone.node.rotation = Some(Track {
+            interpolation: 1,
+            global_sequence_id: -1,
+            keys: vec![Keyframe {
+                frame: 100,
+                value: [0.0, 0.0, 0.0, 1.0],
+                in_tan: None,
+                out_tan: None,
+            }],
+        });
+
+        let mut model = MdxModel {
+            version: 800,
+            name: "RoundTrip".to_string(),
+            sequences: vec![Sequence {
+                name: "Stand".to_string(),
+                interval: [0, 1000],
+                move_speed: 0.0,
+                non_looping: false,
+                rarity: 0.0,
+                sync_point: 0,
+                bounds_radius: 1.0,
+                minimum_extent: Vertex::default(),
+                maximum_extent: Vertex { x: 1.0, y: 1.0, z: 2.0 },
+            }],
+            global_sequences: vec![2000],
+            textures: vec![
+                Texture::new(0, "Textures\\Test.blp".to_string(), 0),
+                Texture::new(1, String::new(), 0),
+            ],
+            materials: vec![Material {
+                priority_plane: 0,
+                flags: 0,
+                shader: String::new(),
+                layers: vec![Layer {
+                    filter_mode: 1,
+                    shading_flags: 0,
+                    texture_id: 0,
+                    texture_animation_id: u32::MAX,
+                    coord_id: 0,
+                    alpha: 1.0,
+                    alpha_track: Some(Track {
+                        interpolation: 2,
+                        global_sequence_id: 0,
+                        keys: vec![Keyframe {
+                            frame: 0,
+                            value: [0.5],
+                            in_tan: Some([0.25]),
+                            out_tan: Some([0.75]),
+                        }],
+                    }),
+                    texture_track: None,
+                    emissive_gain: None,
+                    fresnel_color: None,
+                    fresnel_opacity: None,
+                    fresnel_team_color: None,
+                }],
+            }],
+            geosets: vec![Geoset {
+                bounds: calculate_bounds(&vertices),
+                normals: vec![Normal { x: 0.0, y: 0.0, z: 1.0 }; 3],
+                uvs: vec![vec![UV { u: 0.0, v: 1.0 }; 3]],
+                faces: vec![Face { indices: [0, 1, 2] }],
+                vertex_groups: vec![0; 3],
+                matrix_groups: vec![1],
+                matrix_indices: vec![0],
+                vertices,
+                ..Default::default()
+            }],
+            bones: vec![bone],
+            helpers: vec![node("Helper", 1)],
+            attachments: vec![Attachment {
+                node: node("Origin Ref", 2),
+                path: String::new(),
+                attachment_id: 0,
+            }],
+            pivot_points: vec![Vertex::default(); 3],
+            ..Default::default()
+        };
+        model.update_bounds();
+        model
+    }
+
+    #[test]
+    fn test_write_model_round_trip() {
+        // 经过 JSON 往返，与前端编辑后写回的路径一致
+        let json = serde_json::to_string(&sample_model()).unwrap();
+        let model: MdxModel = serde_json::from_str(&json).unwrap();
+        let data = write_model(&model).unwrap();
+        let parsed = MdxParser::new(data.clone()).unwrap().parse().unwrap();
+
+        assert!(parsed.warnings.is_empty(), "{:?}", parsed.warnings);
+        assert_eq!(parsed.name, "RoundTrip");
+        assert_eq!(parsed.sequences[0].interval, [0, 1000]);
+        assert_eq!(parsed.global_sequences, vec![2000]);
+        assert_eq!(parsed.textures[0].path, "Textures\\Test.blp");
+        assert_eq!(parsed.textures[0].resolved_path, None);
+        assert_eq!(
+            parsed.textures[1].resolved_path.as_deref(),
+            Some("ReplaceableTextures\\TeamColor\\TeamColor00.blp")
+        );
+        let alpha = parsed.materials[0].layers[0].alpha_track.as_ref().unwrap();
+        assert_eq!(alpha.keys[0].in_tan, Some([0.25]));
+        assert_eq!(alpha.keys[0].out_tan, Some([0.75]));
+        let geoset = &parsed.geosets[0];
+        assert_eq!(geoset.vertices.len(), 3);
+        assert_eq!(geoset.faces[0].indices, [0, 1, 2]);
+        assert_eq!(geoset.uvs[0].len(), 3);
+        assert_eq!(geoset.matrix_indices, vec![0]);
+        assert_eq!(parsed.bones[0].node.rotation.as_ref().unwrap().keys[0].frame, 100);
+        assert_eq!(parsed.helpers[0].name, "Helper");
+        assert_eq!(parsed.attachments[0].node.name, "Origin Ref");
+        assert_eq!(parsed.pivot_points.len(), 3);
+
+        // 再次写出应得到完全相同的数据
+        assert_eq!(write_model(&parsed).unwrap(), data);
+    }
+
+    fn push_f32s(out: &mut Vec<u8>, values: &[f32]) {
+        for v in values {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+
+    #[test]
+    fn test_translate_geosets_moves_vertices_and_extents() {
+        let mut body = Vec::new();
+        body.extend_from_slice(b"VRTX");
+        body.extend_from_slice(&1u32.to_le_bytes());
+        push_f32s(&mut body, &[1.0, 2.0, 3.0]);
+        body.extend_from_slice(b"PVTX");
+        body.extend_from_slice(&3u32.to_le_bytes());
+        body.extend_from_slice(&[0; 6]);
+        body.extend_from_slice(b"MATS");
+        body.extend_from_slice(&0u32.to_le_bytes());
+        body.extend_from_slice(&[0; 12]);
+        push_f32s(&mut body, &[5.0, 1.0, 2.0, 3.0, 1.0, 2.0, 3.0]);
+        body.extend_from_slice(&0u32.to_le_bytes());
+        body.extend_from_slice(b"UVAS");
+        body.extend_from_slice(&1u32.to_le_bytes());
+        body.extend_from_slice(b"UVBS");
+        body.extend_from_slice(&1u32.to_le_bytes());
+        push_f32s(&mut body, &[0.5, 0.5]);
+
+        let mut data = ((body.len() + 4) as u32).to_le_bytes().to_vec();
+        data.extend_from_slice(&body);
+        translate_geosets(&mut data, 800, [-1.0, -2.0, -3.0]).unwrap();
+
+        let f32_at = |pos: usize| f32::from_le_bytes(data[pos..pos + 4].try_into().unwrap());
+        assert_eq!([f32_at(12), f32_at(16), f32_at(20)], [0.0; 3]);
+        // extent: bounds_radius 不变，min/max 平移
+        let extent = 4 + 20 + 14 + 8 + 12;
+        assert_eq!(f32_at(extent), 5.0);
+        assert_eq!(f32_at(extent + 4), 0.0);
+        assert_eq!(f32_at(extent + 24), 0.0);
+        // UV 不受影响
+        assert_eq!(f32_at(data.len() - 8), 0.5);
+    }
+}
@@ -0,0 +1,165 @@
+// 由地形数据生成小地图预览
+// 不读取地表贴图，按地表纹理 ID 的类别取基础色，再按高度明暗着色
+
+use crate::w3e_parser::{Terrain, POINT_BLIGHT, POINT_BOUNDARY, POINT_WATER};
+use image::{Rgba, RgbaImage};
+
+const WATER_COLOR: [f32; 3] = [40.0, 80.0, 150.0];
+const BLIGHT_COLOR: [f32; 3] = [90.0, 60.0, 90.0];
+const BOUNDARY_COLOR: [f32; 3] = [10.0, 10.0, 10.0];
+// 高度着色范围：最低点 x0.7，最高点 x1.3，平坦地图取中间值 x1.0
+const SHADE_MIN: f32 = 0.7;
+const SHADE_RANGE: f32 = 0.6;
+
+/// 按地表纹理 ID（如 "Ldrt"、"Agrs"）推测基础色
+fn ground_color(tile_id: &str) -> [f32; 3] {
+    let kind = tile_id.get(1..).unwrap_or("").to_ascii_lowercase();
+    let color = if kind.contains("gr") || kind.contains("lf") || kind.contains("vn") {
+        [70, 120, 50] // 草地、落叶、藤蔓
+    } else if kind.contains("sn") || kind.contains("ic") {
+        [220, 225, 235] // 雪、冰
+    } else if kind.contains("sa") || kind.contains("ds") {
+        [200, 180, 120] // 沙地
+    } else if kind.contains("rk")
+        || kind.contains("rok")
+        || kind.contains("st")
+        || kind.contains("br")
+    {
+        [120, 120, 115] // 岩石、石砖
+    } else if kind.contains("lv") || kind.contains("la") {
+        [160, 60, 30] // 熔岩
+    } else {
+        [110, 85, 60] // 泥土及其他
+    };
+    color.map(|c| c as f32)
+}
+
+fn mix(a: [f32; 3], b: [f32; 3], t: f32) -> [f32; 3] {
+    [0, 1, 2].map(|i| a[i] + (b[i] - a[i]) * t)
+}
+
+/// 将地形渲染为 `size`×`size` 的小地图，非正方形地图按比例居中，两侧留黑
+pub fn render_minimap(terrain: &Terrain, size: u32) -> RgbaImage {
+    let (width, height) = (terrain.header.width, terrain.header.height);
+    let mut img = RgbaImage::from_pixel(size, size, Rgba([0, 0, 0, 255]));
+    if width == 0 || height == 0 || size == 0 {
+        return img;
+    }
+
+    let palette: Vec<[f32; 3]> = terrain
+        .header
+        .ground_tilesets
+        .iter()
+        .map(|id| ground_color(id))
+        .collect();
+    let (min_h, max_h) = terrain
+        .points
+        .iter()
+        .fold((f32::MAX, f32::MIN), |(lo, hi), p| {
+            (lo.min(p.height), hi.max(p.height))
+        });
+
+    let scale = size as f32 / width.max(height) as f32;
+    let draw_w = ((width as f32 * scale) as u32).clamp(1, size);
+    let draw_h = ((height as f32 * scale) as u32).clamp(1, size);
+    let (offset_x, offset_y) = ((size - draw_w) / 2, (size - draw_h) / 2);
+
+    for py in 0..draw_h {
+        // 地形点从左下角开始，图像从左上角开始
+        let ty = height - 1 - ((py as f32 / scale) as u32).min(height - 1);
+        for px in 0..draw_w {
+            let tx = ((px as f32 / scale) as u32).min(width - 1);
+            let point = terrain.point(tx, ty);
+
+            let base = palette
+                .get(point.ground_texture as usize)
+                .copied()
+                .unwrap_or_else(|| ground_color(""));
+            let t = if max_h > min_h {
+                (point.height - min_h) / (max_h - min_h)
+            } else {
+                0.5
+            };
+            let shade = SHADE_MIN + SHADE_RANGE * t;
+            let mut color = base.map(|c| c * shade);
+            if point.flags & POINT_BLIGHT != 0 {
+                color = mix(color, BLIGHT_COLOR, 0.6);
+            }
+            if point.flags & POINT_WATER != 0 && point.water_level > point.height {
+                color = mix(color, WATER_COLOR, 0.8);
+            }
+            if point.flags & POINT_BOUNDARY != 0 {
+                color = mix(color, BOUNDARY_COLOR, 0.7);
+            }
+
+            let [r, g, b] = color.map(|c| c.round().clamp(0.0, 255.0) as u8);
+            img.put_pixel(offset_x + px, offset_y + py, Rgba([r, g, b, 255]));
+        }
+    }
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::w3e_parser::{TerrainHeader, TerrainPoint};
+
+    fn point(flags: u8, water_level: f32) -> TerrainPoint {
+        TerrainPoint {
+            height: 0.0,
+            water_level,
+            flags,
+            ground_texture: 0,
+            cliff_texture: 0,
+            layer_height: 2,
+        }
+    }
+
+    #[test]
+    fn test_render_minimap_centering_and_tints() {
+        // 4x2 的平坦泥土地形：左下角是水，右上角是荒芜，(2, 0) 的水面低于地面
+        let mut points: Vec<TerrainPoint> = (0..8).map(|_| point(0, 0.0)).collect();
+        points[0] = point(POINT_WATER, 50.0);
+        points[2] = point(POINT_WATER, -50.0);
+        points[7] = point(POINT_BLIGHT, 0.0);
+        let terrain = Terrain {
+            header: TerrainHeader {
+                version: 11,
+                tileset: 'L',
+                custom_tilesets: false,
+                ground_tilesets: vec!["Ldrt".to_string()],
+                cliff_tilesets: Vec::new(),
+                width: 4,
+                height: 2,
+                center_offset: [0.0, 0.0],
+            },
+            points,
+        };
+
+        // 每个地形点占 2x2 像素，上下各留 2 行黑边
+        let img = render_minimap(&terrain, 8);
+        let pixel = |x, y| img.get_pixel(x, y).0;
+        for x in 0..8 {
+            for y in [0, 1, 6, 7] {
+                assert_eq!(pixel(x, y), [0, 0, 0, 255]);
+            }
+        }
+        assert_eq!(pixel(2, 4), [110, 85, 60, 255]); // 普通泥土
+        assert_eq!(pixel(4, 5), [110, 85, 60, 255]); // 水面低于地面，不着色
+        assert_eq!(pixel(0, 4), [54, 81, 132, 255]); // 左下角的水
+        assert_eq!(pixel(1, 5), [54, 81, 132, 255]);
+        assert_eq!(pixel(6, 2), [98, 70, 78, 255]); // 右上角的荒芜
+        assert_eq!(pixel(7, 3), [98, 70, 78, 255]);
+
+        // 纵向地图左右留黑
+        let mut tall = terrain.clone();
+        (tall.header.width, tall.header.height) = (2, 4);
+        tall.points = (0..8).map(|_| point(0, 0.0)).collect();
+        let img = render_minimap(&tall, 8);
+        for y in 0..8 {
+            assert_eq!(img.get_pixel(1, y).0, [0, 0, 0, 255]);
+            assert_eq!(img.get_pixel(2, y).0, [110, 85, 60, 255]);
+            assert_eq!(img.get_pixel(6, y).0, [0, 0, 0, 255]);
+        }
+    }
+}
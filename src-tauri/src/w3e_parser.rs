@@ -0,0 +1,229 @@
+// war3map.w3e 地形文件解析器
+// 参考格式: "W3E!" + 版本 11，头部之后为 (width * height) 个地形点
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+
+const W3E_MAGIC: &[u8; 4] = b"W3E!";
+
+/// 地形文件头部
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TerrainHeader {
+    pub version: u32,
+    pub tileset: char,
+    pub custom_tilesets: bool,
+    pub ground_tilesets: Vec<String>, // 地表纹理 ID，如 "Ldrt"
+    pub cliff_tilesets: Vec<String>,  // 悬崖纹理 ID，如 "CLdi"
+    pub width: u32,                   // 地形点数（= 可编辑格子数 + 1）
+    pub height: u32,
+    pub center_offset: [f32; 2],
+}
+
+// 地形点标志位（第 6 字节的高 4 位，0x1 为斜坡）
+pub const POINT_BLIGHT: u8 = 0x2;
+pub const POINT_WATER: u8 = 0x4;
+pub const POINT_BOUNDARY: u8 = 0x8;
+
+// 高度与水面的原始值以 0x2000 为零点，4 个单位对应 1 个游戏单位
+const HEIGHT_ZERO: i32 = 0x2000;
+// 悬崖每层的高度，层高 2 为地面基准
+const LAYER_HEIGHT: f32 = 128.0;
+const WATER_OFFSET: f32 = 89.6;
+
+/// 单个地形点（每点 7 字节）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TerrainPoint {
+    pub height: f32,      // 含悬崖层高的最终地面高度
+    pub water_level: f32, // 水面高度，仅当带 POINT_WATER 标志时有意义
+    pub flags: u8,
+    pub ground_texture: u8, // ground_tilesets 中的索引
+    pub cliff_texture: u8,
+    pub layer_height: u8,
+}
+
+/// 完整地形数据，points 按行优先从左下角开始排列
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Terrain {
+    pub header: TerrainHeader,
+    pub points: Vec<TerrainPoint>,
+}
+
+impl Terrain {
+    pub fn point(&self, x: u32, y: u32) -> &TerrainPoint {
+        &self.points[(y * self.header.width + x) as usize]
+    }
+}
+
+/// 地形主题编号对应的名称
+pub fn tileset_name(tileset: char) -> &'static str {
+    match tileset {
+        'A' => "Ashenvale",
+        'B' => "Barrens",
+        'C' => "Felwood",
+        'D' => "Dungeon",
+        'F' => "Lordaeron Fall",
+        'G' => "Underground",
+        'I' => "Icecrown Glacier",
+        'J' => "Dalaran Ruins",
+        'K' => "Black Citadel",
+        'L' => "Lordaeron Summer",
+        'N' => "Northrend",
+        'O' => "Outland",
+        'Q' => "Village Fall",
+        'V' => "Village",
+        'W' => "Lordaeron Winter",
+        'X' => "Dalaran",
+        'Y' => "Cityscape",
+        'Z' => "Sunken Ruins",
+        _ => "Unknown",
+    }
+}
+
+pub struct W3eParser {
+    cursor: Cursor<Vec<u8>>,
+}
+
+impl W3eParser {
+    pub fn new(data: Vec<u8>) -> Self {
+        W3eParser {
+            cursor: Cursor::new(data),
+        }
+    }
+
+    pub fn parse_header(&mut self) -> Result<TerrainHeader, String> {
+        let magic = self.read_id()?;
+        if magic.as_bytes() != W3E_MAGIC {
+            return Err(format!("Invalid w3e magic: {:?}", magic));
+        }
+
+        let version = self.read_u32()?;
+        let tileset = self.read_u8()? as char;
+        let custom_tilesets = self.read_u32()? != 0;
+
+        let ground_count = self.read_u32()?;
+        let ground_tilesets = (0..ground_count)
+            .map(|_| self.read_id())
+            .collect::<Result<Vec<_>, _>>()?;
+        let cliff_count = self.read_u32()?;
+        let cliff_tilesets = (0..cliff_count)
+            .map(|_| self.read_id())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(TerrainHeader {
+            version,
+            tileset,
+            custom_tilesets,
+            ground_tilesets,
+            cliff_tilesets,
+            width: self.read_u32()?,
+            height: self.read_u32()?,
+            center_offset: [self.read_f32()?, self.read_f32()?],
+        })
+    }
+
+    /// 解析头部及全部地形点
+    pub fn parse(&mut self) -> Result<Terrain, String> {
+        let header = self.parse_header()?;
+        let count = header.width as usize * header.height as usize;
+        let remaining = self.cursor.get_ref().len() as u64 - self.cursor.position();
+        if (count as u64) * 7 > remaining {
+            return Err(format!(
+                "Terrain {}x{} exceeds file size",
+                header.width, header.height
+            ));
+        }
+
+        let points = (0..count)
+            .map(|_| self.parse_point())
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Terrain { header, points })
+    }
+
+    fn parse_point(&mut self) -> Result<TerrainPoint, String> {
+        let ground = self.read_u16()? as i32;
+        let water = (self.read_u16()? & 0x3FFF) as i32;
+        let flags_texture = self.read_u8()?;
+        self.read_u8()?; // 地表纹理细节变化
+        let cliff_layer = self.read_u8()?;
+
+        let layer_height = cliff_layer & 0x0F;
+        Ok(TerrainPoint {
+            height: (ground - HEIGHT_ZERO) as f32 / 4.0
+                + (layer_height as f32 - 2.0) * LAYER_HEIGHT,
+            water_level: (water - HEIGHT_ZERO) as f32 / 4.0 - WATER_OFFSET,
+            flags: flags_texture >> 4,
+            ground_texture: flags_texture & 0x0F,
+            cliff_texture: cliff_layer >> 4,
+            layer_height,
+        })
+    }
+
+    /// 读取 4 字节 ID
+    fn read_id(&mut self) -> Result<String, String> {
+        let mut id = [0u8; 4];
+        self.cursor
+            .read_exact(&mut id)
+            .map_err(|e| format!("Failed to read id: {}", e))?;
+        Ok(String::from_utf8_lossy(&id).to_string())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        self.cursor
+            .read_u8()
+            .map_err(|e| format!("Failed to read u8: {}", e))
+    }
+
+    fn read_u16(&mut self) -> Result<u16, String> {
+        self.cursor
+            .read_u16::<LittleEndian>()
+            .map_err(|e| format!("Failed to read u16: {}", e))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        self.cursor
+            .read_u32::<LittleEndian>()
+            .map_err(|e| format!("Failed to read u32: {}", e))
+    }
+
+    fn read_f32(&mut self) -> Result<f32, String> {
+        self.cursor
+            .read_f32::<LittleEndian>()
+            .map_err(|e| format!("Failed to read f32: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_terrain_points() {
+        let mut data = b"W3E!".to_vec();
+        data.extend_from_slice(&11u32.to_le_bytes());
+        data.push(b'L');
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&1u32.to_le_bytes());
+        data.extend_from_slice(b"Ldrt");
+        data.extend_from_slice(&0u32.to_le_bytes());
+        for v in [2u32, 1] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [-128.0f32, -128.0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        // 地面点: 高度 0，层高 2
+        data.extend_from_slice(&[0x00, 0x20, 0x00, 0x20, 0x00, 0x00, 0x02]);
+        // 水面点: 高度 +16，层高 3，带水面与荒芜标志，纹理 1
+        data.extend_from_slice(&[0x40, 0x20, 0x00, 0x20, 0x61, 0x00, 0x13]);
+
+        let terrain = W3eParser::new(data).parse().unwrap();
+        assert_eq!(terrain.points.len(), 2);
+        assert_eq!(terrain.point(0, 0).height, 0.0);
+        let water = terrain.point(1, 0);
+        assert_eq!(water.height, 16.0 + LAYER_HEIGHT);
+        assert_eq!(water.flags, POINT_WATER | POINT_BLIGHT);
+        assert_eq!((water.ground_texture, water.cliff_texture), (1, 1));
+        assert_eq!(water.water_level, -WATER_OFFSET);
+    }
+}
@@ -0,0 +1,310 @@
+// war3map.w3i 地图信息解析器
+// 参考格式: RoC 为版本 18，TFT 为版本 25，重制版为 28 及以上
+
+use byteorder::{LittleEndian, ReadBytesExt};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, Cursor};
+
+/// 地图信息（w3i 头部）
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct MapInfo {
+    pub format_version: u32,
+    pub save_count: u32,
+    pub editor_version: u32,
+    pub game_version: Option<[u32; 4]>, // 仅版本 27 及以上 (major, minor, patch, build)
+    pub name: String,
+    pub author: String,
+    pub description: String,
+    pub suggested_players: String,
+    pub camera_bounds: [f32; 8],      // 四个角点: 左下, 右上, 左上, 右下
+    pub camera_complements: [u32; 4], // 镜头边界外的格子数: 左, 右, 下, 上
+    pub playable_width: u32,
+    pub playable_height: u32,
+    pub flags: u32,
+    pub main_ground_type: char,
+    pub players: Vec<PlayerSlot>,
+    pub forces: Vec<Force>,
+}
+
+/// 玩家槽位
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlayerSlot {
+    pub id: u32,
+    pub controller: u32, // 1 玩家, 2 电脑, 3 中立, 4 可营救
+    pub race: u32,       // 1 人族, 2 兽族, 3 不死族, 4 暗夜精灵
+    pub fixed_start_position: bool,
+    pub name: String,
+    pub start_position: [f32; 2],
+}
+
+// 队伍标志位
+const FORCE_ALLIED: u32 = 0x01;
+const FORCE_ALLIED_VICTORY: u32 = 0x02;
+const FORCE_SHARED_VISION: u32 = 0x04;
+const FORCE_SHARED_CONTROL: u32 = 0x10;
+
+/// 队伍定义
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Force {
+    pub name: String,
+    pub flags: u32,
+    pub allied: bool,
+    pub allied_victory: bool,
+    pub shared_vision: bool,
+    pub shared_control: bool,
+    pub players: Vec<u32>, // 成员玩家的 id
+}
+
+impl MapInfo {
+    /// 镜头可视范围 [min_x, min_y, max_x, max_y]
+    pub fn camera_rect(&self) -> [f32; 4] {
+        let xs = self.camera_bounds.iter().step_by(2);
+        let ys = self.camera_bounds.iter().skip(1).step_by(2);
+        let (min_x, max_x) = xs.fold((f32::MAX, f32::MIN), |(lo, hi), &x| (lo.min(x), hi.max(x)));
+        let (min_y, max_y) = ys.fold((f32::MAX, f32::MIN), |(lo, hi), &y| (lo.min(y), hi.max(y)));
+        [min_x, min_y, max_x, max_y]
+    }
+}
+
+/// 读取该 w3i 格式版本所需的最低游戏版本 (major, minor)
+pub fn required_game_version(format_version: u32) -> (u32, u32) {
+    match format_version {
+        31.. => (1, 32), // 敌对优先级
+        28.. => (1, 31), // 脚本语言选项
+        26.. => (1, 29), // 24 玩家
+        25 => (1, 7),    // 冰封王座
+        _ => (1, 0),
+    }
+}
+
+pub struct W3iParser {
+    cursor: Cursor<Vec<u8>>,
+}
+
+impl W3iParser {
+    pub fn new(data: Vec<u8>) -> Self {
+        W3iParser {
+            cursor: Cursor::new(data),
+        }
+    }
+
+    pub fn parse(&mut self) -> Result<MapInfo, String> {
+        let mut info = MapInfo {
+            format_version: self.read_u32()?,
+            ..Default::default()
+        };
+
+        if info.format_version >= 16 {
+            info.save_count = self.read_u32()?;
+            info.editor_version = self.read_u32()?;
+        }
+        if info.format_version >= 27 {
+            // 重制版额外记录保存时的游戏版本
+            info.game_version = Some([
+                self.read_u32()?,
+                self.read_u32()?,
+                self.read_u32()?,
+                self.read_u32()?,
+            ]);
+        }
+
+        info.name = self.read_string()?;
+        info.author = self.read_string()?;
+        info.description = self.read_string()?;
+        info.suggested_players = self.read_string()?;
+
+        for value in info.camera_bounds.iter_mut() {
+            *value = self.read_f32()?;
+        }
+        for value in info.camera_complements.iter_mut() {
+            *value = self.read_u32()?;
+        }
+
+        info.playable_width = self.read_u32()?;
+        info.playable_height = self.read_u32()?;
+        info.flags = self.read_u32()?;
+        info.main_ground_type = self.read_u8()? as char;
+
+        if info.format_version >= 25 {
+            self.skip_tft_presentation(info.format_version)?;
+        } else {
+            self.skip_roc_presentation()?;
+        }
+
+        let player_count = self.read_u32()?;
+        for _ in 0..player_count {
+            let player = self.parse_player(info.format_version)?;
+            info.players.push(player);
+        }
+
+        let force_count = self.read_u32()?;
+        for _ in 0..force_count {
+            let flags = self.read_u32()?;
+            let mask = self.read_u32()?;
+            info.forces.push(Force {
+                name: self.read_string()?,
+                flags,
+                allied: flags & FORCE_ALLIED != 0,
+                allied_victory: flags & FORCE_ALLIED_VICTORY != 0,
+                shared_vision: flags & FORCE_SHARED_VISION != 0,
+                shared_control: flags & FORCE_SHARED_CONTROL != 0,
+                // 编辑器会把未使用的位也置 1，只保留实际存在的玩家
+                players: info
+                    .players
+                    .iter()
+                    .map(|p| p.id)
+                    .filter(|&id| id < 32 && mask & (1 << id) != 0)
+                    .collect(),
+            });
+        }
+
+        Ok(info)
+    }
+
+    /// 跳过 TFT 及之后版本的载入画面、序幕、雾效与环境设置
+    fn skip_tft_presentation(&mut self, format_version: u32) -> Result<(), String> {
+        self.read_u32()?; // 载入画面背景编号
+        for _ in 0..4 {
+            self.read_string()?; // 载入画面模型、文本、标题、副标题
+        }
+        self.read_u32()?; // 使用的游戏数据设置
+        for _ in 0..4 {
+            self.read_string()?; // 序幕画面路径、文本、标题、副标题
+        }
+        for _ in 0..4 {
+            self.read_u32()?; // 雾效类型、起点、终点、密度
+        }
+        self.read_u32()?; // 雾颜色 RGBA
+        self.read_u32()?; // 天气效果 ID
+        self.read_string()?; // 自定义音效环境
+        self.read_u8()?; // 自定义光照环境
+        self.read_u32()?; // 水面颜色 RGBA
+
+        if format_version >= 28 {
+            self.read_u32()?; // 脚本语言
+        }
+        if format_version >= 29 {
+            self.read_u32()?; // 支持的画质模式
+            self.read_u32()?; // 游戏数据版本
+        }
+        Ok(())
+    }
+
+    /// 跳过 RoC 版本的战役背景、载入画面与序幕
+    fn skip_roc_presentation(&mut self) -> Result<(), String> {
+        self.read_u32()?; // 战役背景编号
+        for _ in 0..3 {
+            self.read_string()?; // 载入画面文本、标题、副标题
+        }
+        self.read_u32()?; // 载入画面编号
+        for _ in 0..3 {
+            self.read_string()?; // 序幕文本、标题、副标题
+        }
+        Ok(())
+    }
+
+    fn parse_player(&mut self, format_version: u32) -> Result<PlayerSlot, String> {
+        let player = PlayerSlot {
+            id: self.read_u32()?,
+            controller: self.read_u32()?,
+            race: self.read_u32()?,
+            fixed_start_position: self.read_u32()? != 0,
+            name: self.read_string()?,
+            start_position: [self.read_f32()?, self.read_f32()?],
+        };
+        self.read_u32()?; // 同盟优先级（低）
+        self.read_u32()?; // 同盟优先级（高）
+        if format_version >= 31 {
+            self.read_u32()?; // 敌对优先级（低）
+            self.read_u32()?; // 敌对优先级（高）
+        }
+        Ok(player)
+    }
+
+    /// 读取以 null 结尾的字符串
+    fn read_string(&mut self) -> Result<String, String> {
+        let mut bytes = Vec::new();
+        self.cursor
+            .read_until(0, &mut bytes)
+            .map_err(|e| format!("Failed to read string: {}", e))?;
+        if bytes.pop() != Some(0) {
+            return Err("Unterminated string in w3i".to_string());
+        }
+        Ok(String::from_utf8_lossy(&bytes).to_string())
+    }
+
+    fn read_u8(&mut self) -> Result<u8, String> {
+        self.cursor
+            .read_u8()
+            .map_err(|e| format!("Failed to read u8: {}", e))
+    }
+
+    fn read_u32(&mut self) -> Result<u32, String> {
+        self.cursor
+            .read_u32::<LittleEndian>()
+            .map_err(|e| format!("Failed to read u32: {}", e))
+    }
+
+    fn read_f32(&mut self) -> Result<f32, String> {
+        self.cursor
+            .read_f32::<LittleEndian>()
+            .map_err(|e| format!("Failed to read f32: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tft_map_info() {
+        let mut data = Vec::new();
+        for v in [25u32, 3, 6059] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        for s in ["TRIGSTR_001", "Author", "Desc", "1-2"] {
+            data.extend_from_slice(s.as_bytes());
+            data.push(0);
+        }
+        for v in [-2816.0f32, -3328.0, 2816.0, 2560.0, -2816.0, 2560.0, 2816.0, -3328.0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [6u32, 6, 4, 8, 76, 60, 0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.push(b'L');
+        // TFT 载入画面、序幕、雾效与环境
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&[0; 4]);
+        data.extend_from_slice(&[0; 4 * 4 + 4 + 4]);
+        data.push(0);
+        data.push(0);
+        data.extend_from_slice(&[0; 4]);
+        // 一个玩家、一个队伍
+        for v in [1u32, 0, 1, 1, 1] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.extend_from_slice(b"Player 1\0");
+        for v in [0.0f32, 0.0] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        for v in [0u32, 0, 1, 0x05, 0b101] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        data.extend_from_slice(b"Team 1\0");
+
+        let info = W3iParser::new(data).parse().unwrap();
+        assert_eq!(info.name, "TRIGSTR_001");
+        assert_eq!(info.camera_rect(), [-2816.0, -3328.0, 2816.0, 2560.0]);
+        assert_eq!(info.camera_complements, [6, 6, 4, 8]);
+        assert_eq!((info.playable_width, info.playable_height), (76, 60));
+        assert_eq!(info.main_ground_type, 'L');
+        assert_eq!(info.players.len(), 1);
+        assert_eq!(info.players[0].name, "Player 1");
+        assert_eq!(info.forces.len(), 1);
+        assert!(info.forces[0].allied && info.forces[0].shared_vision);
+        assert_eq!(info.forces[0].players, vec![0]);
+    }
+}
@@ -6,6 +6,7 @@ import { detectKKWE, launchMapWithKKWE, type KKWEInfo } from '../utils/kkweDetec
 import { getHotReloadExporter, DEFAULT_HOT_RELOAD_CONFIG, type HotReloadConfig } from '../utils/hotReloadExporter';
 import { useProjectStore } from '../store/projectStore';
 import { war3ProcessManager } from '../utils/war3ProcessManager';
+import { errorMessage } from '../utils/rustBridge';
 import './HotReloadPanel.css';
 
 interface HotReloadPanelProps {
@@ -125,7 +126,7 @@ export const HotReloadPanel: React.FC<HotReloadPanelProps> = ({ onClose }) => {
       }
     } catch (error) {
       console.error('检测 KKWE 失败:', error);
-      showMessage('error', `检测失败: ${error}`);
+      showMessage('error', `检测失败: ${errorMessage(error)}`);
     } finally {
       setIsChecking(false);
     }
@@ -232,7 +233,7 @@ export const HotReloadPanel: React.FC<HotReloadPanelProps> = ({ onClose }) => {
       console.log('[热重载] War3.exe 启动成功, PID=', war3Pid);
     } catch (error) {
       console.error('初始化或启动失败:', error);
-      showMessage('error', `操作失败: ${error}`);
+      showMessage('error', `操作失败: ${errorMessage(error)}`);
     }
   };
   
@@ -3,7 +3,7 @@ import { vec3, mat4, quat } from 'gl-matrix';
 import { join } from '@tauri-apps/api/path';
 import { exists, readFile } from '@tauri-apps/plugin-fs';
 import { mpqManager } from '../utils/mpqManager';
-import { decodeBLPToRGBA, blpImageDataToImageData } from '../utils/rustBridge';
+import { decodeBLPToRGBA, blpImageDataToImageData, errorMessage } from '../utils/rustBridge';
 // @ts-ignore - war3-model 是 TypeScript 源码，没有类型定义
 import { parseMDX, ModelRenderer } from 'war3-model';
 
@@ -350,7 +350,7 @@ export const ModelViewer: React.FC<ModelViewerProps> = ({
 
       } catch (err) {
         console.error('❌ 模型加载失败:', err);
-        setError(err instanceof Error ? err.message : String(err));
+        setError(errorMessage(err));
       }
     };
 
@@ -17,6 +17,7 @@ import { RaceSwitcher } from './RaceSwitcher';
 import { detectKKWE, launchMapWithKKWE, type KKWEInfo } from '../utils/kkweDetector';
 import { getHotReloadExporter } from '../utils/hotReloadExporter';
 import { war3ProcessManager } from '../utils/war3ProcessManager';
+import { errorMessage } from '../utils/rustBridge';
 import {
   NewFileIcon, OpenFileIcon, SaveIcon,
   UndoIcon, RedoIcon,
@@ -232,7 +233,7 @@ export const Toolbar: React.FC<ToolbarProps> = ({ currentFilePath, setCurrentFil
       // 启动成功后不显示提示,让用户专注游戏
     } catch (error) {
       console.error('启动War3失败:', error);
-      showAlert({ title: '错误', message: '启动失败: ' + error, type: 'danger' });
+      showAlert({ title: '错误', message: '启动失败: ' + errorMessage(error), type: 'danger' });
     } finally {
       setIsLaunching(false);
     }
@@ -1,6 +1,7 @@
 // KKWE 检测器 - 检测 KKWE 安装并提供启动功能
 
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
 import { exists } from '@tauri-apps/plugin-fs';
 import { mpqManager } from './mpqManager';
 
@@ -46,6 +47,18 @@ export async function detectKKWE(): Promise<KKWEInfo> {
   }
 }
 
+/**
+ * 查找可能的 War3 安装目录（环境变量 WARCRAFT3_PATH、注册表、常见安装位置），找不到时返回空数组
+ */
+export async function detectWar3Paths(): Promise<string[]> {
+  try {
+    return await invoke<string[]>('detect_war3_path');
+  } catch (error) {
+    console.error('[War3] 检测安装目录失败:', error);
+    return [];
+  }
+}
+
 /**
  * 使用 KKWE 启动地图
  * @param mapPath 地图文件的绝对路径
@@ -73,6 +86,24 @@ export async function launchMapWithKKWE(
   return pid;
 }
 
+/**
+ * 使用任意启动器启动地图：args 中的 mapArgPlaceholder 会被替换为地图路径，
+ * 未指定或未出现时地图路径追加到参数末尾。返回启动器进程的 PID
+ */
+export async function launchMap(
+  launcherPath: string,
+  args: string[],
+  mapPath: string,
+  mapArgPlaceholder?: string,
+): Promise<number> {
+  return invoke<number>('launch_map', {
+    launcherPath,
+    args,
+    mapPath,
+    mapArgPlaceholder: mapArgPlaceholder ?? null,
+  });
+}
+
 /**
  * 检查进程是否正在运行
  */
@@ -85,6 +116,25 @@ export async function isProcessRunning(pid: number): Promise<boolean> {
   }
 }
 
+/**
+ * 监视进程退出（由后端轮询并发送 war3-exited 事件），返回取消监听的函数
+ */
+export async function watchProcess(pid: number, onExit: () => void): Promise<UnlistenFn> {
+  const unlisten = await listen<number>('war3-exited', (event) => {
+    if (event.payload === pid) {
+      unlisten();
+      onExit();
+    }
+  });
+  try {
+    await invoke('watch_process', { pid });
+  } catch (error) {
+    unlisten();
+    throw error;
+  }
+  return unlisten;
+}
+
 /**
  * 结束指定进程
  */
@@ -852,24 +852,34 @@ end`;
     const uiGeneratedPath = '.\\\UI-Designer\\\\ui_generated.lua';
     
     return `--===========================================================================
+-- 加载 UI 内容脚本
+--===========================================================================
+local function LoadUIContent()
+    if io and io.open then
+        local f = io.open([[${uiGeneratedPath}]], 'r')
+        local content = f:read('*a')
+        f:close()
+        load(content)()
+    else
+        -- 重制版没有 io 库，Lua 模式下 Preloader 会执行 CustomMapData 下的文件
+        Preloader("UI-Designer\\\\ui_generated.lua")
+    end
+end
+
+--===========================================================================
 -- 重载 UI
 --===========================================================================
 local function ReloadUI()
-    local startTime = os.clock()
+    local startTime = os and os.clock and os.clock() or 0
     
     -- 清理旧 UI
     CleanupAllFrames()
     
     -- 重新加载 UI 内容脚本
-    local success, err = pcall(function()
-        local f = io.open([[${uiGeneratedPath}]], 'r')
-        local content = f:read('*a')
-        f:close()
-        load(content)()
-    end)
+    local success, err = pcall(LoadUIContent)
     
     if success then
-        local elapsed = (os.clock() - startTime) * 1000
+        local elapsed = ((os and os.clock and os.clock() or 0) - startTime) * 1000
         print(string.format("|cff00ff00[UI Designer]|r UI已重载 (%.1fms)", elapsed))
     else
         print("|cffff0000[UI Designer]|r UI重载失败:")
@@ -885,8 +895,6 @@ end`;
    * 生成初始化函数
    */
   private generateInitFunction(): string {
-    const uiGeneratedPath = '.\\\\UI-Designer\\\\ui_generated.lua';
-    
     return `--===========================================================================
 -- 初始化
 --===========================================================================
@@ -903,12 +911,7 @@ print("|cff00ff00[UI Designer]|r 命令: -reload 或 -rl  刷新UI")
 _G.UI_Designer_ReloadUI = ReloadUI
 
 -- 首次加载 UI
-local success, err = pcall(function()
-    local f = io.open([[${uiGeneratedPath}]], 'r')
-    local content = f:read('*a')
-    f:close()
-    load(content)()
-end)
+local success, err = pcall(LoadUIContent)
 
 if success then
     print("|cff00ff00[UI Designer]|r UI加载成功")
@@ -11,11 +11,14 @@ import { open } from '@tauri-apps/plugin-dialog';
 import { invoke } from '@tauri-apps/api/core';
 import { join } from '@tauri-apps/api/path';
 import { exists } from '@tauri-apps/plugin-fs';
+import type { AppError } from './rustBridge';
 
 // MPQ 文件信息接口
 interface MpqFileInfo {
   name: string;
   size: number;
+  locale: number;   // Windows LANGID，0 为默认语言
+  platform: number;
 }
 
 /**
@@ -179,7 +182,7 @@ export class MPQManager {
       console.log(`[MPQManager]   调用 Rust 后端加载 MPQ...`);
       
       // 使用 Tauri 后端加载 MPQ 档案
-      const files = await invoke<MpqFileInfo[]>('load_mpq_archive', { path: mpqPath });
+      const files = await invoke<MpqFileInfo[]>('load_mpq_archive', { path: mpqPath, force: false });
       const fileCount = files.length;
       console.log(`[MPQManager]   找到 ${fileCount} 个文件`);
       
@@ -301,6 +304,22 @@ export class MPQManager {
       return null;
   }
   
+  /**
+   * 从指定档案批量读取文件（只打开一次档案），读取失败的文件对应 null
+   */
+  async readFilesFrom(mpqName: string, filePaths: string[]): Promise<(ArrayBuffer | null)[]> {
+    const archiveInfo = this.archives.get(mpqName);
+    if (!archiveInfo?.loaded) {
+      return filePaths.map(() => null);
+    }
+
+    const results = await invoke<({ Ok: number[] } | { Err: AppError })[]>('read_mpq_files', {
+      archivePath: archiveInfo.path,
+      names: filePaths.map(path => path.replace(/\//g, '\\')),
+    });
+    return results.map(result => ('Ok' in result ? new Uint8Array(result.Ok).buffer : null));
+  }
+
   /**
    * 检查文件是否存在
    */
@@ -316,6 +335,31 @@ export class MPQManager {
     const buffer = await this.readFile(filePath);
     return buffer !== null;
   }
+
+  /**
+   * 把文件写入已加载的档案（同时更新档案的 listfile）
+   * @param replace 为 false 时档案中已有同名文件会报错
+   */
+  async writeFile(mpqName: string, filePath: string, data: Uint8Array, replace: boolean): Promise<void> {
+    const archiveInfo = this.archives.get(mpqName);
+    if (!archiveInfo?.loaded) {
+      throw new Error(`档案未加载: ${mpqName}`);
+    }
+
+    const normalizedPath = filePath.replace(/\//g, '\\');
+    await invoke('write_mpq_file', {
+      archivePath: archiveInfo.path,
+      fileName: normalizedPath,
+      data: Array.from(data),
+      replace,
+    });
+
+    this.fileListCache.set(normalizedPath.toLowerCase(), {
+      fileName: normalizedPath,
+      archiveName: mpqName,
+      size: data.length,
+    });
+  }
   
   /**
    * 搜索文件 (支持通配符)
@@ -521,6 +565,13 @@ export class MPQManager {
       info.files = [];
       info.loaded = false;
       
+      // 关闭 Rust 后端缓存的档案句柄
+      if (info.path) {
+        invoke('close_mpq_archive', { path: info.path }).catch(err => {
+          console.warn('[MPQManager] 关闭后端档案句柄失败:', err);
+        });
+      }
+      
       console.log(`[MPQManager] ${mpqName} 已卸载`);
     }
   }
@@ -633,3 +684,41 @@ export async function initializeMPQ(war3Path?: string): Promise<boolean> {
 export async function readWC3File(filePath: string): Promise<ArrayBuffer | null> {
   return mpqManager.readFile(filePath);
 }
+
+/**
+ * 快捷函数: 用磁盘上的文件打包新的 MPQ 档案
+ * @param files [档案内路径, 源文件路径] 列表
+ * @param version MPQ 格式版本，1（默认，War3 使用）或 2
+ */
+export async function createMPQ(
+  outputPath: string,
+  files: [string, string][],
+  version: 1 | 2 = 1
+): Promise<void> {
+  await invoke('create_mpq', { outputPath, files, version });
+}
+
+/**
+ * 档案链中的文件及提供它的档案
+ */
+export interface MpqChainFileInfo {
+  name: string;
+  size: number;
+  archive: string;
+}
+
+/**
+ * 快捷函数: 按游戏加载顺序在多个档案中读取文件
+ * @param archivePaths 按加载顺序排列（如 War3.mpq ... War3Patch.mpq），靠后的档案优先
+ */
+export async function readFromMPQChain(archivePaths: string[], fileName: string): Promise<ArrayBuffer> {
+  const data = await invoke<number[]>('read_from_mpq_chain', { archivePaths, fileName });
+  return new Uint8Array(data).buffer;
+}
+
+/**
+ * 快捷函数: 合并多个档案的文件列表，同名文件只保留靠后档案中的版本
+ */
+export async function listMPQChain(archivePaths: string[]): Promise<MpqChainFileInfo[]> {
+  return invoke<MpqChainFileInfo[]>('list_mpq_chain', { archivePaths });
+}
@@ -1,4 +1,46 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen, type UnlistenFn } from '@tauri-apps/api/event';
+
+/**
+ * 后端命令返回的结构化错误，按 kind 区分错误类型
+ */
+export interface AppError {
+  kind:
+    | 'Io'
+    | 'MpqOpen'
+    | 'MpqRead'
+    | 'BlpDecode'
+    | 'BlpEncode'
+    | 'MdxParse'
+    | 'MapParse'
+    | 'ProcessSpawn'
+    | 'UserCancelled'
+    | 'Unsupported'
+    | 'Other';
+  message: string;
+}
+
+/**
+ * 判断 invoke 抛出的错误是否为后端的 AppError
+ */
+export function isAppError(error: unknown): error is AppError {
+  return (
+    typeof error === 'object' &&
+    error !== null &&
+    typeof (error as AppError).kind === 'string' &&
+    typeof (error as AppError).message === 'string'
+  );
+}
+
+/**
+ * 取出任意错误的提示文本（AppError、Error 或其他值）
+ */
+export function errorMessage(error: unknown): string {
+  if (isAppError(error) || error instanceof Error) {
+    return error.message;
+  }
+  return String(error);
+}
 
 /**
  * BLP 图像数据（RGBA 格式）
@@ -16,7 +58,10 @@ export interface BlpInfo {
   width: number;
   height: number;
   mipmap_count: number;
-  format: string; // "JPEG" | "Paletted" | "DXT1/DXT3/DXT5"
+  format: string; // "JPEG" | "Paletted" | "DXT1" | "DXT3" | "DXT5" | "BGRA" | "Unknown"
+  has_alpha: boolean;
+  alpha_bits: number;
+  mipmap_dimensions: [number, number][]; // 文件中实际存储的各级 [宽, 高]
 }
 
 /**
@@ -61,18 +106,298 @@ export interface MdxBoundingBox {
 }
 
 /**
- * MDX 模型数据
+ * MDX 图元分组（只有三角形列表分组生成 faces）
  */
-export interface MdxModel {
-  version: number;
-  name: string;
+export interface MdxPrimitiveGroup {
+  primitive_type: number; // 4 = 三角形列表
+  index_count: number;
+  first_face: number;
+  face_count: number;
+}
+
+/**
+ * MDX 几何体（面索引相对于本 geoset 的顶点）
+ */
+export interface MdxGeoset {
   vertices: MdxVertex[];
   normals: MdxNormal[];
-  uvs: MdxUV[];
+  uvs: MdxUV[][]; // 每层一组 UV
   faces: MdxFace[];
+  primitive_groups: MdxPrimitiveGroup[];
+  vertex_groups: number[];
+  matrix_groups: number[];
+  matrix_indices: number[];
+  skin: number[][];
+  material_id: number;
+  selection_group: number;
+  selection_flags: number;
   bounds: MdxBoundingBox;
 }
 
+/**
+ * MDX 动画序列
+ */
+export interface MdxSequence {
+  name: string;
+  interval: [number, number];
+  move_speed: number;
+  non_looping: boolean;
+  rarity: number;
+  sync_point: number;
+  bounds_radius: number;
+  minimum_extent: MdxVertex;
+  maximum_extent: MdxVertex;
+}
+
+/**
+ * MDX 纹理
+ */
+export interface MdxTexture {
+  replaceable_id: number;
+  path: string;
+  flags: number;
+  resolved_path: string | null; // 可替换纹理对应的标准 BLP（按红色队伍解析），普通纹理为 null
+}
+
+/**
+ * MDX 关键帧轨道
+ */
+export interface MdxKeyframe<T> {
+  frame: number;
+  value: T;
+  in_tan: T | null;
+  out_tan: T | null;
+}
+
+export interface MdxTrack<T> {
+  interpolation: number;
+  global_sequence_id: number;
+  keys: MdxKeyframe<T>[];
+}
+
+/**
+ * MDX 节点 flags 的解码结果（类型标志均为 false 时为辅助体）
+ */
+export interface MdxNodeFlags {
+  dont_inherit_translation: boolean;
+  dont_inherit_rotation: boolean;
+  dont_inherit_scaling: boolean;
+  billboarded: boolean;
+  billboarded_lock_x: boolean;
+  billboarded_lock_y: boolean;
+  billboarded_lock_z: boolean;
+  camera_anchored: boolean;
+  bone: boolean;
+  light: boolean;
+  event_object: boolean;
+  attachment: boolean;
+  particle_emitter: boolean;
+  collision_shape: boolean;
+  ribbon_emitter: boolean;
+}
+
+/**
+ * MDX 节点公共头（骨骼、辅助体、附着点等），变换轨道为 KGTR/KGRT/KGSC
+ */
+export interface MdxNode {
+  name: string;
+  object_id: number;
+  parent_id: number; // 0xFFFFFFFF 表示无父节点
+  flags: number;
+  decoded_flags: MdxNodeFlags; // 只读，写回 MDX 时以 flags 为准
+  translation: MdxTrack<[number, number, number]> | null;
+  rotation: MdxTrack<[number, number, number, number]> | null; // 四元数 (x, y, z, w)
+  scaling: MdxTrack<[number, number, number]> | null;
+}
+
+/**
+ * MDX 骨骼
+ */
+export interface MdxBone {
+  node: MdxNode;
+  geoset_id: number;
+  geoset_animation_id: number;
+}
+
+/**
+ * MDX 几何体动画（GEOA）
+ */
+export interface MdxGeosetAnimation {
+  alpha: number;
+  flags: number;
+  color: [number, number, number];
+  geoset_id: number;
+  alpha_track: MdxTrack<[number]> | null;
+  color_track: MdxTrack<[number, number, number]> | null;
+}
+
+/**
+ * MDX 材质层
+ */
+export interface MdxLayer {
+  filter_mode: number;
+  shading_flags: number;
+  texture_id: number;
+  texture_animation_id: number;
+  coord_id: number;
+  alpha: number;
+  alpha_track: MdxTrack<[number]> | null;
+  texture_track: MdxTrack<number> | null;
+  emissive_gain: number | null; // v900+
+  fresnel_color: [number, number, number] | null; // v1000+，以下同
+  fresnel_opacity: number | null;
+  fresnel_team_color: number | null;
+}
+
+/**
+ * MDX 材质
+ */
+export interface MdxMaterial {
+  priority_plane: number;
+  flags: number;
+  shader: string;
+  layers: MdxLayer[];
+}
+
+/**
+ * MDX 粒子发射器（PREM，发射模型）
+ */
+export interface MdxParticleEmitter {
+  node: MdxNode;
+  emission_rate: number;
+  gravity: number;
+  longitude: number;
+  latitude: number;
+  path: string;
+  life_span: number;
+  initial_velocity: number;
+}
+
+/**
+ * MDX 粒子发射器 v2（PRE2，贴图粒子）
+ */
+export interface MdxParticleEmitter2 {
+  node: MdxNode;
+  speed: number;
+  variation: number;
+  latitude: number;
+  gravity: number;
+  life_span: number;
+  emission_rate: number;
+  width: number;
+  length: number;
+  filter_mode: number;
+  rows: number;
+  columns: number;
+  head_or_tail: number; // 0 头部, 1 尾部, 2 两者
+  tail_length: number;
+  time: number;
+  segment_color: [number, number, number][]; // 起始/中段/结束，BGR
+  segment_alpha: [number, number, number];
+  segment_scaling: [number, number, number];
+  head_intervals: [number, number, number][];
+  tail_intervals: [number, number, number][];
+  texture_id: number;
+  squirt: number;
+  priority_plane: number;
+  replaceable_id: number;
+  speed_track: MdxTrack<[number]> | null; // KP2S
+  variation_track: MdxTrack<[number]> | null; // KP2R
+  latitude_track: MdxTrack<[number]> | null; // KP2L
+  gravity_track: MdxTrack<[number]> | null; // KP2G
+  emission_rate_track: MdxTrack<[number]> | null; // KP2E
+  length_track: MdxTrack<[number]> | null; // KP2N
+  width_track: MdxTrack<[number]> | null; // KP2W
+  visibility_track: MdxTrack<[number]> | null; // KP2V
+}
+
+/**
+ * MDX 灯光（LITE）
+ */
+export interface MdxLight {
+  node: MdxNode;
+  light_type: number; // 0 点光源, 1 平行光, 2 环境光
+  attenuation_start: number;
+  attenuation_end: number;
+  color: [number, number, number]; // BGR
+  intensity: number;
+  ambient_color: [number, number, number]; // BGR
+  ambient_intensity: number;
+}
+
+/**
+ * MDX 镜头（CAMS）
+ */
+export interface MdxCamera {
+  name: string;
+  position: MdxVertex;
+  field_of_view: number; // 弧度
+  far_clip: number;
+  near_clip: number;
+  target: MdxVertex;
+  translation: MdxTrack<[number, number, number]> | null;
+  rotation: MdxTrack<[number]> | null;
+  target_translation: MdxTrack<[number, number, number]> | null;
+}
+
+/**
+ * MDX 模型数据
+ */
+export interface MdxModel {
+  version: number;
+  name: string;
+  sequences: MdxSequence[];
+  global_sequences: number[];
+  textures: MdxTexture[];
+  materials: MdxMaterial[];
+  geosets: MdxGeoset[];
+  geoset_animations: MdxGeosetAnimation[];
+  bones: MdxBone[];
+  helpers: MdxNode[];
+  pivot_points: MdxVertex[];
+  particle_emitters: MdxParticleEmitter[];
+  particle_emitters2: MdxParticleEmitter2[];
+  cameras: MdxCamera[];
+  lights: MdxLight[];
+  warnings: string[]; // 因截断或损坏而跳过的 chunk
+  overall_bounds: MdxBoundingBox; // 所有 geoset 的合并包围盒，没有顶点时取 MODL 中的 extent
+  overall_bounds_radius: number;
+}
+
+/**
+ * FDF 锚点（SetPoint）
+ */
+export interface FdfAnchor {
+  point: string;
+  relative_to: string;
+  relative_point: string;
+  x: number;
+  y: number;
+}
+
+/**
+ * FDF 属性值：word 为不带引号的标识符/数字，string 为带引号的字符串
+ */
+export interface FdfValue {
+  kind: 'word' | 'string';
+  value: string;
+}
+
+/**
+ * FDF 框体定义
+ */
+export interface FdfFrameDef {
+  frame_type: string;
+  name: string;
+  inherits: string | null;
+  inherit_children: boolean;
+  width: number | null;
+  height: number | null;
+  anchors: FdfAnchor[];
+  properties: { name: string; values: FdfValue[] }[];
+  children: FdfFrameDef[];
+}
+
 /**
  * 解码 BLP 文件为 PNG base64（直接用于 <img> 标签）
  */
@@ -82,6 +407,20 @@ export async function decodeBLPToPNG(blpData: Uint8Array): Promise<string> {
   });
 }
 
+/**
+ * 将 BLP 转换为其他图像格式的文件数据
+ */
+export async function convertBLP(
+  blpData: Uint8Array,
+  format: 'png' | 'jpeg' | 'tga' | 'webp' | 'bmp',
+): Promise<Uint8Array> {
+  const bytes = await invoke<number[]>('convert_blp', {
+    blpData: Array.from(blpData),
+    format,
+  });
+  return new Uint8Array(bytes);
+}
+
 /**
  * 解码 BLP 文件为 RGBA 数据
  */
@@ -91,6 +430,23 @@ export async function decodeBLPToRGBA(blpData: Uint8Array): Promise<BlpImageData
   });
 }
 
+/**
+ * 解码 BLP 为 WebGL 纹理数据
+ * @param flipY 翻转为左下角原点
+ * @param premultiplyAlpha 颜色预乘 alpha
+ */
+export async function decodeBLPEx(
+  blpData: Uint8Array,
+  flipY: boolean,
+  premultiplyAlpha: boolean
+): Promise<BlpImageData> {
+  return invoke<BlpImageData>('decode_blp_ex', {
+    blpData: Array.from(blpData),
+    flipY,
+    premultiplyAlpha,
+  });
+}
+
 /**
  * 获取 BLP 文件信息（不解码图像数据）
  */
@@ -111,15 +467,171 @@ export async function decodeBLPMipmap(blpData: Uint8Array, level: number): Promi
 }
 
 /**
- * 解析 MDX 文件
+ * 一次性解码 BLP 的所有 mipmap 层级（从大到小，跳过没有数据的层级）
+ */
+export async function decodeBLPAllMipmaps(blpData: Uint8Array): Promise<BlpImageData[]> {
+  return invoke<BlpImageData[]>('decode_blp_all_mipmaps', {
+    blpData: Array.from(blpData),
+  });
+}
+
+/**
+ * 把 RGBA 像素编码为 BLP：compression 1 JPEG、2 调色板（BLP1），3 DXT（BLP2）
+ */
+export async function encodeRGBAToBLP(
+  image: BlpImageData,
+  compression: number,
+  generateMipmaps = true,
+): Promise<Uint8Array> {
+  const bytes = await invoke<number[]>('encode_rgba_to_blp', {
+    width: image.width,
+    height: image.height,
+    rgba: image.data,
+    compression,
+    generateMipmaps,
+  });
+  return new Uint8Array(bytes);
+}
+
+/**
+ * 生成长边不超过 maxEdge 的 BLP 缩略图，只解码最接近目标尺寸的 mipmap
+ */
+export async function decodeBLPThumbnail(blpData: Uint8Array, maxEdge: number): Promise<BlpImageData> {
+  return invoke<BlpImageData>('decode_blp_thumbnail', {
+    blpData: Array.from(blpData),
+    maxEdge,
+  });
+}
+
+/**
+ * 并行批量解码 BLP，asThumbnail 指定时生成该长边的缩略图；单个文件失败不影响其他项
+ */
+export async function decodeBLPBatch(
+  items: Uint8Array[],
+  asThumbnail?: number,
+): Promise<({ Ok: BlpImageData } | { Err: AppError })[]> {
+  return invoke<({ Ok: BlpImageData } | { Err: AppError })[]>('decode_blp_batch', {
+    items: items.map((item) => Array.from(item)),
+    asThumbnail: asThumbnail ?? null,
+  });
+}
+
+/**
+ * 读取调色板 BLP 的 256 色调色板，每项为 [r, g, b, a]
+ */
+export async function getBLPPalette(blpData: Uint8Array): Promise<[number, number, number, number][]> {
+  return invoke<[number, number, number, number][]>('get_blp_palette', {
+    blpData: Array.from(blpData),
+  });
+}
+
+/**
+ * 把 JPEG BLP 的某一级 mipmap 还原为完整的 JPEG 文件数据（通道顺序为 BGRA）
  */
-export async function parseMDX(mdxData: Uint8Array): Promise<MdxModel> {
+export async function extractBLPJpeg(blpData: Uint8Array, mipmapLevel = 0): Promise<Uint8Array> {
+  const bytes = await invoke<number[]>('extract_blp_jpeg', {
+    blpData: Array.from(blpData),
+    mipmapLevel,
+  });
+  return new Uint8Array(bytes);
+}
+
+/**
+ * 解析 MDX 文件，computeMissingNormals 为 true 时为缺少法线的 geoset 生成平滑法线
+ */
+export async function parseMDX(mdxData: Uint8Array, computeMissingNormals = false): Promise<MdxModel> {
   const jsonStr = await invoke<string>('parse_mdx_file', {
     mdxData: Array.from(mdxData),
+    computeMissingNormals,
+  });
+  return JSON.parse(jsonStr);
+}
+
+/**
+ * MDX 解析进度（每读完一个 chunk 发送一次 mdx-parse-progress 事件）
+ */
+export interface MdxParseProgress {
+  chunk: string;
+  bytes_done: number;
+  bytes_total: number;
+}
+
+/**
+ * 监听 parseMDX 的解析进度，返回取消监听的函数
+ */
+export function onMdxParseProgress(handler: (progress: MdxParseProgress) => void): Promise<UnlistenFn> {
+  return listen<MdxParseProgress>('mdx-parse-progress', (event) => handler(event.payload));
+}
+
+/**
+ * 解析模型文件：以 MDLX 开头按 MDX 二进制解析，否则（或 isText 为 true）按 MDL 文本解析
+ */
+export async function parseModelFile(data: Uint8Array, isText = false): Promise<MdxModel> {
+  const jsonStr = await invoke<string>('parse_model_file', {
+    data: Array.from(data),
+    isText,
   });
   return JSON.parse(jsonStr);
 }
 
+/**
+ * MDX 模型概要
+ */
+export interface MdxSummary {
+  name: string;
+  geoset_count: number;
+  vertex_count: number;
+  triangle_count: number;
+  bone_count: number;
+  texture_count: number;
+  sequence_count: number;
+}
+
+/**
+ * 获取模型概要（不传输几何数据）
+ */
+export async function getMdxSummary(mdxData: Uint8Array): Promise<MdxSummary> {
+  return invoke<MdxSummary>('get_mdx_summary', {
+    mdxData: Array.from(mdxData),
+  });
+}
+
+/**
+ * 动画时间轴中的序列（帧数即毫秒数）
+ */
+export interface MdxTimelineSequence {
+  name: string;
+  interval: [number, number];
+  duration_ms: number;
+  non_looping: boolean;
+}
+
+/**
+ * MDX 动画时间轴
+ */
+export interface MdxTimeline {
+  sequences: MdxTimelineSequence[];
+  global_sequences: number[]; // 毫秒
+}
+
+/**
+ * 获取动画时间轴（序列区间与全局序列时长）
+ */
+export async function getMdxTimeline(mdxData: Uint8Array): Promise<MdxTimeline> {
+  return invoke<MdxTimeline>('get_mdx_timeline', {
+    mdxData: Array.from(mdxData),
+  });
+}
+
+/**
+ * 只读取 MDX 的动画序列（不解析几何数据），用于动画选择列表
+ */
+export async function getMdxSequences(mdxData: Uint8Array): Promise<MdxSequence[]> {
+  return invoke<MdxSequence[]>('get_mdx_sequences', {
+    mdxData: Array.from(mdxData),
+  });
+}
+
 /**
  * 从 MPQ 档案中解析 MDX 文件
  */
@@ -131,6 +643,23 @@ export async function parseMDXFromMPQ(archivePath: string, fileName: string): Pr
   return JSON.parse(jsonStr);
 }
 
+/**
+ * 从 MPQ 档案中解析 FDF 界面定义文件
+ */
+export async function parseFDFFromMPQ(archivePath: string, fileName: string): Promise<FdfFrameDef[]> {
+  return invoke<FdfFrameDef[]>('parse_fdf_from_mpq', {
+    archivePath,
+    fileName,
+  });
+}
+
+/**
+ * 把框体定义树写回 FDF 文本
+ */
+export async function saveFDF(frames: FdfFrameDef[]): Promise<string> {
+  return invoke<string>('save_fdf', { frames });
+}
+
 /**
  * 从本地文件系统解析 MDX 文件
  */
@@ -141,6 +670,41 @@ export async function parseMDXFromFile(filePath: string): Promise<MdxModel> {
   return JSON.parse(jsonStr);
 }
 
+/**
+ * 把可替换纹理 id 解析为指定队伍颜色下的标准 BLP 路径，未知 id 返回 null
+ */
+export async function resolveReplaceableTexture(id: number, teamColor: number): Promise<string | null> {
+  return invoke<string | null>('resolve_replaceable_texture', { id, teamColor });
+}
+
+/**
+ * 取得头像镜头（名称含 "Portrait" 者优先，否则为第一个镜头），没有镜头时为 null
+ */
+export async function getMDXPortraitCamera(mdxData: Uint8Array): Promise<MdxCamera | null> {
+  return invoke<MdxCamera | null>('get_mdx_portrait_camera', {
+    mdxData: Array.from(mdxData),
+  });
+}
+
+/**
+ * 把（编辑后的）模型写回二进制 MDX
+ */
+export async function serializeMDX(model: MdxModel): Promise<Uint8Array> {
+  const data = await invoke<number[]>('serialize_mdx', {
+    modelJson: JSON.stringify(model),
+  });
+  return new Uint8Array(data);
+}
+
+/**
+ * 导出 MDX 为 MDL 文本（灯光、粒子发射器与镜头暂不导出）
+ */
+export async function exportMDXToMDL(mdxData: Uint8Array): Promise<string> {
+  return invoke<string>('export_mdx_to_mdl', {
+    mdxData: Array.from(mdxData),
+  });
+}
+
 /**
  * 将 BlpImageData 转换为 ImageData（用于 Canvas）
  */
@@ -12,11 +12,13 @@ import { invoke } from '@tauri-apps/api/core';
 interface MdxModel {
   version: number;
   name: string;
-  vertices: Array<{ x: number; y: number; z: number }>;
-  normals: Array<{ x: number; y: number; z: number }>;
-  uvs: Array<{ u: number; v: number }>;
-  faces: Array<{ indices: [number, number, number] }>;
-  bounds: {
+  geosets: Array<{
+    vertices: Array<{ x: number; y: number; z: number }>;
+    normals: Array<{ x: number; y: number; z: number }>;
+    uvs: Array<Array<{ u: number; v: number }>>;
+    faces: Array<{ indices: [number, number, number] }>;
+  }>;
+  overall_bounds: {
     min: { x: number; y: number; z: number };
     max: { x: number; y: number; z: number };
   };
@@ -64,21 +66,25 @@ export async function testMdxParsing() {
       console.log(`✅ 解析成功 (${(endTime - startTime).toFixed(2)}ms)`);
       console.log(`  - 版本: ${model.version}`);
       console.log(`  - 名称: ${model.name}`);
-      console.log(`  - 顶点数: ${model.vertices.length}`);
-      console.log(`  - 法线数: ${model.normals.length}`);
-      console.log(`  - UV数: ${model.uvs.length}`);
-      console.log(`  - 面数: ${model.faces.length}`);
-      console.log(`  - 边界框: min(${model.bounds.min.x.toFixed(2)}, ${model.bounds.min.y.toFixed(2)}, ${model.bounds.min.z.toFixed(2)}) max(${model.bounds.max.x.toFixed(2)}, ${model.bounds.max.y.toFixed(2)}, ${model.bounds.max.z.toFixed(2)})`);
+      const count = (pick: (g: MdxModel['geosets'][number]) => unknown[]) =>
+        model.geosets.reduce((sum, g) => sum + pick(g).length, 0);
+      console.log(`  - Geoset数: ${model.geosets.length}`);
+      console.log(`  - 顶点数: ${count((g) => g.vertices)}`);
+      console.log(`  - 法线数: ${count((g) => g.normals)}`);
+      console.log(`  - UV数: ${count((g) => g.uvs[0] ?? [])}`);
+      console.log(`  - 面数: ${count((g) => g.faces)}`);
+      console.log(`  - 边界框: min(${model.overall_bounds.min.x.toFixed(2)}, ${model.overall_bounds.min.y.toFixed(2)}, ${model.overall_bounds.min.z.toFixed(2)}) max(${model.overall_bounds.max.x.toFixed(2)}, ${model.overall_bounds.max.y.toFixed(2)}, ${model.overall_bounds.max.z.toFixed(2)})`);
       
       // 采样第一个顶点
-      if (model.vertices.length > 0) {
-        const v = model.vertices[0];
+      const first = model.geosets[0];
+      if (first && first.vertices.length > 0) {
+        const v = first.vertices[0];
         console.log(`  - 第一个顶点: (${v.x.toFixed(2)}, ${v.y.toFixed(2)}, ${v.z.toFixed(2)})`);
       }
       
       // 采样第一个面
-      if (model.faces.length > 0) {
-        const f = model.faces[0];
+      if (first && first.faces.length > 0) {
+        const f = first.faces[0];
         console.log(`  - 第一个面: [${f.indices[0]}, ${f.indices[1]}, ${f.indices[2]}]`);
       }
       
@@ -100,6 +106,7 @@ export async function testMdxBinaryParsing(mdxData: Uint8Array) {
     
     const modelJson = await invoke<string>('parse_mdx_file', {
       mdxData: Array.from(mdxData),
+      computeMissingNormals: false,
     });
     
     const endTime = performance.now();
@@ -1,8 +1,9 @@
 // War3 进程管理器 - 管理War3进程和热重载监听
 
-import { isProcessRunning, killProcess, killProcessElevated, isWar3Running, killWar3Processes } from './kkweDetector';
+import { isProcessRunning, killProcess, killProcessElevated, isWar3Running, killWar3Processes, watchProcess } from './kkweDetector';
 import { getHotReloadExporter } from './hotReloadExporter';
 import { useProjectStore } from '../store/projectStore';
+import { errorMessage, isAppError } from './rustBridge';
 
 class War3ProcessManager {
   private currentPid: number | null = null;
@@ -39,6 +40,15 @@ class War3ProcessManager {
     localStorage.setItem('war3_process_pid', pid.toString());
     
     console.log('[War3进程] 已设置War3.exe进程: PID=', pid);
+
+    // 进程退出时清除记录，无需前端轮询
+    watchProcess(pid, () => {
+      console.log('[War3进程] War3.exe已退出: PID=', pid);
+      if (this.currentPid === pid) {
+        this.currentPid = null;
+        localStorage.removeItem('war3_process_pid');
+      }
+    }).catch((error) => console.warn('[War3进程] 无法监视进程退出:', error));
     
     // 等待一下再验证进程是否存在
     setTimeout(async () => {
@@ -90,7 +100,7 @@ class War3ProcessManager {
       console.log('[War3进程] 已清理进程记录: PID=', oldPid);
       
     } catch (error) {
-      const errorMsg = String(error);
+      const errorMsg = errorMessage(error);
       console.error('[War3进程] ❌ 普通权限结束进程失败: PID=', this.currentPid, '错误=', error);
       
       // 检查是否是权限错误
@@ -110,11 +120,10 @@ class War3ProcessManager {
           console.log('[War3进程] 已清理进程记录: PID=', pidToKill);
           
         } catch (elevatedError) {
-          const elevatedMsg = String(elevatedError);
           console.error('[War3进程] ❌ 管理员权限结束进程失败:', elevatedError);
           
           // 用户取消了UAC
-          if (elevatedMsg.includes('canceled') || elevatedMsg.includes('取消')) {
+          if (isAppError(elevatedError) && elevatedError.kind === 'UserCancelled') {
             this.currentPid = null;
             localStorage.removeItem('war3_process_pid');
             throw new Error('用户取消了权限提升，请手动关闭War3.exe后重试。');